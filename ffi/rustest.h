@@ -0,0 +1,105 @@
+/*
+ * Copyright 2025 Google LLC
+ * SPDX-License-Identifier: MIT
+ */
+
+/*
+ * The C API of librustest_ffi.so, built with "cargo build -p rustest-ffi --release".
+ *
+ * Functions return 0 or a non-negative count on success, and a negative errno on failure.
+ */
+
+#ifndef RUSTEST_H
+#define RUSTEST_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+struct rustest_mmap;
+
+/* /proc/meminfo fields, in KB */
+struct rustest_meminfo {
+   uint64_t mem_total;
+   uint64_t mem_free;
+   uint64_t mem_available;
+   uint64_t buffers;
+   uint64_t cached;
+   uint64_t swap_cached;
+   uint64_t active;
+   uint64_t inactive;
+   uint64_t swap_total;
+   uint64_t swap_free;
+   uint64_t zswap;
+   uint64_t zswapped;
+   uint64_t dirty;
+   uint64_t writeback;
+   uint64_t anon_pages;
+   uint64_t mapped;
+   uint64_t shmem;
+   uint64_t slab;
+   uint64_t s_reclaimable;
+   uint64_t k_reclaimable;
+   uint64_t mlocked;
+   uint64_t committed_as;
+};
+
+/* a private anonymous mapping; flags are in addition to MAP_PRIVATE | MAP_ANONYMOUS */
+int
+rustest_mmap_anonymous(size_t len, int flags, struct rustest_mmap **out);
+
+/* a shared mapping of a new memfd */
+int
+rustest_mmap_memfd(const char *name, size_t len, struct rustest_mmap **out);
+
+/* a shared mapping of a file, which is created or truncated */
+int
+rustest_mmap_file(const char *path, size_t len, struct rustest_mmap **out);
+
+void
+rustest_mmap_destroy(struct rustest_mmap *mmap);
+
+void *
+rustest_mmap_addr(const struct rustest_mmap *mmap);
+
+size_t
+rustest_mmap_len(const struct rustest_mmap *mmap);
+
+int
+rustest_mmap_lock(const struct rustest_mmap *mmap);
+
+int
+rustest_mmap_unlock(const struct rustest_mmap *mmap);
+
+/* advice is a MADV_* value; offset must be page-aligned */
+int
+rustest_mmap_advise(struct rustest_mmap *mmap, size_t offset, size_t len, int advice);
+
+/* faults in every page by reading it */
+int
+rustest_mmap_populate(const struct rustest_mmap *mmap);
+
+/* fill is zero, byte, text, random[:SEED], or mixed:PCT, as in the --fill options */
+int
+rustest_mmap_fill(struct rustest_mmap *mmap, const char *fill, uint8_t val);
+
+/* returns the number of pages that do not hold what rustest_mmap_fill wrote */
+ssize_t
+rustest_mmap_verify(const struct rustest_mmap *mmap, const char *fill, uint8_t val);
+
+/* returns the number of resident pages */
+ssize_t
+rustest_mmap_resident(const struct rustest_mmap *mmap);
+
+int
+rustest_meminfo_snapshot(struct rustest_meminfo *info);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUSTEST_H */
@@ -0,0 +1,305 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+//! A C API for the mappings and collectors of rustest, for C test harnesses.  See rustest.h.
+//!
+//! Functions return 0 or a non-negative count on success, and a negative errno on failure.
+
+use std::{
+    ffi::{CStr, c_char, c_int, c_void},
+    io, ptr,
+};
+
+// an opaque mapping owned by the caller
+pub struct RustestMmap(rustest::Mmap);
+
+// struct rustest_meminfo, in KB
+#[repr(C)]
+pub struct RustestMeminfo {
+    pub mem_total: u64,
+    pub mem_free: u64,
+    pub mem_available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    pub swap_cached: u64,
+    pub active: u64,
+    pub inactive: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    pub zswap: u64,
+    pub zswapped: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    pub anon_pages: u64,
+    pub mapped: u64,
+    pub shmem: u64,
+    pub slab: u64,
+    pub s_reclaimable: u64,
+    pub k_reclaimable: u64,
+    pub mlocked: u64,
+    pub committed_as: u64,
+}
+
+fn errno(err: io::Error) -> c_int {
+    -err.raw_os_error().unwrap_or(libc::EIO)
+}
+
+// stores a new mapping to out
+fn create(mmap: Result<rustest::Mmap, io::Error>, out: *mut *mut RustestMmap) -> c_int {
+    if out.is_null() {
+        return -libc::EINVAL;
+    }
+
+    let mmap = match mmap {
+        Ok(mmap) => Box::into_raw(Box::new(RustestMmap(mmap))),
+        Err(err) => return errno(err),
+    };
+    // SAFETY: out is non-null and the caller guarantees that it is valid
+    unsafe { *out = mmap };
+
+    0
+}
+
+// returns the string, or None when it is null or not UTF-8
+fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+
+    // SAFETY: s is non-null and the caller guarantees that it is a valid C string
+    let s = unsafe { CStr::from_ptr(s) };
+    s.to_str().ok()
+}
+
+fn to_fill(fill: *const c_char) -> Option<rustest::Fill> {
+    to_str(fill).and_then(rustest::Fill::parse)
+}
+
+/// # Safety
+///
+/// out must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_anonymous(
+    len: usize,
+    flags: c_int,
+    out: *mut *mut RustestMmap,
+) -> c_int {
+    create(rustest::Mmap::anonymous_with_flags(len, flags), out)
+}
+
+/// # Safety
+///
+/// name must be a valid C string and out must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_memfd(
+    name: *const c_char,
+    len: usize,
+    out: *mut *mut RustestMmap,
+) -> c_int {
+    let Some(name) = to_str(name) else {
+        return -libc::EINVAL;
+    };
+
+    create(rustest::Mmap::memfd(name, len), out)
+}
+
+/// # Safety
+///
+/// path must be a valid C string and out must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_file(
+    path: *const c_char,
+    len: usize,
+    out: *mut *mut RustestMmap,
+) -> c_int {
+    let Some(path) = to_str(path) else {
+        return -libc::EINVAL;
+    };
+
+    create(rustest::Mmap::create(path, len), out)
+}
+
+/// # Safety
+///
+/// mmap must be null or from one of the create functions, and is invalid afterward.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_destroy(mmap: *mut RustestMmap) {
+    if mmap.is_null() {
+        return;
+    }
+
+    // SAFETY: mmap is from Box::into_raw and the caller gives up its ownership
+    drop(unsafe { Box::from_raw(mmap) });
+}
+
+/// # Safety
+///
+/// mmap must be from one of the create functions.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_addr(mmap: *const RustestMmap) -> *mut c_void {
+    // SAFETY: the caller guarantees that mmap is valid
+    let mmap = unsafe { &(*mmap).0 };
+    mmap.as_bytes().as_ptr() as *mut _
+}
+
+/// # Safety
+///
+/// mmap must be from one of the create functions.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_len(mmap: *const RustestMmap) -> usize {
+    // SAFETY: the caller guarantees that mmap is valid
+    let mmap = unsafe { &(*mmap).0 };
+    mmap.as_bytes().len()
+}
+
+/// # Safety
+///
+/// mmap must be from one of the create functions.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_lock(mmap: *const RustestMmap) -> c_int {
+    // SAFETY: the caller guarantees that mmap is valid
+    let mmap = unsafe { &(*mmap).0 };
+    mmap.mlock().map_or_else(errno, |_| 0)
+}
+
+/// # Safety
+///
+/// mmap must be from one of the create functions.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_unlock(mmap: *const RustestMmap) -> c_int {
+    // SAFETY: the caller guarantees that mmap is valid
+    let mmap = unsafe { &(*mmap).0 };
+    mmap.munlock();
+
+    0
+}
+
+/// # Safety
+///
+/// mmap must be from one of the create functions.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_advise(
+    mmap: *mut RustestMmap,
+    offset: usize,
+    len: usize,
+    advice: c_int,
+) -> c_int {
+    // SAFETY: the caller guarantees that mmap is valid
+    let mmap = unsafe { &mut (*mmap).0 };
+
+    // madvise_range panics on ranges outside the mapping
+    let end = offset.saturating_add(len);
+    if end > mmap.as_bytes().len() {
+        return -libc::EINVAL;
+    }
+
+    mmap.madvise_range(offset..end, advice)
+        .map_or_else(errno, |_| 0)
+}
+
+/// # Safety
+///
+/// mmap must be from one of the create functions.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_populate(mmap: *const RustestMmap) -> c_int {
+    // SAFETY: the caller guarantees that mmap is valid
+    let mmap = unsafe { &(*mmap).0 };
+    mmap.populate();
+
+    0
+}
+
+/// # Safety
+///
+/// mmap must be from one of the create functions and fill must be a valid C string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_fill(
+    mmap: *mut RustestMmap,
+    fill: *const c_char,
+    val: u8,
+) -> c_int {
+    let Some(fill) = to_fill(fill) else {
+        return -libc::EINVAL;
+    };
+    // SAFETY: the caller guarantees that mmap is valid
+    let mmap = unsafe { &mut (*mmap).0 };
+    mmap.fill_with(fill, val);
+
+    0
+}
+
+/// # Safety
+///
+/// mmap must be from one of the create functions and fill must be a valid C string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_verify(
+    mmap: *const RustestMmap,
+    fill: *const c_char,
+    val: u8,
+) -> isize {
+    let Some(fill) = to_fill(fill) else {
+        return -libc::EINVAL as isize;
+    };
+    // SAFETY: the caller guarantees that mmap is valid
+    let mmap = unsafe { &(*mmap).0 };
+
+    mmap.verify_with(fill, val) as isize
+}
+
+/// # Safety
+///
+/// mmap must be from one of the create functions.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_mmap_resident(mmap: *const RustestMmap) -> isize {
+    // SAFETY: the caller guarantees that mmap is valid
+    let mmap = unsafe { &(*mmap).0 };
+
+    match mmap.resident() {
+        Ok(count) => count as isize,
+        Err(err) => errno(err) as isize,
+    }
+}
+
+/// # Safety
+///
+/// info must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustest_meminfo_snapshot(info: *mut RustestMeminfo) -> c_int {
+    if info.is_null() {
+        return -libc::EINVAL;
+    }
+
+    let snapshot = match rustest::Meminfo::collect() {
+        Ok(snapshot) => snapshot,
+        Err(err) => return errno(err),
+    };
+    let snapshot = RustestMeminfo {
+        mem_total: snapshot.mem_total,
+        mem_free: snapshot.mem_free,
+        mem_available: snapshot.mem_available,
+        buffers: snapshot.buffers,
+        cached: snapshot.cached,
+        swap_cached: snapshot.swap_cached,
+        active: snapshot.active,
+        inactive: snapshot.inactive,
+        swap_total: snapshot.swap_total,
+        swap_free: snapshot.swap_free,
+        zswap: snapshot.zswap,
+        zswapped: snapshot.zswapped,
+        dirty: snapshot.dirty,
+        writeback: snapshot.writeback,
+        anon_pages: snapshot.anon_pages,
+        mapped: snapshot.mapped,
+        shmem: snapshot.shmem,
+        slab: snapshot.slab,
+        s_reclaimable: snapshot.s_reclaimable,
+        k_reclaimable: snapshot.k_reclaimable,
+        mlocked: snapshot.mlocked,
+        committed_as: snapshot.committed_as,
+    };
+
+    // SAFETY: info is non-null and the caller guarantees that it is valid
+    unsafe { ptr::write(info, snapshot) };
+
+    0
+}
@@ -0,0 +1,121 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+// async variants of Term::poll and the fd-based collectors, for binaries built on tokio
+//
+// They let a binary await key events, psi triggers, and cgroup events in a tokio::select!
+// together with tokio::time::interval for sampling and tokio::net::UnixListener for control
+// sockets.
+
+use crate::{CgroupEvent, CgroupWatcher, PsiTrigger, Term};
+use crossterm::event;
+use std::{fs, future, io, os::fd::RawFd, time};
+use tokio::io::{Interest, unix::AsyncFd};
+
+// kernfs and psi notify with POLLPRI, and with POLLERR when the cgroup is removed
+const NOTIFY_INTEREST: Interest = Interest::PRIORITY.add(Interest::ERROR);
+
+impl Term {
+    // waits for a key without blocking the runtime
+    //
+    // In plain mode, it waits for the next frame and never returns keys.  Otherwise, it
+    // returns None for events other than keys.
+    pub async fn poll_async(&mut self) -> Result<Option<event::KeyEvent>, io::Error> {
+        if self.plain.is_some() {
+            tokio::time::sleep(self.until_next_frame()).await;
+            return Ok(None);
+        }
+
+        // crossterm reads from stdin or /dev/tty, which are the same terminal
+        let tty = AsyncFd::with_interest(fs::File::open("/dev/tty")?, Interest::READABLE)?;
+        loop {
+            // crossterm may have buffered events already
+            if event::poll(time::Duration::ZERO)? {
+                return match event::read()? {
+                    event::Event::Key(key) => Ok(Some(key)),
+                    _ => Ok(None),
+                };
+            }
+
+            tty.readable().await?.clear_ready();
+        }
+    }
+}
+
+// a PsiTrigger that can be awaited
+pub struct AsyncPsiTrigger {
+    fd: AsyncFd<PsiTrigger>,
+}
+
+impl AsyncPsiTrigger {
+    // must be called from a tokio runtime
+    pub fn new(trigger: PsiTrigger) -> Result<Self, io::Error> {
+        Ok(AsyncPsiTrigger {
+            fd: AsyncFd::with_interest(trigger, NOTIFY_INTEREST)?,
+        })
+    }
+
+    pub fn spec(&self) -> &str {
+        &self.fd.get_ref().spec
+    }
+
+    // waits for the trigger to fire; fails when the cgroup of the pressure file is removed
+    pub async fn wait(&self) -> Result<(), io::Error> {
+        let mut guard = self.fd.ready(NOTIFY_INTEREST).await?;
+        let ready = guard.ready();
+        guard.clear_ready();
+
+        if ready.is_error() {
+            return Err(io::Error::other(format!("{} is gone", self.spec())));
+        }
+
+        Ok(())
+    }
+}
+
+// a CgroupWatcher that can be awaited
+pub struct AsyncCgroupWatcher {
+    // declared first to be deregistered before watcher closes the fds
+    events: AsyncFd<RawFd>,
+    pressure: Option<AsyncFd<RawFd>>,
+    watcher: CgroupWatcher,
+}
+
+impl AsyncCgroupWatcher {
+    // must be called from a tokio runtime
+    pub fn new(watcher: CgroupWatcher) -> Result<Self, io::Error> {
+        let (events, pressure) = watcher.fds();
+        let events = AsyncFd::with_interest(events, NOTIFY_INTEREST)?;
+        let pressure = pressure
+            .map(|fd| AsyncFd::with_interest(fd, NOTIFY_INTEREST))
+            .transpose()?;
+
+        Ok(AsyncCgroupWatcher {
+            events,
+            pressure,
+            watcher,
+        })
+    }
+
+    // waits for memory.events to change or the trigger to fire, and returns the changes
+    pub async fn wait(&mut self) -> Result<Vec<CgroupEvent>, io::Error> {
+        loop {
+            let pressure = async {
+                match &self.pressure {
+                    Some(fd) => fd.ready(NOTIFY_INTEREST).await,
+                    None => future::pending().await,
+                }
+            };
+            tokio::select! {
+                guard = self.events.ready(NOTIFY_INTEREST) => guard?.clear_ready(),
+                guard = pressure => guard?.clear_ready(),
+            }
+
+            // a notification can be spurious, such as when a counter changes and changes back
+            let events = self.watcher.poll();
+            if !events.is_empty() {
+                return Ok(events);
+            }
+        }
+    }
+}
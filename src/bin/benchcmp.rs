@@ -0,0 +1,302 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crossterm::style::Stylize;
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+    process,
+};
+
+// metrics whose keys contain these are better when lower
+const LOWER_BETTER: [&str; 10] = [
+    "_ns", "_us", "_ms", "_seconds", "lat", "fault", "stall", "miss", "fail", "bad",
+];
+// metrics whose keys contain these are better when higher
+const HIGHER_BETTER: [&str; 6] = ["mbps", "gbps", "per_sec", "ops", "hit", "speedup"];
+
+// a record of a --json output, such as a "result", with its nested objects flattened
+struct Record {
+    kind: String,
+    // string fields and sizes, which identify the record
+    label: Vec<String>,
+    metrics: Vec<(String, f64)>,
+}
+
+impl Record {
+    fn parse(line: &rustest::JsonLine) -> Self {
+        let kind = line
+            .get("type")
+            .and_then(rustest::json_unquote)
+            .unwrap_or_default();
+        let mut rec = Record {
+            kind,
+            label: Vec::new(),
+            metrics: Vec::new(),
+        };
+        rec.flatten("", line);
+
+        rec
+    }
+
+    fn flatten(&mut self, prefix: &str, line: &rustest::JsonLine) {
+        for (key, val) in line.fields() {
+            if key == "type" || key == "time" {
+                continue;
+            }
+
+            let key = format!("{}{}", prefix, key);
+            if let Some(nested) = rustest::JsonLine::parse(val) {
+                self.flatten(&format!("{}_", key), &nested);
+            } else if let Some(val) = rustest::json_unquote(val) {
+                self.label.push(format!("{} {}", key, val));
+            } else if let Ok(num) = val.parse::<f64>() {
+                if key.ends_with("_mb") {
+                    self.label.push(format!("{} {}", key, val));
+                } else {
+                    self.metrics.push((key, num));
+                }
+            }
+        }
+    }
+}
+
+// returns the records of kinds in a --json output
+fn load(path: &str, kinds: &[String]) -> Result<Vec<Record>, io::Error> {
+    let text = fs::read_to_string(path)?;
+
+    let mut recs = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let line = rustest::JsonLine::parse(line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: invalid JSON line", path, idx + 1),
+            )
+        })?;
+        let rec = Record::parse(&line);
+        if kinds.contains(&rec.kind) {
+            recs.push(rec);
+        }
+    }
+
+    Ok(recs)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Verdict {
+    Same,
+    Better,
+    Worse,
+    // the direction of the metric is unknown
+    Changed,
+}
+
+impl Verdict {
+    fn judge(key: &str, delta_pct: f64, threshold_pct: f64) -> Self {
+        if delta_pct.abs() < threshold_pct {
+            return Verdict::Same;
+        }
+
+        let key = key.to_ascii_lowercase();
+        let higher_better = if LOWER_BETTER.iter().any(|pat| key.contains(pat)) {
+            false
+        } else if HIGHER_BETTER.iter().any(|pat| key.contains(pat)) {
+            true
+        } else {
+            return Verdict::Changed;
+        };
+
+        if (delta_pct > 0.0) == higher_better {
+            Verdict::Better
+        } else {
+            Verdict::Worse
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Verdict::Same => "same",
+            Verdict::Better => "better",
+            Verdict::Worse => "worse",
+            Verdict::Changed => "changed",
+        }
+    }
+}
+
+fn print_help() {
+    println!("usage: benchcmp [options] BASE NEW");
+    println!("  BASE, NEW: files saved from the --json output of the tools");
+    println!("  --type TYPE: record type to compare, repeatable (default: result, summary)");
+    println!("  --threshold PCT: changes below PCT are ignored (default: 5)");
+    println!("  --all: also print the metrics that did not change");
+    println!("  --json: print the deltas as JSON lines");
+    println!("records are matched by their order within each type");
+    println!("exits with status 1 when a metric got worse by more than the threshold");
+}
+
+struct Args {
+    base: Option<String>,
+    new: Option<String>,
+    kinds: Vec<String>,
+    threshold_pct: f64,
+    all: bool,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        base: None,
+        new: None,
+        kinds: Vec::new(),
+        threshold_pct: 5.0,
+        all: false,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--type" => args.kinds.extend(iter.next()),
+            "--threshold" => {
+                let pct = iter.next().unwrap_or_default();
+                args.threshold_pct = pct.trim_end_matches('%').parse().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid threshold {}", pct),
+                    )
+                })?;
+            }
+            "--all" => args.all = true,
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ if args.base.is_none() => args.base = Some(arg),
+            _ if args.new.is_none() => args.new = Some(arg),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("extra argument {}", arg),
+                ));
+            }
+        }
+    }
+
+    if args.kinds.is_empty() {
+        args.kinds = vec![String::from("result"), String::from("summary")];
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<process::ExitCode, io::Error> {
+    let args = parse_args().inspect_err(|_| print_help())?;
+    let (Some(base_path), Some(new_path)) = (&args.base, &args.new) else {
+        print_help();
+        return Ok(process::ExitCode::FAILURE);
+    };
+
+    let base = load(base_path, &args.kinds)?;
+    let new = load(new_path, &args.kinds)?;
+    let color = !args.json && io::stdout().is_terminal();
+
+    let mut worse_count = 0;
+    let mut better_count = 0;
+    for kind in &args.kinds {
+        let base_recs = base.iter().filter(|rec| rec.kind == *kind);
+        let new_recs = new.iter().filter(|rec| rec.kind == *kind);
+        for (idx, (base_rec, new_rec)) in base_recs.zip(new_recs).enumerate() {
+            let label = base_rec.label.join(", ");
+            let mut header_printed = false;
+
+            for (key, base_val) in &base_rec.metrics {
+                let Some(new_val) = new_rec
+                    .metrics
+                    .iter()
+                    .find_map(|(k, val)| (k == key).then_some(*val))
+                else {
+                    continue;
+                };
+
+                let delta_pct = if *base_val != 0.0 {
+                    (new_val - base_val) / base_val.abs() * 100.0
+                } else if new_val != 0.0 {
+                    f64::INFINITY.copysign(new_val)
+                } else {
+                    0.0
+                };
+                let verdict = Verdict::judge(key, delta_pct, args.threshold_pct);
+                match verdict {
+                    Verdict::Worse => worse_count += 1,
+                    Verdict::Better => better_count += 1,
+                    _ => (),
+                }
+                if verdict == Verdict::Same && !args.all {
+                    continue;
+                }
+
+                if args.json {
+                    rustest::JsonLine::new("delta")
+                        .field("record", kind)
+                        .field("index", idx)
+                        .field("label", &label)
+                        .field("metric", key)
+                        .field("base", *base_val)
+                        .field("new", new_val)
+                        .field("delta_pct", delta_pct)
+                        .field("verdict", verdict.name())
+                        .emit();
+                    continue;
+                }
+
+                if !header_printed {
+                    println!("{} #{}: {}", kind, idx + 1, label);
+                    header_printed = true;
+                }
+
+                let row = format!(
+                    "  {:32} {:>14.2} {:>14.2} {:>+9.2}% {}",
+                    key,
+                    base_val,
+                    new_val,
+                    delta_pct,
+                    verdict.name()
+                );
+                match verdict {
+                    Verdict::Worse if color => println!("{}", row.red()),
+                    Verdict::Better if color => println!("{}", row.green()),
+                    _ => println!("{}", row),
+                }
+            }
+        }
+
+        let [base_count, new_count] =
+            [&base, &new].map(|recs| recs.iter().filter(|rec| rec.kind == *kind).count());
+        if base_count != new_count && !args.json {
+            println!(
+                "{}: {} records in {} but {} in {}; the extra ones are skipped",
+                kind, base_count, base_path, new_count, new_path
+            );
+        }
+    }
+
+    if args.json {
+        rustest::JsonLine::new("summary")
+            .field("worse", worse_count)
+            .field("better", better_count)
+            .emit();
+    } else {
+        println!(
+            "{} worse, {} better beyond {}%",
+            worse_count, better_count, args.threshold_pct
+        );
+    }
+
+    Ok(if worse_count > 0 {
+        process::ExitCode::FAILURE
+    } else {
+        process::ExitCode::SUCCESS
+    })
+}
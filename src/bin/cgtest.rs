@@ -0,0 +1,323 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    mem, ptr, thread, time,
+};
+
+const CHUNK_SIZE_MB: usize = 16;
+const POLL_INTERVAL: time::Duration = time::Duration::from_millis(500);
+// how long the child holds its memory after allocating it
+const HOLD_TIME: time::Duration = time::Duration::from_secs(2);
+
+// memory.events keys, in the order their thresholds are crossed
+const EVENTS: [&str; 5] = ["low", "high", "max", "oom", "oom_kill"];
+
+// a forked child that grows to size_mb once it is in the cgroup, holds the memory for a moment,
+// and exits
+struct Child {
+    pid: libc::pid_t,
+    status: Option<i32>,
+}
+
+impl Child {
+    fn spawn(cgroup: &rustest::Cgroup, size_mb: usize, rate: f64) -> Result<Self, io::Error> {
+        let (go_rd, go_wr) = rustest::pipe()?;
+
+        // SAFETY: we are single-threaded and the child never returns
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            return Err(io::Error::last_os_error());
+        } else if pid == 0 {
+            drop(go_wr);
+            Self::serve(go_rd, size_mb, rate);
+        }
+
+        // dropping the child on errors kills it
+        let child = Child { pid, status: None };
+        cgroup.add_pid(pid as u32)?;
+        (&go_wr).write_all(b"g")?;
+
+        Ok(child)
+    }
+
+    fn serve(mut go: fs::File, size_mb: usize, rate: f64) -> ! {
+        let mut buf = [0u8; 1];
+        let _ = go.read(&mut buf);
+
+        let interval = time::Duration::from_secs_f64(CHUNK_SIZE_MB as f64 / rate);
+        let mut allocated = 0;
+        while allocated < size_mb {
+            let Ok(mut mmap) = rustest::Mmap::anonymous(CHUNK_SIZE_MB * 1024 * 1024) else {
+                break;
+            };
+            mmap.fill_with(rustest::Fill::Random(allocated as u64), 1);
+            mem::forget(mmap);
+            allocated += CHUNK_SIZE_MB;
+
+            thread::sleep(interval);
+        }
+
+        thread::sleep(HOLD_TIME);
+
+        // SAFETY: valid _exit call
+        unsafe { libc::_exit(0) };
+    }
+
+    fn rss_kb(&self) -> u64 {
+        fs::read_to_string(format!("/proc/{}/status", self.pid))
+            .ok()
+            .and_then(|status| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix("VmRSS:"))
+                    .and_then(|val| val.split_ascii_whitespace().next())
+                    .and_then(|val| val.parse().ok())
+            })
+            .unwrap_or_default()
+    }
+
+    fn try_wait(&mut self) -> bool {
+        if self.status.is_some() {
+            return false;
+        }
+
+        let mut status = 0;
+        // SAFETY: valid waitpid call
+        let ret = unsafe { libc::waitpid(self.pid, &mut status, libc::WNOHANG) };
+        if ret == self.pid {
+            self.status = Some(status);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if self.status.is_some() {
+            return;
+        }
+
+        // SAFETY: valid kill call
+        unsafe { libc::kill(self.pid, libc::SIGKILL) };
+        // SAFETY: valid waitpid call
+        unsafe { libc::waitpid(self.pid, ptr::null_mut(), 0) };
+    }
+}
+
+// counters of the cgroup at one point in time
+struct Sample {
+    current: u64,
+    swap_current: u64,
+    events: [u64; EVENTS.len()],
+    pgscan: u64,
+    pgsteal: u64,
+}
+
+impl Sample {
+    fn collect(cgroup: &rustest::Cgroup) -> Self {
+        Sample {
+            current: cgroup.read_value("memory.current").unwrap_or_default(),
+            swap_current: cgroup.read_value("memory.swap.current").unwrap_or_default(),
+            events: EVENTS.map(|key| cgroup.read_keyed("memory.events", key).unwrap_or_default()),
+            pgscan: cgroup
+                .read_keyed("memory.stat", "pgscan")
+                .unwrap_or_default(),
+            pgsteal: cgroup
+                .read_keyed("memory.stat", "pgsteal")
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn print_help() {
+    println!("usage: cgtest [options]");
+    println!("  --cgroup NAME: cgroup to create (default: rustest-cgtest)");
+    println!("  --max MB: memory.max (default: 256)");
+    println!("  --high MB: memory.high (default: none)");
+    println!("  --swap-max MB: memory.swap.max (default: unchanged)");
+    println!("  --size MB: how much the child allocates (default: twice memory.max)");
+    println!("  --rate MB/s: how fast the child allocates (default: 64)");
+    println!("  --json: print samples and the summary as JSON lines");
+}
+
+struct Args {
+    cgroup: String,
+    max_mb: u64,
+    high_mb: Option<u64>,
+    swap_max_mb: Option<u64>,
+    size_mb: Option<usize>,
+    rate: f64,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        cgroup: String::from("rustest-cgtest"),
+        max_mb: 256,
+        high_mb: None,
+        swap_max_mb: None,
+        size_mb: None,
+        rate: 64.0,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--cgroup" => args.cgroup = iter.next().unwrap_or(args.cgroup),
+            "--max" => args.max_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--high" => args.high_mb = Some(rustest::parse_mb(&iter.next().unwrap_or_default())?),
+            "--swap-max" => {
+                args.swap_max_mb = Some(rustest::parse_mb(&iter.next().unwrap_or_default())?)
+            }
+            "--size" => args.size_mb = Some(rustest::parse_mb(&iter.next().unwrap_or_default())?),
+            "--rate" => {
+                let rate = iter.next().unwrap_or_default();
+                args.rate = rate
+                    .parse()
+                    .ok()
+                    .filter(|&rate| rate > 0.0)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("invalid rate {}", rate),
+                        )
+                    })?;
+            }
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let cgroup = rustest::Cgroup::create(&args.cgroup)?;
+    cgroup.write_value("memory.max", args.max_mb * 1024 * 1024)?;
+    if let Some(high_mb) = args.high_mb {
+        cgroup.write_value("memory.high", high_mb * 1024 * 1024)?;
+    }
+    if let Some(swap_max_mb) = args.swap_max_mb {
+        cgroup.write_value("memory.swap.max", swap_max_mb * 1024 * 1024)?;
+    }
+
+    let size_mb = args.size_mb.unwrap_or(args.max_mb as usize * 2);
+    if args.json {
+        rustest::JsonLine::new("config")
+            .field("cgroup", cgroup.name())
+            .field("max_mb", args.max_mb)
+            .field("high_mb", args.high_mb)
+            .field("size_mb", size_mb)
+            .field("rate", args.rate)
+            .emit();
+    } else {
+        println!(
+            "{}: memory.max {} MB, memory.high {}, child allocating {} MB at {} MB/s",
+            cgroup.name(),
+            args.max_mb,
+            args.high_mb
+                .map_or("max".to_string(), |mb| format!("{} MB", mb)),
+            size_mb,
+            args.rate
+        );
+        println!();
+    }
+
+    let mut prev = Sample::collect(&cgroup);
+    let mut child = Child::spawn(&cgroup, size_mb, args.rate)?;
+    let start = time::Instant::now();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let exited = child.try_wait();
+        let cur = Sample::collect(&cgroup);
+        let [current, swap_current] = [cur.current, cur.swap_current].map(|b| b / 1024 / 1024);
+
+        if args.json {
+            let mut line = rustest::JsonLine::new("sample")
+                .field("elapsed_ns", start.elapsed())
+                .field("child_rss_kb", child.rss_kb())
+                .field("current_mb", current)
+                .field("swap_mb", swap_current)
+                .field("pgscan", cur.pgscan - prev.pgscan)
+                .field("pgsteal", cur.pgsteal - prev.pgsteal);
+            for (key, val) in EVENTS.iter().zip(cur.events) {
+                line.push(key, val);
+            }
+            line.emit();
+        } else {
+            println!(
+                "{:6.1}s: child rss {:5} MB, current {:5} MB, swap {:5} MB, pgscan +{}, pgsteal +{}",
+                start.elapsed().as_secs_f64(),
+                child.rss_kb() / 1024,
+                current,
+                swap_current,
+                cur.pgscan - prev.pgscan,
+                cur.pgsteal - prev.pgsteal
+            );
+
+            for (idx, key) in EVENTS.iter().enumerate() {
+                if cur.events[idx] > prev.events[idx] {
+                    println!(
+                        "         memory.events {} +{} (total {})",
+                        key,
+                        cur.events[idx] - prev.events[idx],
+                        cur.events[idx]
+                    );
+                }
+            }
+        }
+
+        prev = cur;
+
+        if exited {
+            break;
+        }
+    }
+
+    let status = child.status.unwrap_or_default();
+    // the cgroup can only be removed once the child is gone
+    drop(child);
+
+    if args.json {
+        let mut line = rustest::JsonLine::new("summary")
+            .field("size_mb", size_mb)
+            .field(
+                "signal",
+                libc::WIFSIGNALED(status).then(|| libc::WTERMSIG(status)),
+            );
+        for (key, val) in EVENTS.iter().zip(prev.events) {
+            line.push(key, val);
+        }
+        line.emit();
+
+        return Ok(());
+    }
+
+    if libc::WIFSIGNALED(status) {
+        println!("child killed by signal {}", libc::WTERMSIG(status));
+    } else {
+        println!("child allocated {} MB and exited", size_mb);
+    }
+
+    println!();
+    println!("memory.events:");
+    for (key, val) in EVENTS.iter().zip(prev.events) {
+        println!("  {:8} {}", key, val);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,205 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{cmp, collections::BTreeSet, env, fs, hint, io, time};
+
+// returns the files mapped by the process
+fn mapped_files(pid: &str) -> Result<BTreeSet<String>, io::Error> {
+    // lines are in the form of "<range> <perms> <offset> <dev> <inode> <path>"
+    let maps = fs::read_to_string(format!("/proc/{}/maps", pid))?;
+    let files = maps
+        .lines()
+        .filter_map(|line| {
+            let mut tokens = line.splitn(6, ' ');
+            let inode = tokens.nth(4)?;
+            let path = tokens.next()?.trim_start();
+            (inode != "0" && path.starts_with('/') && !path.ends_with(" (deleted)"))
+                .then(|| path.to_string())
+        })
+        .collect();
+
+    Ok(files)
+}
+
+// a file and the pages of it that were in the page cache when recorded
+struct Record {
+    path: String,
+    pages: Vec<usize>,
+}
+
+impl Record {
+    fn collect(path: String) -> Result<Self, io::Error> {
+        // empty files cannot be mapped
+        let pages = if fs::metadata(&path)?.len() > 0 {
+            rustest::Mmap::new(&path)?.resident_pages()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Record { path, pages })
+    }
+
+    // evicts the file and returns the number of recorded pages that stay resident, such as
+    // those mapped by the target
+    fn evict(&self) -> Result<usize, io::Error> {
+        rustest::fadvise(&fs::File::open(&self.path)?, libc::POSIX_FADV_DONTNEED)?;
+        self.resident()
+    }
+
+    fn resident(&self) -> Result<usize, io::Error> {
+        if self.pages.is_empty() {
+            return Ok(0);
+        }
+
+        let resident = rustest::Mmap::new(&self.path)?.resident_pages()?;
+        Ok(self
+            .pages
+            .iter()
+            .filter(|page| resident.binary_search(page).is_ok())
+            .count())
+    }
+
+    // faults the recorded pages back in
+    fn fault_in(&self) -> Result<time::Duration, io::Error> {
+        if self.pages.is_empty() {
+            return Ok(time::Duration::ZERO);
+        }
+
+        let page_size = rustest::page_size();
+        let start = time::Instant::now();
+        let mmap = rustest::Mmap::new(&self.path)?;
+        let bytes = mmap.as_bytes();
+        for page in &self.pages {
+            hint::black_box(bytes[page * page_size]);
+        }
+
+        Ok(start.elapsed())
+    }
+}
+
+fn print_help() {
+    println!("usage: coldstart [options] PID");
+    println!("  PID: process whose mapped files make up the cold start set");
+    println!("  --loops N: number of evict and fault-in cycles (default: 1)");
+    println!("  --top N: number of slowest files shown (default: 10)");
+    println!("  --json: print the results as JSON lines");
+    println!("note: pages mapped by a running process cannot be evicted");
+}
+
+struct Args {
+    pid: Option<String>,
+    loops: usize,
+    top: usize,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        pid: None,
+        loops: 1,
+        top: 10,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--loops" => args.loops = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--top" => args.top = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.pid = Some(arg),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json || args.pid.is_none() {
+        print_help();
+        println!();
+    }
+
+    let Some(pid) = &args.pid else {
+        return Ok(());
+    };
+
+    let mut records = Vec::new();
+    for path in mapped_files(pid)? {
+        match Record::collect(path.clone()) {
+            Ok(record) => records.push(record),
+            Err(err) if args.json => rustest::JsonLine::new("skip")
+                .field("path", &path)
+                .field("error", err.to_string())
+                .emit(),
+            Err(err) => println!("skipping {}: {}", path, err),
+        }
+    }
+
+    let page_kb = rustest::page_size() / 1024;
+    let recorded: usize = records.iter().map(|record| record.pages.len()).sum();
+    if args.json {
+        rustest::JsonLine::new("recorded")
+            .field("pid", pid)
+            .field("files", records.len())
+            .field("recorded_kb", recorded * page_kb)
+            .emit();
+    } else {
+        println!(
+            "recorded {} KB in {} files mapped by pid {}",
+            recorded * page_kb,
+            records.len(),
+            pid
+        );
+    }
+
+    for _ in 0..args.loops {
+        let mut pinned = 0;
+        for record in &records {
+            pinned += record.evict()?;
+        }
+
+        let mut times = Vec::new();
+        for record in &records {
+            times.push((record.fault_in()?, record));
+        }
+        let total: time::Duration = times.iter().map(|(elapsed, _)| *elapsed).sum();
+        times.sort_by_key(|(elapsed, _)| cmp::Reverse(*elapsed));
+
+        if args.json {
+            let top = &times[..times.len().min(args.top)];
+            let paths: Vec<&str> = top.iter().map(|(_, record)| record.path.as_str()).collect();
+            let elapsed: Vec<time::Duration> = top.iter().map(|(elapsed, _)| *elapsed).collect();
+            rustest::JsonLine::new("sample")
+                .field("evicted_kb", (recorded - pinned) * page_kb)
+                .field("pinned_kb", pinned * page_kb)
+                .field("fault_in_ns", total)
+                .field("slowest", paths)
+                .field("slowest_ns", elapsed)
+                .emit();
+            continue;
+        }
+
+        println!();
+        println!(
+            "evicted {} KB ({} KB stayed resident), faulted back in {:.1?}",
+            (recorded - pinned) * page_kb,
+            pinned * page_kb,
+            total
+        );
+
+        for (elapsed, record) in times.iter().take(args.top) {
+            println!(
+                "  {:>10} {:8} KB  {}",
+                format!("{:.1?}", elapsed),
+                record.pages.len() * page_kb,
+                record.path
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, time};
+
+// vmstat counters that compaction updates
+const COUNTERS: [&str; 8] = [
+    "compact_stall",
+    "compact_success",
+    "compact_fail",
+    "compact_migrate_scanned",
+    "compact_free_scanned",
+    "compact_isolated",
+    "compact_daemon_wake",
+    "pgmigrate_success",
+];
+
+// free block counts of a zone, indexed by order
+struct Zone {
+    name: String,
+    free: Vec<u64>,
+}
+
+impl Zone {
+    // returns the free memory in blocks of the order or higher, in KB
+    fn free_kb(&self, order: usize) -> u64 {
+        let page_kb = rustest::page_size() as u64 / 1024;
+        self.free
+            .iter()
+            .enumerate()
+            .skip(order)
+            .map(|(order, count)| (count << order) * page_kb)
+            .sum()
+    }
+}
+
+struct Sample {
+    zones: Vec<Zone>,
+    counters: [u64; COUNTERS.len()],
+}
+
+impl Sample {
+    fn collect() -> Self {
+        // lines are in the form of "Node 0, zone Normal <order 0 count> <order 1 count> ..."
+        let buddyinfo = fs::read_to_string("/proc/buddyinfo").unwrap_or_default();
+        let zones = buddyinfo
+            .lines()
+            .filter_map(|line| {
+                let mut tokens = line.split_ascii_whitespace();
+                let node = tokens.nth(1)?.trim_end_matches(',');
+                let zone = tokens.nth(1)?;
+                Some(Zone {
+                    name: format!("node{} {}", node, zone),
+                    free: tokens
+                        .map(|count| count.parse().unwrap_or_default())
+                        .collect(),
+                })
+            })
+            .collect();
+
+        let vmstat = fs::read_to_string("/proc/vmstat").unwrap_or_default();
+        let counters = COUNTERS.map(|key| {
+            vmstat
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default()
+        });
+
+        Sample { zones, counters }
+    }
+
+    fn print_zones(&self) {
+        for zone in &self.zones {
+            let free: Vec<String> = zone.free.iter().map(|count| count.to_string()).collect();
+            println!("  {:16} {}", zone.name, free.join(" "));
+        }
+    }
+}
+
+fn print_help() {
+    println!("usage: compact [options]");
+    println!("  --node N: compact only the node rather than all of memory");
+    println!("  --order N: report free memory in blocks of this order or higher (default: 9)");
+    println!("  --json: print the results as JSON lines");
+}
+
+struct Args {
+    node: Option<usize>,
+    order: usize,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        node: None,
+        order: 9,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--node" => args.node = Some(rustest::parse_num(&iter.next().unwrap_or_default())?),
+            "--order" => args.order = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let knob = match args.node {
+        Some(node) => format!("/sys/devices/system/node/node{}/compact", node),
+        None => String::from("/proc/sys/vm/compact_memory"),
+    };
+
+    let before = Sample::collect();
+    if !args.json {
+        println!("free blocks by order, before:");
+        before.print_zones();
+        println!();
+        println!("writing 1 to {}...", knob);
+    }
+
+    let start = time::Instant::now();
+    fs::write(&knob, "1")?;
+    let elapsed = start.elapsed();
+
+    let after = Sample::collect();
+    if args.json {
+        for (before, after) in before.zones.iter().zip(&after.zones) {
+            rustest::JsonLine::new("zone")
+                .field("zone", &after.name)
+                .field("free_before", &before.free)
+                .field("free_after", &after.free)
+                .field("order", args.order)
+                .field("order_free_kb_before", before.free_kb(args.order))
+                .field("order_free_kb_after", after.free_kb(args.order))
+                .emit();
+        }
+
+        let mut line = rustest::JsonLine::new("summary")
+            .field("knob", &knob)
+            .field("elapsed_ns", elapsed);
+        for ((key, before), after) in COUNTERS.iter().zip(before.counters).zip(after.counters) {
+            line.push(key, after - before);
+        }
+        line.emit();
+
+        return Ok(());
+    }
+
+    println!("compaction took {:?}", elapsed);
+    println!();
+    println!("free blocks by order, after:");
+    after.print_zones();
+
+    println!();
+    println!("free memory in order-{}+ blocks:", args.order);
+    for (before, after) in before.zones.iter().zip(&after.zones) {
+        println!(
+            "  {:16} {:7} -> {:7} MB",
+            after.name,
+            before.free_kb(args.order) / 1024,
+            after.free_kb(args.order) / 1024
+        );
+    }
+
+    println!();
+    println!("vmstat:");
+    for ((key, before), after) in COUNTERS.iter().zip(before.counters).zip(after.counters) {
+        println!("  {:24} +{}", key, after - before);
+    }
+
+    Ok(())
+}
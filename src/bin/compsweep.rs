@@ -0,0 +1,186 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io};
+
+// original and compressed sizes of the swapped data, in bytes
+#[derive(Clone, Copy, Default)]
+struct Usage {
+    orig: u64,
+    compr: u64,
+}
+
+struct Sample {
+    zram: Usage,
+    zswap: Usage,
+}
+
+impl Sample {
+    fn collect() -> Self {
+        // mm_stat starts with orig_data_size and compr_data_size in bytes
+        let mut zram = Usage::default();
+        for entry in fs::read_dir("/sys/block").into_iter().flatten().flatten() {
+            if !entry.file_name().to_string_lossy().starts_with("zram") {
+                continue;
+            }
+
+            let mm_stat = fs::read_to_string(entry.path().join("mm_stat")).unwrap_or_default();
+            let mut vals = mm_stat
+                .split_ascii_whitespace()
+                .map(|val| val.parse::<u64>().unwrap_or_default());
+            zram.orig += vals.next().unwrap_or_default();
+            zram.compr += vals.next().unwrap_or_default();
+        }
+
+        let meminfo = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+        let extract_val = |key: &str| -> u64 {
+            meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .and_then(|val| val.split_ascii_whitespace().next())
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default()
+        };
+        let zswap = Usage {
+            orig: extract_val("Zswapped") * 1024,
+            compr: extract_val("Zswap") * 1024,
+        };
+
+        Sample { zram, zswap }
+    }
+
+    fn delta(&self, prev: &Self) -> Self {
+        let delta = |cur: Usage, prev: Usage| Usage {
+            orig: cur.orig.saturating_sub(prev.orig),
+            compr: cur.compr.saturating_sub(prev.compr),
+        };
+
+        Sample {
+            zram: delta(self.zram, prev.zram),
+            zswap: delta(self.zswap, prev.zswap),
+        }
+    }
+}
+
+fn format_usage(usage: Usage) -> String {
+    let ratio = if usage.compr > 0 {
+        format!("{:.2}", usage.orig as f64 / usage.compr as f64)
+    } else {
+        "-".to_string()
+    };
+
+    format!(
+        "{:8.1} {:8.1} {:>6}",
+        usage.orig as f64 / 1024.0 / 1024.0,
+        usage.compr as f64 / 1024.0 / 1024.0,
+        ratio
+    )
+}
+
+fn print_help() {
+    println!("usage: compsweep [options] [SIZE_MB]");
+    println!("  SIZE_MB: memory to fill and page out per level (default: 256)");
+    println!("  --levels PCT,...: percentages of incompressible data (default: 0,25,50,75,100)");
+    println!("  --json: print the results as JSON lines");
+    println!("note: pages only leave memory when there is zram or zswap-backed swap");
+}
+
+struct Args {
+    size_mb: usize,
+    levels: Vec<u8>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        size_mb: 256,
+        levels: vec![0, 25, 50, 75, 100],
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--levels" => {
+                let list = iter.next().unwrap_or_default();
+                args.levels = list
+                    .split(',')
+                    .filter(|level| !level.is_empty())
+                    .map(|level| {
+                        level.parse().ok().filter(|&pct| pct <= 100).ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!("invalid level {}", level),
+                            )
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.size_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let page_size = rustest::page_size();
+    let len = args.size_mb * 1024 * 1024;
+    if len == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid size"));
+    }
+
+    if !args.json {
+        println!(
+            "{:>5} {:>10} {:>8} {:>8} {:>6} {:>8} {:>8} {:>6}",
+            "level", "swapped", "zram MB", "compr", "ratio", "zswap MB", "compr", "ratio"
+        );
+    }
+    for &level in &args.levels {
+        let fill = rustest::Fill::Mixed(level);
+        let mut mmap = rustest::Mmap::anonymous(len)?;
+        mmap.fill_with(fill, 1);
+
+        let prev = Sample::collect();
+        mmap.madvise(libc::MADV_PAGEOUT)?;
+        let swapped = len / page_size - mmap.resident()?;
+        let delta = Sample::collect().delta(&prev);
+        let bad = mmap.verify_with(fill, 1);
+
+        if args.json {
+            rustest::JsonLine::new("sample")
+                .field("level", level)
+                .field("swapped", swapped)
+                .field("zram_orig_bytes", delta.zram.orig)
+                .field("zram_compr_bytes", delta.zram.compr)
+                .field("zswap_orig_bytes", delta.zswap.orig)
+                .field("zswap_compr_bytes", delta.zswap.compr)
+                .field("bad", bad)
+                .emit();
+            continue;
+        }
+
+        println!(
+            "{:>4}% {:10} {} {}",
+            level,
+            swapped,
+            format_usage(delta.zram),
+            format_usage(delta.zswap)
+        );
+
+        if bad > 0 {
+            println!("{:>4}% read back {} bad pages", level, bad);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, ptr, thread, time};
+
+const REPORT_INTERVAL: time::Duration = time::Duration::from_secs(1);
+// how often a child writes its next batch of pages
+const WRITE_INTERVAL: time::Duration = time::Duration::from_millis(100);
+
+// smaps_rollup of a process, in KB
+struct Rollup {
+    rss: u64,
+    pss: u64,
+    shared: u64,
+    private: u64,
+}
+
+impl Rollup {
+    fn collect(pid: &str) -> Self {
+        let rollup = fs::read_to_string(format!("/proc/{}/smaps_rollup", pid)).unwrap_or_default();
+        let extract_val = |key: &str| -> u64 {
+            rollup
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .and_then(|val| val.split_ascii_whitespace().next())
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default()
+        };
+
+        Rollup {
+            rss: extract_val("Rss"),
+            pss: extract_val("Pss"),
+            shared: extract_val("Shared_Clean") + extract_val("Shared_Dirty"),
+            private: extract_val("Private_Clean") + extract_val("Private_Dirty"),
+        }
+    }
+
+    fn print(&self, role: &str) {
+        println!(
+            "  {:8} rss {:6} MB, pss {:6} MB, shared {:6} MB, private {:6} MB",
+            role,
+            self.rss / 1024,
+            self.pss / 1024,
+            self.shared / 1024,
+            self.private / 1024
+        );
+    }
+
+    fn emit(&self, role: &str, elapsed: time::Duration) {
+        rustest::JsonLine::new("sample")
+            .field("elapsed_ns", elapsed)
+            .field("process", role)
+            .field("rss_kb", self.rss)
+            .field("pss_kb", self.pss)
+            .field("shared_kb", self.shared)
+            .field("private_kb", self.private)
+            .emit();
+    }
+}
+
+// a forked child that writes a fraction of the inherited pages over the duration
+struct Child {
+    pid: libc::pid_t,
+    fraction: f64,
+}
+
+impl Child {
+    fn spawn(
+        mmap: &mut rustest::Mmap,
+        idx: usize,
+        fraction: f64,
+        duration: time::Duration,
+    ) -> Result<Self, io::Error> {
+        // SAFETY: we are single-threaded and the child never returns
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            return Err(io::Error::last_os_error());
+        } else if pid == 0 {
+            Self::serve(mmap, idx, fraction, duration);
+        }
+
+        Ok(Child { pid, fraction })
+    }
+
+    fn serve(mmap: &mut rustest::Mmap, idx: usize, fraction: f64, duration: time::Duration) -> ! {
+        let page_size = rustest::page_size();
+        let bytes = mmap.as_bytes_mut();
+
+        let mut pages: Vec<usize> = (0..bytes.len() / page_size).collect();
+        rustest::shuffle(&mut pages, idx as u64 + 1);
+        pages.truncate((pages.len() as f64 * fraction) as usize);
+
+        // spread the writes evenly over the duration
+        let batch_count = (duration.as_secs_f64() / WRITE_INTERVAL.as_secs_f64()).max(1.0);
+        let batch_size = (pages.len() as f64 / batch_count).ceil().max(1.0) as usize;
+        for batch in pages.chunks(batch_size) {
+            for page in batch {
+                bytes[page * page_size] = bytes[page * page_size].wrapping_add(1);
+            }
+            thread::sleep(WRITE_INTERVAL);
+        }
+
+        loop {
+            // SAFETY: valid pause call
+            unsafe { libc::pause() };
+        }
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        // SAFETY: valid kill call
+        unsafe { libc::kill(self.pid, libc::SIGKILL) };
+        // SAFETY: valid waitpid call
+        unsafe { libc::waitpid(self.pid, ptr::null_mut(), 0) };
+    }
+}
+
+fn print_help() {
+    println!("usage: cowtest [options] [SIZE_MB]");
+    println!("  SIZE_MB: size of the mapping that the children inherit (default: 256)");
+    println!("  --children N: number of children; child i writes (i + 1) / N of the pages");
+    println!("      (default: 4)");
+    println!("  --duration SECS: how long the children take to write (default: 5)");
+    println!("  --json: print samples and the summary as JSON lines");
+}
+
+struct Args {
+    size_mb: usize,
+    children: usize,
+    duration: time::Duration,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        size_mb: 256,
+        children: 4,
+        duration: time::Duration::from_secs(5),
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--children" => args.children = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--duration" => {
+                args.duration = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.size_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    args.children = args.children.max(1);
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    if !args.json {
+        println!("filling {} MB...", args.size_mb);
+    }
+    let mut mmap = rustest::Mmap::anonymous(args.size_mb * 1024 * 1024)?;
+    mmap.fill_with(rustest::Fill::Random(0), 1);
+
+    let mut children = Vec::new();
+    for idx in 0..args.children {
+        let fraction = (idx + 1) as f64 / args.children as f64;
+        children.push(Child::spawn(&mut mmap, idx, fraction, args.duration)?);
+    }
+
+    // one more report after the children are done
+    let start = time::Instant::now();
+    while start.elapsed() < args.duration + REPORT_INTERVAL {
+        thread::sleep(REPORT_INTERVAL);
+
+        let elapsed = start.elapsed();
+        let mut rollups = vec![(String::from("parent"), Rollup::collect("self"))];
+        for (idx, child) in children.iter().enumerate() {
+            rollups.push((
+                format!("child{}", idx),
+                Rollup::collect(&child.pid.to_string()),
+            ));
+        }
+
+        if args.json {
+            for (role, rollup) in &rollups {
+                rollup.emit(role, elapsed);
+            }
+        } else {
+            println!("{:5.1}s:", elapsed.as_secs_f64());
+            for (role, rollup) in &rollups {
+                rollup.print(role);
+            }
+        }
+    }
+
+    if args.json {
+        for (idx, child) in children.iter().enumerate() {
+            let rollup = Rollup::collect(&child.pid.to_string());
+            rustest::JsonLine::new("summary")
+                .field("process", format!("child{}", idx))
+                .field("fraction", child.fraction)
+                .field(
+                    "expected_kb",
+                    (args.size_mb as f64 * child.fraction * 1024.0) as u64,
+                )
+                .field("private_kb", rollup.private)
+                .emit();
+        }
+
+        return Ok(());
+    }
+
+    println!();
+    println!("children, expected private vs actual:");
+    for (idx, child) in children.iter().enumerate() {
+        let rollup = Rollup::collect(&child.pid.to_string());
+        println!(
+            "  child{:<3} wrote {:5.1}%: expected {:6} MB, private {:6} MB",
+            idx,
+            child.fraction * 100.0,
+            (args.size_mb as f64 * child.fraction) as u64,
+            rollup.private / 1024
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,319 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, fs,
+    io::{self, BufRead},
+    path, process, thread, time,
+};
+
+const REPORT_INTERVAL: time::Duration = time::Duration::from_secs(1);
+// the rate limit is enforced at this granularity
+const THROTTLE_SIZE: usize = 1024 * 1024;
+
+// writeback sysctls printed at start
+const SYSCTLS: [&str; 6] = [
+    "dirty_ratio",
+    "dirty_background_ratio",
+    "dirty_bytes",
+    "dirty_background_bytes",
+    "dirty_expire_centisecs",
+    "dirty_writeback_centisecs",
+];
+
+struct Proc {
+    dirty: u64,
+    writeback: u64,
+    // in pages
+    dirty_threshold: u64,
+    dirty_background_threshold: u64,
+}
+
+impl Proc {
+    fn collect() -> Self {
+        let mut proc = Proc {
+            dirty: 0,
+            writeback: 0,
+            dirty_threshold: 0,
+            dirty_background_threshold: 0,
+        };
+
+        let _ = proc.collect_meminfo();
+        let _ = proc.collect_vmstat();
+
+        proc
+    }
+
+    fn collect_meminfo(&mut self) -> Result<(), io::Error> {
+        let fp = fs::File::open("/proc/meminfo")?;
+        let reader = io::BufReader::new(fp);
+
+        for line in reader.lines() {
+            let line = line?;
+
+            let extract_val = |line: &str| {
+                line.split_ascii_whitespace()
+                    .nth(1)
+                    .and_then(|val| val.parse::<u64>().ok())
+                    .unwrap_or_default()
+            };
+
+            if line.starts_with("Dirty:") {
+                self.dirty = extract_val(&line);
+            } else if line.starts_with("Writeback:") {
+                self.writeback = extract_val(&line);
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn collect_vmstat(&mut self) -> Result<(), io::Error> {
+        let fp = fs::File::open("/proc/vmstat")?;
+        let reader = io::BufReader::new(fp);
+
+        for line in reader.lines() {
+            let line = line?;
+
+            if let Some(val) = line.strip_prefix("nr_dirty_threshold ") {
+                self.dirty_threshold = val.parse().unwrap_or_default();
+            } else if let Some(val) = line.strip_prefix("nr_dirty_background_threshold ") {
+                self.dirty_background_threshold = val.parse().unwrap_or_default();
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn print(&self) {
+        let [dirty_threshold, dirty_background_threshold] =
+            [self.dirty_threshold, self.dirty_background_threshold]
+                .map(|page_count| page_count as usize * rustest::page_size() / 1024 / 1024);
+
+        println!(
+            "Dirty {:5} MB, Writeback {:5} MB, thresholds {} / {} MB",
+            self.dirty / 1024,
+            self.writeback / 1024,
+            dirty_background_threshold,
+            dirty_threshold
+        );
+    }
+
+    fn push_json(&self, line: &mut rustest::JsonLine) {
+        let page_kb = rustest::page_size() as u64 / 1024;
+        line.push("dirty_kb", self.dirty);
+        line.push("writeback_kb", self.writeback);
+        line.push("dirty_threshold_kb", self.dirty_threshold * page_kb);
+        line.push(
+            "dirty_background_threshold_kb",
+            self.dirty_background_threshold * page_kb,
+        );
+    }
+}
+
+fn print_help() {
+    println!("usage: dirtytest [options] [SIZE_MB]");
+    println!("  SIZE_MB: size of the file mapping (default: 1024)");
+    println!("  --dir DIR: where the file is created (default: /var/tmp)");
+    println!("  --rate MB/s: how fast pages are dirtied, 0 for unlimited (default: 0)");
+    println!("  --duration SECS: how long to dirty pages (default: 10)");
+    println!("  --msync SECS: msync the mapping periodically (default: never)");
+    println!("  --json: print samples and the summary as JSON lines");
+}
+
+struct Args {
+    size_mb: usize,
+    dir: String,
+    rate: f64,
+    duration: time::Duration,
+    msync: Option<time::Duration>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        size_mb: 1024,
+        dir: String::from("/var/tmp"),
+        rate: 0.0,
+        duration: time::Duration::from_secs(10),
+        msync: None,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--dir" => args.dir = iter.next().unwrap_or(args.dir),
+            "--rate" => args.rate = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--duration" => {
+                args.duration = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--msync" => {
+                args.msync = Some(rustest::parse_duration(&iter.next().unwrap_or_default())?)
+            }
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.size_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let page_size = rustest::page_size();
+    let len = args.size_mb * 1024 * 1024;
+    let page_count = len / page_size;
+    if page_count == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid size"));
+    }
+
+    let sysctls: Vec<(&str, String)> = SYSCTLS
+        .iter()
+        .map(|name| {
+            let val = fs::read_to_string(format!("/proc/sys/vm/{}", name)).unwrap_or_default();
+            (*name, val.trim().to_string())
+        })
+        .collect();
+    if args.json {
+        let mut line = rustest::JsonLine::new("config");
+        for (name, val) in &sysctls {
+            line.push(name, val.parse::<u64>().ok());
+        }
+        line.emit();
+    } else {
+        let sysctls: Vec<String> = sysctls
+            .iter()
+            .map(|(name, val)| format!("{} {}", name, val))
+            .collect();
+        println!("vm: {}", sysctls.join(", "));
+    }
+
+    let path = path::Path::new(&args.dir).join(format!("dirtytest-{}", process::id()));
+    let path = path.to_string_lossy();
+    let mmap = rustest::Mmap::create(&path, len);
+    // the mapping keeps the file alive
+    let _ = fs::remove_file(path.as_ref());
+    let mut mmap = mmap?;
+
+    if !args.json {
+        println!(
+            "dirtying {} MB of {} for {:?}...",
+            args.size_mb, args.dir, args.duration
+        );
+    }
+
+    let throttle = (args.rate > 0.0)
+        .then(|| time::Duration::from_secs_f64(THROTTLE_SIZE as f64 / 1024.0 / 1024.0 / args.rate));
+    let mut total = rustest::Histogram::new();
+    let mut interval = rustest::Histogram::new();
+    let mut page = 0;
+    let mut dirtied = 0;
+    let start = time::Instant::now();
+    let mut report = start;
+    let mut msync = start;
+    let mut next = start;
+    while start.elapsed() < args.duration {
+        let offset = page * page_size;
+        let bytes = mmap.as_bytes_mut();
+
+        // balance_dirty_pages throttles the write faults
+        let begin = time::Instant::now();
+        bytes[offset] = bytes[offset].wrapping_add(1);
+        interval.record(begin.elapsed());
+
+        page = (page + 1) % page_count;
+        dirtied += page_size;
+
+        if let Some(throttle) = throttle
+            && dirtied % THROTTLE_SIZE == 0
+        {
+            next += throttle;
+            thread::sleep(next.saturating_duration_since(time::Instant::now()));
+        }
+
+        if let Some(period) = args.msync
+            && msync.elapsed() >= period
+        {
+            let begin = time::Instant::now();
+            mmap.msync(libc::MS_SYNC)?;
+            if args.json {
+                rustest::JsonLine::new("msync")
+                    .field("elapsed_ns", begin.elapsed())
+                    .emit();
+            } else {
+                println!("msync took {:?}", begin.elapsed());
+            }
+            msync = time::Instant::now();
+        }
+
+        if report.elapsed() >= REPORT_INTERVAL {
+            let mbps = dirtied as f64 / 1024.0 / 1024.0 / report.elapsed().as_secs_f64();
+            if args.json {
+                let mut line = rustest::JsonLine::new("sample")
+                    .field("elapsed_ns", start.elapsed())
+                    .field("dirtied_mbps", mbps)
+                    .field("faults", &interval);
+                Proc::collect().push_json(&mut line);
+                line.emit();
+            } else {
+                print!(
+                    "{:5.1}s: dirtied {:6.1} MB/s, {}, ",
+                    start.elapsed().as_secs_f64(),
+                    mbps,
+                    interval
+                );
+                Proc::collect().print();
+            }
+
+            total.add(&interval);
+            interval = rustest::Histogram::new();
+            dirtied = 0;
+            report = time::Instant::now();
+        }
+    }
+    total.add(&interval);
+
+    if args.json {
+        let before = Proc::collect();
+        let begin = time::Instant::now();
+        mmap.msync(libc::MS_SYNC)?;
+        let elapsed = begin.elapsed();
+        let after = Proc::collect();
+
+        rustest::JsonLine::new("summary")
+            .field("faults", &total)
+            .field("dirty_before_kb", before.dirty)
+            .field("writeback_before_kb", before.writeback)
+            .field("msync_ns", elapsed)
+            .field("dirty_after_kb", after.dirty)
+            .field("writeback_after_kb", after.writeback)
+            .emit();
+
+        return Ok(());
+    }
+
+    println!();
+    println!("write faults: {}", total);
+    total.print();
+
+    println!();
+    print!("before msync: ");
+    Proc::collect().print();
+    let begin = time::Instant::now();
+    mmap.msync(libc::MS_SYNC)?;
+    println!("msync took {:?}", begin.elapsed());
+    print!("after msync:  ");
+    Proc::collect().print();
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, time};
+
+// meminfo fields compared before and after
+const FIELDS: [&str; 6] = [
+    "MemFree",
+    "MemAvailable",
+    "Cached",
+    "Buffers",
+    "Slab",
+    "SReclaimable",
+];
+
+// returns FIELDS from meminfo, in KB
+fn meminfo() -> [i64; FIELDS.len()] {
+    let meminfo = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    FIELDS.map(|key| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|val| val.split_ascii_whitespace().next())
+            .and_then(|val| val.parse().ok())
+            .unwrap_or_default()
+    })
+}
+
+fn print_help() {
+    println!("usage: dropcaches [--json] [MODE]");
+    println!("  MODE: 1 drops the page cache, 2 slab objects, 3 both (default: 3)");
+    println!("  --json: print the results as JSON lines");
+}
+
+struct Args {
+    mode: u32,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        mode: 3,
+        json: false,
+    };
+
+    for arg in env::args().skip(1) {
+        if arg == "--json" {
+            args.json = true;
+            continue;
+        }
+
+        args.mode = match arg.parse() {
+            Ok(mode @ 1..=3) => mode,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid mode {}", arg),
+                ));
+            }
+        };
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let before = meminfo();
+
+    // dirty pages cannot be dropped
+    let start = time::Instant::now();
+    // SAFETY: valid sync call
+    unsafe { libc::sync() };
+    let sync_elapsed = start.elapsed();
+
+    let start = time::Instant::now();
+    fs::write("/proc/sys/vm/drop_caches", args.mode.to_string())?;
+    let drop_elapsed = start.elapsed();
+
+    let after = meminfo();
+
+    if args.json {
+        let mut line = rustest::JsonLine::new("summary")
+            .field("mode", args.mode)
+            .field("sync_ns", sync_elapsed)
+            .field("drop_caches_ns", drop_elapsed);
+        for ((key, before), after) in FIELDS.iter().zip(before).zip(after) {
+            line.push(&format!("{}_before_kb", key), before);
+            line.push(&format!("{}_after_kb", key), after);
+        }
+        line.emit();
+
+        return Ok(());
+    }
+
+    println!("sync took {:?}", sync_elapsed);
+    println!("drop_caches {} took {:?}", args.mode, drop_elapsed);
+
+    println!();
+    println!(
+        "{:14} {:>10} {:>10} {:>10}",
+        "", "before MB", "after MB", "diff MB"
+    );
+    for ((key, before), after) in FIELDS.iter().zip(before).zip(after) {
+        println!(
+            "{:14} {:10} {:10} {:+10}",
+            key,
+            before / 1024,
+            after / 1024,
+            (after - before) / 1024
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,227 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, mem, path, process, time};
+
+enum Mode {
+    // write faults that allocate anonymous pages
+    Minor,
+    // read faults that map the shared zero page
+    Zero,
+    // read faults on an evicted file
+    Major,
+}
+
+impl Mode {
+    const ALL: [Mode; 3] = [Mode::Minor, Mode::Zero, Mode::Major];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Minor => "minor",
+            Mode::Zero => "zero",
+            Mode::Major => "major",
+        }
+    }
+}
+
+// returns the minor and major fault counts of this process
+fn faults() -> (i64, i64) {
+    // SAFETY: rusage is plain data
+    let mut usage: libc::rusage = unsafe { mem::zeroed() };
+    // SAFETY: usage is valid
+    unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+
+    (usage.ru_minflt, usage.ru_majflt)
+}
+
+// a file that is removed on drop
+struct TempFile {
+    path: String,
+    fp: fs::File,
+}
+
+impl TempFile {
+    fn create(dir: &str, len: usize) -> Result<Self, io::Error> {
+        let path = path::Path::new(dir).join(format!("faultbench-{}", process::id()));
+        let path = path.to_string_lossy().to_string();
+
+        let res = rustest::Mmap::create(&path, len).and_then(|mut mmap| {
+            mmap.fill(1);
+            mmap.msync(libc::MS_SYNC)?;
+            fs::File::open(&path)
+        });
+        match res {
+            Ok(fp) => Ok(TempFile { path, fp }),
+            Err(err) => {
+                let _ = fs::remove_file(&path);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// maps, touches, and discards memory of the size once
+fn iterate(mode: &Mode, size: usize, file: Option<&TempFile>) -> Result<(), io::Error> {
+    match mode {
+        Mode::Minor => {
+            let mut mmap = rustest::Mmap::anonymous(size)?;
+            mmap.fill(1);
+        }
+        Mode::Zero => {
+            let mmap = rustest::Mmap::anonymous(size)?;
+            mmap.populate();
+        }
+        Mode::Major => {
+            let file = file.ok_or(io::ErrorKind::NotFound)?;
+            rustest::fadvise(&file.fp, libc::POSIX_FADV_DONTNEED)?;
+
+            let mut mmap = rustest::Mmap::new(&file.path)?;
+            // one major fault per page rather than per readahead window
+            mmap.madvise(libc::MADV_RANDOM)?;
+            mmap.populate();
+        }
+    }
+
+    Ok(())
+}
+
+fn print_help() {
+    println!("usage: faultbench [options]");
+    println!("  --size MB: memory mapped and touched per iteration (default: 64)");
+    println!("  --duration SECS: how long each mode runs (default: 5)");
+    println!("  --mode MODE: minor, zero, or major (default: all)");
+    println!("  --dir DIR: where the file for major faults is created (default: /var/tmp)");
+    println!("  --perf: also count faults, context switches, and dTLB misses with perf events");
+    println!("  --json: print the results as JSON lines");
+}
+
+struct Args {
+    size_mb: usize,
+    duration: time::Duration,
+    mode: Option<String>,
+    dir: String,
+    perf: bool,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        size_mb: 64,
+        duration: time::Duration::from_secs(5),
+        mode: None,
+        dir: String::from("/var/tmp"),
+        perf: false,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--size" => args.size_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--duration" => {
+                args.duration = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--mode" => args.mode = iter.next(),
+            "--dir" => args.dir = iter.next().unwrap_or(args.dir),
+            "--perf" => args.perf = true,
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let modes: Vec<&Mode> = Mode::ALL
+        .iter()
+        .filter(|mode| args.mode.as_deref().is_none_or(|name| name == mode.name()))
+        .collect();
+    if modes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid mode {}", args.mode.unwrap_or_default()),
+        ));
+    }
+
+    let size = args.size_mb * 1024 * 1024;
+    let file = if modes.iter().any(|mode| matches!(mode, Mode::Major)) {
+        Some(TempFile::create(&args.dir, size)?)
+    } else {
+        None
+    };
+
+    let perf = args
+        .perf
+        .then(|| rustest::PerfCounters::open(&rustest::PerfEvent::ALL))
+        .transpose()?;
+
+    if !args.json {
+        println!(
+            "{:6} {:>6} {:>10} {:>10} {:>12} {:>10}",
+            "mode", "iters", "minflt", "majflt", "faults/s", "us/fault"
+        );
+    }
+
+    for mode in modes {
+        let mut iters = 0;
+        let (minflt, majflt) = faults();
+        let perf_prev = perf.as_ref().map(|perf| perf.read());
+        let start = time::Instant::now();
+        while start.elapsed() < args.duration {
+            iterate(mode, size, file.as_ref())?;
+            iters += 1;
+        }
+        let elapsed = start.elapsed();
+        let perf_delta = perf
+            .as_ref()
+            .zip(perf_prev)
+            .map(|(perf, prev)| perf.read().delta(&prev));
+        let (minflt, majflt) = {
+            let (cur_minflt, cur_majflt) = faults();
+            (cur_minflt - minflt, cur_majflt - majflt)
+        };
+
+        let count = (minflt + majflt).max(1);
+        if args.json {
+            rustest::JsonLine::new("result")
+                .field("mode", mode.name())
+                .field("iters", iters)
+                .field("minflt", minflt)
+                .field("majflt", majflt)
+                .field("elapsed_ns", elapsed)
+                .field("ns_per_fault", elapsed.as_nanos() as u64 / count as u64)
+                .field("perf", &perf_delta)
+                .emit();
+            continue;
+        }
+
+        println!(
+            "{:6} {:6} {:10} {:10} {:12.0} {:10.2}",
+            mode.name(),
+            iters,
+            minflt,
+            majflt,
+            count as f64 / elapsed.as_secs_f64(),
+            elapsed.as_secs_f64() * 1e6 / count as f64
+        );
+        if let Some(perf_delta) = &perf_delta {
+            println!("{:6} perf: {}", "", perf_delta);
+        }
+    }
+
+    Ok(())
+}
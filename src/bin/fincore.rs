@@ -0,0 +1,223 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, path};
+
+// page cache state of a file, falling back to mincore when cachestat is unavailable
+fn residency(path: &path::Path) -> Result<rustest::CacheStat, io::Error> {
+    let fp = fs::File::open(path)?;
+    if let Ok(cstat) = rustest::cachestat(&fp) {
+        return Ok(cstat);
+    }
+
+    // empty files cannot be mapped
+    if fp.metadata()?.len() == 0 {
+        return Ok(rustest::CacheStat::default());
+    }
+
+    let mmap = rustest::Mmap::new(&path.to_string_lossy())?;
+    Ok(rustest::CacheStat {
+        cache: mmap.resident()? as u64,
+        ..Default::default()
+    })
+}
+
+struct Report {
+    args: Args,
+    file_count: usize,
+    size: u64,
+    total: rustest::CacheStat,
+}
+
+impl Report {
+    fn new(args: Args) -> Self {
+        Report {
+            args,
+            file_count: 0,
+            size: 0,
+            total: rustest::CacheStat::default(),
+        }
+    }
+
+    fn print_header(&self) {
+        if !self.args.summary && !self.args.json {
+            println!(
+                "{:>10} {:>10} {:>6} {:>10} {:>10}  file",
+                "size KB", "cached KB", "%", "dirty KB", "evicted KB"
+            );
+        }
+    }
+
+    fn print_row(&self, size: u64, cstat: &rustest::CacheStat, name: &str) {
+        if self.args.json {
+            let page_kb = rustest::page_size() as u64 / 1024;
+            rustest::JsonLine::new("file")
+                .field("path", name)
+                .field("size_kb", size / 1024)
+                .field("cached_kb", cstat.cache * page_kb)
+                .field("dirty_kb", cstat.dirty * page_kb)
+                .field("writeback_kb", cstat.writeback * page_kb)
+                .field("evicted_kb", cstat.evicted * page_kb)
+                .field("recently_evicted_kb", cstat.recently_evicted * page_kb)
+                .emit();
+            return;
+        }
+
+        let page_kb = rustest::page_size() as u64 / 1024;
+        let pct = if size > 0 {
+            (cstat.cache * page_kb * 1024) as f64 * 100.0 / size as f64
+        } else {
+            0.0
+        };
+
+        println!(
+            "{:10} {:10} {:5.1}% {:10} {:10}  {}",
+            size / 1024,
+            cstat.cache * page_kb,
+            pct.min(100.0),
+            cstat.dirty * page_kb,
+            cstat.evicted * page_kb,
+            name
+        );
+    }
+
+    fn print_skip(&self, path: &path::Path, err: io::Error) {
+        if self.args.json {
+            rustest::JsonLine::new("skip")
+                .field("path", path.to_string_lossy().as_ref())
+                .field("error", err.to_string())
+                .emit();
+        } else {
+            println!("skipping {}: {}", path.display(), err);
+        }
+    }
+
+    fn add_file(&mut self, path: &path::Path) {
+        let size = match fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(err) => {
+                self.print_skip(path, err);
+                return;
+            }
+        };
+        let cstat = match residency(path) {
+            Ok(cstat) => cstat,
+            Err(err) => {
+                self.print_skip(path, err);
+                return;
+            }
+        };
+
+        if !self.args.summary && (self.args.all || cstat.cache > 0) {
+            self.print_row(size, &cstat, &path.to_string_lossy());
+        }
+
+        self.file_count += 1;
+        self.size += size;
+        self.total.cache += cstat.cache;
+        self.total.dirty += cstat.dirty;
+        self.total.writeback += cstat.writeback;
+        self.total.evicted += cstat.evicted;
+        self.total.recently_evicted += cstat.recently_evicted;
+    }
+
+    fn add_dir(&mut self, dir: &path::Path) -> Result<(), io::Error> {
+        let mut entries = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+
+        for entry in entries {
+            let file_type = fs::symlink_metadata(&entry)?.file_type();
+            if file_type.is_dir() {
+                self.add_dir(&entry)?;
+            } else if file_type.is_file() {
+                self.add_file(&entry);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add(&mut self, path: &path::Path) -> Result<(), io::Error> {
+        if fs::metadata(path)?.is_dir() {
+            self.add_dir(path)
+        } else {
+            self.add_file(path);
+            Ok(())
+        }
+    }
+
+    fn print_total(&self) {
+        if self.args.json {
+            let page_kb = rustest::page_size() as u64 / 1024;
+            rustest::JsonLine::new("summary")
+                .field("files", self.file_count)
+                .field("size_kb", self.size / 1024)
+                .field("cached_kb", self.total.cache * page_kb)
+                .field("dirty_kb", self.total.dirty * page_kb)
+                .field("writeback_kb", self.total.writeback * page_kb)
+                .field("evicted_kb", self.total.evicted * page_kb)
+                .field("recently_evicted_kb", self.total.recently_evicted * page_kb)
+                .emit();
+            return;
+        }
+
+        let name = format!("total of {} files", self.file_count);
+        self.print_row(self.size, &self.total, &name);
+    }
+}
+
+fn print_help() {
+    println!("usage: fincore [options] PATH...");
+    println!("  PATH: files, or directories to walk recursively");
+    println!("  --all: also list files with nothing cached");
+    println!("  --summary: only print the total");
+    println!("  --json: print the files and the total as JSON lines");
+}
+
+struct Args {
+    paths: Vec<String>,
+    all: bool,
+    summary: bool,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        paths: Vec::new(),
+        all: false,
+        summary: false,
+        json: false,
+    };
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--all" => args.all = true,
+            "--summary" => args.summary = true,
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.paths.push(arg),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+    if args.paths.is_empty() {
+        print_help();
+        return Ok(());
+    }
+
+    let paths = args.paths.clone();
+    let mut report = Report::new(args);
+    report.print_header();
+    for path in &paths {
+        report.add(path::Path::new(path))?;
+    }
+    report.print_total();
+
+    Ok(())
+}
@@ -0,0 +1,218 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, ffi, fs,
+    io::{self, Read, Write},
+    ptr, time,
+};
+
+const EXEC_PATH: &str = "/bin/true";
+
+// returns MemAvailable in KB
+fn mem_available() -> u64 {
+    rustest::Meminfo::collect().map_or(0, |info| info.mem_available)
+}
+
+// a forked child that holds memory to create pressure
+struct Hog {
+    pid: libc::pid_t,
+}
+
+impl Hog {
+    fn spawn(size_mb: usize) -> Result<Self, io::Error> {
+        let (ready_rd, ready_wr) = rustest::pipe()?;
+
+        // SAFETY: we are single-threaded and the child never returns
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            return Err(io::Error::last_os_error());
+        } else if pid == 0 {
+            drop(ready_rd);
+            Self::serve(ready_wr, size_mb);
+        }
+        drop(ready_wr);
+
+        // dropping the hog on errors kills it
+        let hog = Hog { pid };
+        let mut buf = [0u8; 1];
+        if (&ready_rd).read(&mut buf)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                format!("hog failed to allocate {} MB", size_mb),
+            ));
+        }
+
+        Ok(hog)
+    }
+
+    fn serve(mut ready: fs::File, size_mb: usize) -> ! {
+        if let Ok(mut mmap) = rustest::Mmap::anonymous(size_mb * 1024 * 1024) {
+            mmap.fill_with(rustest::Fill::Random(0), 1);
+            let _ = ready.write_all(b"r");
+
+            loop {
+                // SAFETY: valid pause call
+                unsafe { libc::pause() };
+            }
+        }
+
+        // SAFETY: valid _exit call
+        unsafe { libc::_exit(1) };
+    }
+}
+
+impl Drop for Hog {
+    fn drop(&mut self) {
+        // SAFETY: valid kill call
+        unsafe { libc::kill(self.pid, libc::SIGKILL) };
+        // SAFETY: valid waitpid call
+        unsafe { libc::waitpid(self.pid, ptr::null_mut(), 0) };
+    }
+}
+
+// forks a child that exits, or that execs when exec_path is set, and waits for it
+fn fork_once(exec_path: Option<&ffi::CStr>) -> Result<(), io::Error> {
+    // SAFETY: we are single-threaded and the child never returns
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        return Err(io::Error::last_os_error());
+    } else if pid == 0 {
+        if let Some(path) = exec_path {
+            let argv = [path.as_ptr(), ptr::null()];
+            // SAFETY: path and argv are NUL-terminated
+            unsafe { libc::execv(path.as_ptr(), argv.as_ptr()) };
+        }
+
+        // SAFETY: valid _exit call
+        unsafe { libc::_exit(0) };
+    }
+
+    // SAFETY: valid waitpid call
+    unsafe { libc::waitpid(pid, ptr::null_mut(), 0) };
+
+    Ok(())
+}
+
+fn measure(iters: usize, exec_path: Option<&ffi::CStr>) -> Result<rustest::Histogram, io::Error> {
+    let mut hist = rustest::Histogram::new();
+    for _ in 0..iters {
+        let begin = time::Instant::now();
+        fork_once(exec_path)?;
+        hist.record(begin.elapsed());
+    }
+
+    Ok(hist)
+}
+
+fn format_pct(hist: &rustest::Histogram, pct: u64) -> String {
+    format!("< {}", rustest::Histogram::format_ns(hist.percentile(pct)))
+}
+
+fn parse_list(list: &str) -> Result<Vec<usize>, io::Error> {
+    list.split(',')
+        .filter(|val| !val.is_empty())
+        .map(rustest::parse_mb)
+        .collect()
+}
+
+fn print_help() {
+    println!("usage: forkbench [options]");
+    println!("  --sizes MB,...: dirty parent memory the children inherit (default: 0,256,1024)");
+    println!("  --pressure MB,...: memory held by a separate hog process (default: 0)");
+    println!("  --iters N: forks per measurement (default: 100)");
+    println!("  --json: print the results as JSON lines");
+    println!("plain forks exit right away; exec forks run {}", EXEC_PATH);
+}
+
+struct Args {
+    sizes: Vec<usize>,
+    pressure: Vec<usize>,
+    iters: usize,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        sizes: vec![0, 256, 1024],
+        pressure: vec![0],
+        iters: 100,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--sizes" => args.sizes = parse_list(&iter.next().unwrap_or_default())?,
+            "--pressure" => args.pressure = parse_list(&iter.next().unwrap_or_default())?,
+            "--iters" => args.iters = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let exec_path = ffi::CString::new(EXEC_PATH)?;
+
+    if !args.json {
+        println!(
+            "{:>8} {:>8} {:>9} {:>10} {:>10} {:>10} {:>10}",
+            "hog MB", "size MB", "avail MB", "fork p50", "fork p99", "exec p50", "exec p99"
+        );
+    }
+    for &pressure_mb in &args.pressure {
+        let _hog = if pressure_mb > 0 {
+            Some(Hog::spawn(pressure_mb)?)
+        } else {
+            None
+        };
+
+        for &size_mb in &args.sizes {
+            let mmap = if size_mb > 0 {
+                let mut mmap = rustest::Mmap::anonymous(size_mb * 1024 * 1024)?;
+                mmap.fill(1);
+                Some(mmap)
+            } else {
+                None
+            };
+
+            let fork = measure(args.iters, None)?;
+            let exec = measure(args.iters, Some(&exec_path))?;
+            if args.json {
+                rustest::JsonLine::new("result")
+                    .field("pressure_mb", pressure_mb)
+                    .field("size_mb", size_mb)
+                    .field("avail_kb", mem_available())
+                    .field("fork", &fork)
+                    .field("exec", &exec)
+                    .emit();
+                continue;
+            }
+
+            println!(
+                "{:8} {:8} {:9} {:>10} {:>10} {:>10} {:>10}",
+                pressure_mb,
+                size_mb,
+                mem_available() / 1024,
+                format_pct(&fork, 50),
+                format_pct(&fork, 99),
+                format_pct(&exec, 50),
+                format_pct(&exec, 99)
+            );
+
+            drop(mmap);
+        }
+    }
+
+    Ok(())
+}
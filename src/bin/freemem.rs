@@ -0,0 +1,236 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, thread, time};
+
+// zram devices combined, in KB
+#[derive(Default)]
+struct Zram {
+    disksize: u64,
+    orig: u64,
+    compr: u64,
+    used: u64,
+}
+
+impl Zram {
+    fn collect() -> Self {
+        let mut zram = Zram::default();
+        for entry in fs::read_dir("/sys/block").into_iter().flatten().flatten() {
+            if !entry.file_name().to_string_lossy().starts_with("zram") {
+                continue;
+            }
+
+            let read_bytes = |name: &str| -> Vec<u64> {
+                fs::read_to_string(entry.path().join(name))
+                    .unwrap_or_default()
+                    .split_ascii_whitespace()
+                    .map(|val| val.parse::<u64>().unwrap_or_default() / 1024)
+                    .collect()
+            };
+
+            // mm_stat starts with orig_data_size, compr_data_size, and mem_used_total
+            let mm_stat = read_bytes("mm_stat");
+            let field = |idx: usize| mm_stat.get(idx).copied().unwrap_or_default();
+            zram.disksize += read_bytes("disksize").first().copied().unwrap_or_default();
+            zram.orig += field(0);
+            zram.compr += field(1);
+            zram.used += field(2);
+        }
+
+        zram
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Kilo,
+    Mega,
+    Giga,
+}
+
+impl Unit {
+    fn format(&self, kb: u64) -> String {
+        match self {
+            Unit::Kilo => kb.to_string(),
+            Unit::Mega => (kb / 1024).to_string(),
+            Unit::Giga => format!("{:.1}", kb as f64 / 1024.0 / 1024.0),
+        }
+    }
+}
+
+fn format_ratio(orig: u64, compr: u64) -> String {
+    if compr > 0 {
+        format!("{:.2}", orig as f64 / compr as f64)
+    } else {
+        "-".to_string()
+    }
+}
+
+fn print_summary(unit: Unit) -> Result<(), io::Error> {
+    let info = rustest::Meminfo::collect()?;
+    let zram = Zram::collect();
+    let fmt = |kb| unit.format(kb);
+
+    println!(
+        "{:7}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}",
+        "", "total", "used", "free", "shared", "buff/cache", "available"
+    );
+    println!(
+        "{:7}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}",
+        "Mem:",
+        fmt(info.mem_total),
+        fmt(info.used()),
+        fmt(info.mem_free),
+        fmt(info.shmem),
+        fmt(info.buff_cache()),
+        fmt(info.mem_available)
+    );
+    println!(
+        "{:7}{:>12}{:>12}{:>12}",
+        "Swap:",
+        fmt(info.swap_total),
+        fmt(info.swap_total.saturating_sub(info.swap_free)),
+        fmt(info.swap_free)
+    );
+    println!(
+        "{:7}{:>12}{:>12}{:>12}{:>12}{:>12}",
+        "", "total", "stored", "compressed", "used", "ratio"
+    );
+    println!(
+        "{:7}{:>12}{:>12}{:>12}{:>12}{:>12}",
+        "Zram:",
+        fmt(zram.disksize),
+        fmt(zram.orig),
+        fmt(zram.compr),
+        fmt(zram.used),
+        format_ratio(zram.orig, zram.used)
+    );
+    println!(
+        "{:7}{:>12}{:>12}{:>12}{:>12}{:>12}",
+        "Zswap:",
+        "-",
+        fmt(info.zswapped),
+        fmt(info.zswap),
+        fmt(info.zswap),
+        format_ratio(info.zswapped, info.zswap)
+    );
+
+    let dmabuf = rustest::DmaBufInfo::collect(&info);
+    if dmabuf.is_present() {
+        let fmt_opt = |kb: Option<u64>| kb.map_or("-".to_string(), fmt);
+        println!("{:7}{:>12}{:>12}{:>12}", "", "heaps", "pools", "kreclaim");
+        println!(
+            "{:7}{:>12}{:>12}{:>12}",
+            "Dmabuf:",
+            fmt_opt(dmabuf.heaps),
+            fmt_opt(dmabuf.pools),
+            fmt(info.k_reclaimable_other())
+        );
+    }
+
+    Ok(())
+}
+
+fn emit_sample() -> Result<(), io::Error> {
+    let info = rustest::Meminfo::collect()?;
+    let zram = Zram::collect();
+    let dmabuf = rustest::DmaBufInfo::collect(&info);
+
+    rustest::JsonLine::new("sample")
+        .field("mem_total_kb", info.mem_total)
+        .field("mem_used_kb", info.used())
+        .field("mem_free_kb", info.mem_free)
+        .field("shared_kb", info.shmem)
+        .field("buff_cache_kb", info.buff_cache())
+        .field("mem_available_kb", info.mem_available)
+        .field("swap_total_kb", info.swap_total)
+        .field("swap_free_kb", info.swap_free)
+        .field("zram_disksize_kb", zram.disksize)
+        .field("zram_orig_kb", zram.orig)
+        .field("zram_compr_kb", zram.compr)
+        .field("zram_used_kb", zram.used)
+        .field("zswapped_kb", info.zswapped)
+        .field("zswap_kb", info.zswap)
+        .field("kreclaimable_kb", info.k_reclaimable)
+        .field("dmabuf_heaps_kb", dmabuf.heaps)
+        .field("dmabuf_pools_kb", dmabuf.pools)
+        .emit();
+
+    Ok(())
+}
+
+fn print_help() {
+    println!("usage: freemem [options]");
+    println!("  -k, -m, -g: show KB (default), MB, or GB");
+    println!("  -s SECS: repeat every SECS seconds");
+    println!("  -c COUNT: repeat COUNT times (default: forever with -s)");
+    println!("  --json: print a JSON line per sample, in KB");
+    println!("the dmabuf row shows Android DMA-BUF/ION heaps and pools and the non-slab");
+    println!("KReclaimable, and is omitted when the kernel does not report them");
+}
+
+struct Args {
+    unit: Unit,
+    interval: Option<time::Duration>,
+    count: Option<usize>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        unit: Unit::Kilo,
+        interval: None,
+        count: None,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-k" => args.unit = Unit::Kilo,
+            "-m" => args.unit = Unit::Mega,
+            "-g" => args.unit = Unit::Giga,
+            "-s" => {
+                args.interval = Some(rustest::parse_duration(&iter.next().unwrap_or_default())?)
+            }
+            "-c" => args.count = Some(rustest::parse_num(&iter.next().unwrap_or_default())?),
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let count = match (args.interval, args.count) {
+        (_, Some(count)) => count,
+        (Some(_), None) => usize::MAX,
+        (None, None) => 1,
+    };
+    let interval = args.interval.unwrap_or(time::Duration::from_secs(1));
+
+    for idx in 0..count {
+        if idx > 0 {
+            thread::sleep(interval);
+            if !args.json {
+                println!();
+            }
+        }
+
+        if args.json {
+            emit_sample()?;
+        } else {
+            print_summary(args.unit)?;
+        }
+    }
+
+    Ok(())
+}
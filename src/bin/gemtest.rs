@@ -0,0 +1,237 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io};
+
+// dumb buffers are 32bpp, 1024 pixels wide, and 256 lines per MB
+const WIDTH: u32 = 1024;
+const BPP: u32 = 32;
+const LINES_PER_MB: u32 = 256;
+
+// sums the drm-total-* and drm-resident-* keys of the fdinfo of fd, in KB
+fn drm_fdinfo(fd: i32) -> (u64, u64) {
+    let fdinfo = fs::read_to_string(format!("/proc/self/fdinfo/{}", fd)).unwrap_or_default();
+
+    // lines are in the form of "drm-total-system0:\t4096 KiB"
+    let mut total = 0;
+    let mut resident = 0;
+    for line in fdinfo.lines() {
+        let Some((key, val)) = line.split_once(':') else {
+            continue;
+        };
+
+        let mut iter = val.split_ascii_whitespace();
+        let val: u64 = iter
+            .next()
+            .and_then(|val| val.parse().ok())
+            .unwrap_or_default();
+        let kb = match iter.next() {
+            Some("KiB") => val,
+            Some("MiB") => val * 1024,
+            Some("GiB") => val * 1024 * 1024,
+            _ => val / 1024,
+        };
+
+        if key.starts_with("drm-total-") {
+            total += kb;
+        } else if key.starts_with("drm-resident-") {
+            resident += kb;
+        }
+    }
+
+    (total, resident)
+}
+
+// memory of this process and of the system, in KB
+struct Usage {
+    rss: u64,
+    rss_shmem: u64,
+    shmem: u64,
+    avail: u64,
+    drm_total: u64,
+    drm_resident: u64,
+}
+
+impl Usage {
+    fn collect(drm: &rustest::Drm) -> Self {
+        let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        let extract_val = |key: &str| -> u64 {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .and_then(|val| val.split_ascii_whitespace().next())
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default()
+        };
+
+        let info = rustest::Meminfo::collect().unwrap_or_default();
+        let (drm_total, drm_resident) = drm_fdinfo(drm.fd());
+
+        Usage {
+            rss: extract_val("VmRSS"),
+            rss_shmem: extract_val("RssShmem"),
+            shmem: info.shmem,
+            avail: info.mem_available,
+            drm_total,
+            drm_resident,
+        }
+    }
+
+    fn print(&self, phase: &str, gem_mb: usize, json: bool) {
+        if json {
+            rustest::JsonLine::new("phase")
+                .field("phase", phase)
+                .field("gem_mb", gem_mb)
+                .field("rss_kb", self.rss)
+                .field("rss_shmem_kb", self.rss_shmem)
+                .field("shmem_kb", self.shmem)
+                .field("mem_available_kb", self.avail)
+                .field("drm_total_kb", self.drm_total)
+                .field("drm_resident_kb", self.drm_resident)
+                .emit();
+            return;
+        }
+
+        println!(
+            "  {:12} {:>7} {:>7} {:>9} {:>8} {:>8} {:>9} {:>9}",
+            phase,
+            gem_mb,
+            self.rss / 1024,
+            self.rss_shmem / 1024,
+            self.shmem / 1024,
+            self.avail / 1024,
+            self.drm_total / 1024,
+            self.drm_resident / 1024
+        );
+    }
+}
+
+fn print_help() {
+    println!("usage: gemtest [options]");
+    println!("  --device PATH: DRM device supporting dumb buffers (default: /dev/dri/card0)");
+    println!("  --count N: number of buffers (default: 16)");
+    println!("  --size MB: size of each buffer (default: 16)");
+    println!("  --fill MODE: zero, byte, text, random[:SEED], mixed:PCT (default: random)");
+    println!("  --pressure MB: anonymous memory allocated after the buffers (default: 0)");
+    println!("  --json: print the phases as JSON lines");
+    println!("drm total/resident are from fdinfo and read 0 for drivers that do not report them");
+}
+
+struct Args {
+    device: String,
+    count: usize,
+    size_mb: usize,
+    fill: rustest::Fill,
+    pressure_mb: usize,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        device: String::from("/dev/dri/card0"),
+        count: 16,
+        size_mb: 16,
+        fill: rustest::Fill::Random(0),
+        pressure_mb: 0,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--device" => {
+                if let Some(device) = iter.next() {
+                    args.device = device;
+                }
+            }
+            "--count" => args.count = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--size" => args.size_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--fill" => {
+                let fill = iter.next().unwrap_or_default();
+                args.fill = rustest::Fill::parse(&fill).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid fill {}", fill),
+                    )
+                })?;
+            }
+            "--pressure" => args.pressure_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    args.size_mb = args.size_mb.max(1);
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let drm = rustest::Drm::open(&args.device)?;
+    let height = args.size_mb as u32 * LINES_PER_MB;
+
+    if !args.json {
+        println!(
+            "{}: {} dumb buffers of {} MB, filled with {}",
+            args.device, args.count, args.size_mb, args.fill
+        );
+        println!(
+            "  {:12} {:>7} {:>7} {:>9} {:>8} {:>8} {:>9} {:>9}",
+            "phase",
+            "gem MB",
+            "rss MB",
+            "rssshm MB",
+            "shmem MB",
+            "avail MB",
+            "drm total",
+            "drm resid"
+        );
+    }
+    Usage::collect(&drm).print("start", 0, args.json);
+
+    let mut bufs = Vec::with_capacity(args.count);
+    for _ in 0..args.count {
+        bufs.push(drm.create_dumb(WIDTH, height, BPP)?);
+    }
+    let gem_mb = bufs.len() * args.size_mb;
+    Usage::collect(&drm).print("created", gem_mb, args.json);
+
+    for (idx, buf) in bufs.iter_mut().enumerate() {
+        buf.mmap().fill_with(args.fill, (idx % 255 + 1) as u8);
+    }
+    Usage::collect(&drm).print("filled", gem_mb, args.json);
+
+    if args.pressure_mb > 0 {
+        let mut pressure = rustest::Mmap::anonymous(args.pressure_mb * 1024 * 1024)?;
+        pressure.fill_with(rustest::Fill::Random(1), 1);
+        Usage::collect(&drm).print("pressured", gem_mb, args.json);
+        drop(pressure);
+    }
+
+    let mut bad = 0;
+    for (idx, buf) in bufs.iter_mut().enumerate() {
+        bad += buf.mmap().verify_with(args.fill, (idx % 255 + 1) as u8);
+    }
+    Usage::collect(&drm).print("verified", gem_mb, args.json);
+
+    bufs.truncate(bufs.len() / 2);
+    Usage::collect(&drm).print("freed half", bufs.len() * args.size_mb, args.json);
+
+    bufs.clear();
+    Usage::collect(&drm).print("freed all", 0, args.json);
+
+    if args.json {
+        rustest::JsonLine::new("summary").field("bad", bad).emit();
+    } else {
+        println!("bad pages: {}", bad);
+    }
+
+    Ok(())
+}
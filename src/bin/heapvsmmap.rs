@@ -0,0 +1,254 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, ptr, slice};
+
+#[derive(Clone, Copy)]
+enum Mode {
+    // malloc below the mmap threshold, which glibc serves from the brk heap
+    Heap,
+    // one rustest::Mmap per chunk
+    Mmap,
+}
+
+impl Mode {
+    const ALL: [Mode; 2] = [Mode::Heap, Mode::Mmap];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Heap => "heap",
+            Mode::Mmap => "mmap",
+        }
+    }
+}
+
+enum Chunk {
+    Heap(ptr::NonNull<u8>, usize),
+    Mmap(rustest::Mmap),
+}
+
+impl Chunk {
+    fn alloc(mode: Mode, len: usize, val: u8) -> Result<Self, io::Error> {
+        let mut chunk = match mode {
+            Mode::Heap => {
+                // SAFETY: valid malloc call
+                let ptr = unsafe { libc::malloc(len) };
+                let ptr = ptr::NonNull::new(ptr as *mut u8).ok_or(io::ErrorKind::OutOfMemory)?;
+                Chunk::Heap(ptr, len)
+            }
+            Mode::Mmap => Chunk::Mmap(rustest::Mmap::anonymous(len)?),
+        };
+        chunk.as_bytes_mut().fill(val);
+
+        Ok(chunk)
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        match self {
+            // SAFETY: the block is owned and valid for len bytes
+            Chunk::Heap(ptr, len) => unsafe { slice::from_raw_parts_mut(ptr.as_ptr(), *len) },
+            Chunk::Mmap(mmap) => mmap.as_bytes_mut(),
+        }
+    }
+
+    // pages out the whole pages of the chunk
+    fn pageout(&mut self) {
+        let page_size = rustest::page_size();
+        let bytes = self.as_bytes_mut();
+        let addr = bytes.as_mut_ptr() as usize;
+        let start = addr.next_multiple_of(page_size);
+        let end = (addr + bytes.len()) / page_size * page_size;
+        if start >= end {
+            return;
+        }
+
+        // SAFETY: the page-aligned range lies within the chunk
+        unsafe { libc::madvise(start as _, end - start, libc::MADV_PAGEOUT) };
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        if let Chunk::Heap(ptr, _) = self {
+            // SAFETY: the block is from malloc and freed once
+            unsafe { libc::free(ptr.as_ptr() as _) };
+        }
+    }
+}
+
+// memory of this process, in KB
+struct Usage {
+    rss: u64,
+    swap: u64,
+    data: u64,
+    heap: u64,
+}
+
+impl Usage {
+    fn collect() -> Self {
+        let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        let extract_val = |key: &str| -> u64 {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .and_then(|val| val.split_ascii_whitespace().next())
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default()
+        };
+
+        let heap = rustest::smaps("self")
+            .unwrap_or_default()
+            .iter()
+            .find(|vma| vma.name == "[heap]")
+            .map_or(0, |vma| vma.usage.size);
+
+        Usage {
+            rss: extract_val("VmRSS"),
+            swap: extract_val("VmSwap"),
+            data: extract_val("VmData"),
+            heap,
+        }
+    }
+
+    fn print(&self, mode: Mode, phase: &str, json: bool) {
+        if json {
+            rustest::JsonLine::new("phase")
+                .field("mode", mode.name())
+                .field("phase", phase)
+                .field("rss_kb", self.rss)
+                .field("swap_kb", self.swap)
+                .field("data_kb", self.data)
+                .field("heap_kb", self.heap)
+                .emit();
+            return;
+        }
+
+        println!(
+            "  {:12} {:>8} {:>8} {:>8} {:>8}",
+            phase,
+            self.rss / 1024,
+            self.swap / 1024,
+            self.data / 1024,
+            self.heap / 1024
+        );
+    }
+}
+
+fn run(mode: Mode, args: &Args) -> Result<(), io::Error> {
+    let chunk_len = args.chunk_kb * 1024;
+    let count = args.size_mb * 1024 / args.chunk_kb;
+
+    if !args.json {
+        println!("{}: {} chunks of {} KB", mode.name(), count, args.chunk_kb);
+        println!(
+            "  {:12} {:>8} {:>8} {:>8} {:>8}",
+            "phase", "rss MB", "swap MB", "data MB", "heap MB"
+        );
+    }
+    Usage::collect().print(mode, "start", args.json);
+
+    let mut chunks = Vec::with_capacity(count);
+    for idx in 0..count {
+        chunks.push(Some(Chunk::alloc(mode, chunk_len, (idx % 255 + 1) as u8)?));
+    }
+    Usage::collect().print(mode, "grown", args.json);
+
+    for chunk in chunks.iter_mut().flatten() {
+        chunk.pageout();
+    }
+    Usage::collect().print(mode, "paged out", args.json);
+
+    // every other chunk, which fragments the heap
+    for chunk in chunks.iter_mut().step_by(2) {
+        *chunk = None;
+    }
+    Usage::collect().print(mode, "freed half", args.json);
+
+    // everything but the last chunk, which pins the top of the heap
+    let last = chunks.pop();
+    chunks.clear();
+    Usage::collect().print(mode, "kept last", args.json);
+
+    if let Mode::Heap = mode {
+        // SAFETY: valid malloc_trim call
+        unsafe { libc::malloc_trim(0) };
+        Usage::collect().print(mode, "trimmed", args.json);
+    }
+
+    drop(last);
+    Usage::collect().print(mode, "freed all", args.json);
+
+    Ok(())
+}
+
+fn print_help() {
+    println!("usage: heapvsmmap [options] [SIZE_MB]");
+    println!("  SIZE_MB: memory to allocate in each mode (default: 256)");
+    println!("  --chunk KB: size of each allocation (default: 64)");
+    println!("  --mode MODE: heap or mmap (default: both)");
+    println!("  --json: print the phases as JSON lines");
+    println!("note: heap chunks must stay below the malloc mmap threshold (128 KB by default)");
+}
+
+struct Args {
+    size_mb: usize,
+    chunk_kb: usize,
+    mode: Option<String>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        size_mb: 256,
+        chunk_kb: 64,
+        mode: None,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--chunk" => args.chunk_kb = rustest::parse_kb(&iter.next().unwrap_or_default())?,
+            "--mode" => args.mode = iter.next(),
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.size_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    args.chunk_kb = args.chunk_kb.max(1);
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let modes: Vec<Mode> = Mode::ALL
+        .into_iter()
+        .filter(|mode| args.mode.as_deref().is_none_or(|name| name == mode.name()))
+        .collect();
+    if modes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid mode {}", args.mode.unwrap_or_default()),
+        ));
+    }
+
+    // both the heap and private mappings count against RLIMIT_DATA
+    rustest::Rlimit::Data.reserve(Some((args.size_mb * 1024 * 1024) as u64))?;
+
+    for mode in modes {
+        run(mode, &args)?;
+        if !args.json {
+            println!();
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,272 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, time};
+
+const MEMORY_DIR: &str = "/sys/devices/system/memory";
+// kernel messages shown per attempt, as dump_page with page_owner can be long
+const MAX_KMSG_LINES: usize = 32;
+
+struct Block {
+    index: usize,
+    state: String,
+    // zones the block can be onlined to, or the zone it is in when online
+    zones: String,
+}
+
+impl Block {
+    fn collect(index: usize) -> Self {
+        let read = |name: &str| {
+            fs::read_to_string(format!("{}/memory{}/{}", MEMORY_DIR, index, name))
+                .map(|val| val.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        Block {
+            index,
+            state: read("state"),
+            zones: read("valid_zones"),
+        }
+    }
+
+    fn set_state(&self, state: &str) -> Result<(), io::Error> {
+        fs::write(format!("{}/memory{}/state", MEMORY_DIR, self.index), state)
+    }
+}
+
+// returns all memory blocks, sorted by index
+fn blocks() -> Result<Vec<Block>, io::Error> {
+    let mut indices = Vec::new();
+    for entry in fs::read_dir(MEMORY_DIR)? {
+        let name = entry?.file_name();
+        if let Some(index) = name
+            .to_string_lossy()
+            .strip_prefix("memory")
+            .and_then(|index| index.parse::<usize>().ok())
+        {
+            indices.push(index);
+        }
+    }
+    indices.sort();
+
+    Ok(indices.into_iter().map(Block::collect).collect())
+}
+
+fn block_size() -> u64 {
+    fs::read_to_string(format!("{}/block_size_bytes", MEMORY_DIR))
+        .ok()
+        .and_then(|val| u64::from_str_radix(val.trim(), 16).ok())
+        .unwrap_or_default()
+}
+
+fn mem_total_kb() -> u64 {
+    fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| {
+            meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix("MemTotal:"))
+                .and_then(|val| val.split_ascii_whitespace().next())
+                .and_then(|val| val.parse().ok())
+        })
+        .unwrap_or_default()
+}
+
+// picks online blocks from the end of memory, preferring those in ZONE_MOVABLE
+fn pick_blocks(blocks: &[Block], count: usize) -> Vec<usize> {
+    let mut online: Vec<&Block> = blocks
+        .iter()
+        .rev()
+        .filter(|block| block.state == "online")
+        .collect();
+    online.sort_by_key(|block| !block.zones.contains("Movable"));
+
+    online.iter().take(count).map(|block| block.index).collect()
+}
+
+fn print_help() {
+    println!("usage: hotplug [options]");
+    println!("  --list: only list the memory blocks");
+    println!("  --blocks N,...: blocks to offline (default: picked from the end of memory)");
+    println!("  --count N: number of blocks to pick (default: 1)");
+    println!("  --keep-offline: do not online the blocks again");
+    println!("  --json: print the blocks and the results as JSON lines");
+    println!("note: booting with page_owner=on explains unmovable pages in the kernel log");
+}
+
+struct Args {
+    list: bool,
+    blocks: Vec<usize>,
+    count: usize,
+    keep_offline: bool,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        list: false,
+        blocks: Vec::new(),
+        count: 1,
+        keep_offline: false,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--list" => args.list = true,
+            "--blocks" => {
+                let list = iter.next().unwrap_or_default();
+                args.blocks = list
+                    .split(',')
+                    .filter(|index| !index.is_empty())
+                    .map(|index| {
+                        index.parse().map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!("invalid block {}", index),
+                            )
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--count" => args.count = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--keep-offline" => args.keep_offline = true,
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let blocks = blocks()?;
+    if args.json {
+        rustest::JsonLine::new("config")
+            .field("blocks", blocks.len())
+            .field("block_size_bytes", block_size())
+            .field("mem_total_kb", mem_total_kb())
+            .emit();
+    } else {
+        println!(
+            "{} blocks of {} MB, MemTotal {} MB",
+            blocks.len(),
+            block_size() / 1024 / 1024,
+            mem_total_kb() / 1024
+        );
+    }
+
+    if args.list {
+        for block in &blocks {
+            if args.json {
+                rustest::JsonLine::new("block")
+                    .field("index", block.index)
+                    .field("state", &block.state)
+                    .field("zones", &block.zones)
+                    .emit();
+            } else {
+                println!(
+                    "  memory{:<5} {:8} {}",
+                    block.index, block.state, block.zones
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    let picked = if args.blocks.is_empty() {
+        pick_blocks(&blocks, args.count)
+    } else {
+        args.blocks.clone()
+    };
+
+    let mut kmsg = rustest::Kmsg::open()
+        .inspect_err(|err| {
+            if !args.json {
+                println!("not watching /dev/kmsg: {}", err)
+            }
+        })
+        .ok();
+
+    let mut failed = 0;
+    for index in picked {
+        let block = Block::collect(index);
+        if !args.json {
+            println!();
+            println!(
+                "memory{}: {}, zones {}",
+                block.index, block.state, block.zones
+            );
+        }
+
+        let start = time::Instant::now();
+        let res = block.set_state("offline");
+        let elapsed = start.elapsed();
+        if res.is_err() {
+            failed += 1;
+        }
+        let msgs = kmsg.as_mut().map(|kmsg| kmsg.read()).unwrap_or_default();
+        let online_res = (res.is_ok() && !args.keep_offline).then(|| block.set_state("online"));
+
+        if args.json {
+            let msgs = &msgs[..msgs.len().min(MAX_KMSG_LINES)];
+            rustest::JsonLine::new("result")
+                .field("index", block.index)
+                .field("zones", &block.zones)
+                .field("offline_ns", elapsed)
+                .field("offline_error", res.err().map(|err| err.to_string()))
+                .field(
+                    "online_error",
+                    online_res
+                        .and_then(|res| res.err())
+                        .map(|err| err.to_string()),
+                )
+                .field("mem_total_kb", mem_total_kb())
+                .field("kmsg", msgs)
+                .emit();
+            continue;
+        }
+
+        match &res {
+            Ok(()) => println!(
+                "  offlined in {:?}, MemTotal {} MB",
+                elapsed,
+                mem_total_kb() / 1024
+            ),
+            Err(err) => println!("  failed to offline after {:?}: {}", elapsed, err),
+        }
+
+        for msg in msgs.iter().take(MAX_KMSG_LINES) {
+            println!("  kmsg: {}", msg);
+        }
+        if msgs.len() > MAX_KMSG_LINES {
+            println!("  kmsg: ... {} more lines", msgs.len() - MAX_KMSG_LINES);
+        }
+
+        match online_res {
+            Some(Ok(())) => println!("  onlined, zones {}", Block::collect(index).zones),
+            Some(Err(err)) => println!("  failed to online: {}", err),
+            None => (),
+        }
+    }
+
+    if args.json {
+        rustest::JsonLine::new("summary")
+            .field("failed", failed)
+            .emit();
+        return Ok(());
+    }
+
+    println!();
+    println!("{} failed to offline", failed);
+
+    Ok(())
+}
@@ -0,0 +1,248 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, ptr, thread, time};
+
+const KSM_DIR: &str = "/sys/kernel/mm/ksm";
+const REPORT_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+// knobs printed at start
+const KNOBS: [&str; 4] = [
+    "run",
+    "pages_to_scan",
+    "sleep_millisecs",
+    "merge_across_nodes",
+];
+
+fn read_ksm(name: &str) -> u64 {
+    fs::read_to_string(format!("{}/{}", KSM_DIR, name))
+        .ok()
+        .and_then(|val| val.trim().parse().ok())
+        .unwrap_or_default()
+}
+
+// system-wide ksm counters
+struct Sample {
+    pages_shared: u64,
+    pages_sharing: u64,
+    pages_unshared: u64,
+    pages_volatile: u64,
+    full_scans: u64,
+}
+
+impl Sample {
+    fn collect() -> Self {
+        Sample {
+            pages_shared: read_ksm("pages_shared"),
+            pages_sharing: read_ksm("pages_sharing"),
+            pages_unshared: read_ksm("pages_unshared"),
+            pages_volatile: read_ksm("pages_volatile"),
+            full_scans: read_ksm("full_scans"),
+        }
+    }
+}
+
+// returns the number of pages of the process that are merged
+fn ksm_merging_pages(pid: &str) -> u64 {
+    fs::read_to_string(format!("/proc/{}/ksm_merging_pages", pid))
+        .ok()
+        .and_then(|val| val.trim().parse().ok())
+        .unwrap_or_default()
+}
+
+// allocates mergeable mappings whose pages cycle through distinct contents
+fn allocate(args: &Args) -> Result<Vec<rustest::Mmap>, io::Error> {
+    let page_size = rustest::page_size();
+
+    let mut mmaps = Vec::new();
+    for _ in 0..args.mappings {
+        let mut mmap = rustest::Mmap::anonymous(args.size_mb * 1024 * 1024)?;
+        mmap.madvise(libc::MADV_MERGEABLE)?;
+        for (idx, page) in mmap.as_bytes_mut().chunks_mut(page_size).enumerate() {
+            // 0 would be merged with the zero page when use_zero_pages is set
+            page.fill((idx % args.distinct) as u8 + 1);
+        }
+        mmaps.push(mmap);
+    }
+
+    Ok(mmaps)
+}
+
+// a forked child that holds its own mappings until killed
+struct Child {
+    pid: libc::pid_t,
+}
+
+impl Child {
+    fn spawn(args: &Args) -> Result<Self, io::Error> {
+        // SAFETY: we are single-threaded and the child never returns
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            return Err(io::Error::last_os_error());
+        } else if pid == 0 {
+            Self::serve(args);
+        }
+
+        Ok(Child { pid })
+    }
+
+    fn serve(args: &Args) -> ! {
+        let _mmaps = allocate(args);
+
+        loop {
+            // SAFETY: valid pause call
+            unsafe { libc::pause() };
+        }
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        // SAFETY: valid kill call
+        unsafe { libc::kill(self.pid, libc::SIGKILL) };
+        // SAFETY: valid waitpid call
+        unsafe { libc::waitpid(self.pid, ptr::null_mut(), 0) };
+    }
+}
+
+fn print_help() {
+    println!("usage: ksmtest [options]");
+    println!("  --size MB: size of each mapping (default: 64)");
+    println!("  --mappings N: number of mappings per process (default: 4)");
+    println!("  --distinct N: number of distinct page contents, up to 255 (default: 1)");
+    println!("  --children N: forked children that allocate the same mappings (default: 0)");
+    println!("  --duration SECS: how long to watch the counters (default: 30)");
+    println!("  --start: write 1 to {}/run", KSM_DIR);
+    println!("  --json: print samples as JSON lines");
+}
+
+struct Args {
+    size_mb: usize,
+    mappings: usize,
+    distinct: usize,
+    children: usize,
+    duration: time::Duration,
+    start: bool,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        size_mb: 64,
+        mappings: 4,
+        distinct: 1,
+        children: 0,
+        duration: time::Duration::from_secs(30),
+        start: false,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--size" => args.size_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--mappings" => args.mappings = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--distinct" => args.distinct = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--children" => args.children = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--duration" => {
+                args.duration = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--start" => args.start = true,
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    args.distinct = args.distinct.clamp(1, 255);
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    if args.start {
+        fs::write(format!("{}/run", KSM_DIR), "1")?;
+    }
+
+    let page_count = args.size_mb * 1024 * 1024 / rustest::page_size() * args.mappings;
+    if args.json {
+        let mut line = rustest::JsonLine::new("config");
+        for knob in KNOBS {
+            line.push(knob, read_ksm(knob));
+        }
+        line.push("processes", args.children + 1);
+        line.push("pages", page_count);
+        line.push("distinct", args.distinct);
+        line.emit();
+    } else {
+        let knobs: Vec<String> = KNOBS
+            .iter()
+            .map(|knob| format!("{} {}", knob, read_ksm(knob)))
+            .collect();
+        println!("ksm: {}", knobs.join(", "));
+        if read_ksm("run") != 1 {
+            println!("warning: ksm is not running, try --start");
+        }
+
+        println!(
+            "{} processes with {} mergeable pages each, {} distinct contents",
+            args.children + 1,
+            page_count,
+            args.distinct
+        );
+        println!();
+    }
+
+    let base = Sample::collect();
+    let mut children = Vec::new();
+    for _ in 0..args.children {
+        children.push(Child::spawn(&args)?);
+    }
+    let _mmaps = allocate(&args)?;
+
+    let start = time::Instant::now();
+    while start.elapsed() < args.duration {
+        thread::sleep(REPORT_INTERVAL);
+
+        let cur = Sample::collect();
+        let merging: u64 = ksm_merging_pages("self")
+            + children
+                .iter()
+                .map(|child| ksm_merging_pages(&child.pid.to_string()))
+                .sum::<u64>();
+
+        if args.json {
+            rustest::JsonLine::new("sample")
+                .field("elapsed_ns", start.elapsed())
+                .field("pages_shared", cur.pages_shared)
+                .field("pages_sharing", cur.pages_sharing)
+                .field("pages_unshared", cur.pages_unshared)
+                .field("pages_volatile", cur.pages_volatile)
+                .field("full_scans", cur.full_scans - base.full_scans)
+                .field("merging", merging)
+                .field("mergeable", page_count * (args.children + 1))
+                .emit();
+            continue;
+        }
+
+        println!(
+            "{:5.1}s: shared {:7}, sharing {:7}, unshared {:7}, volatile {:7}, full scans +{}, merging {} of {}",
+            start.elapsed().as_secs_f64(),
+            cur.pages_shared,
+            cur.pages_sharing,
+            cur.pages_unshared,
+            cur.pages_volatile,
+            cur.full_scans - base.full_scans,
+            merging,
+            page_count * (args.children + 1)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,223 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    path, process, thread, time,
+};
+
+const REPORT_INTERVAL: time::Duration = time::Duration::from_secs(1);
+const ALLOC_SIZE: usize = 64 * 1024;
+const FILE_SIZE: usize = 4096;
+
+enum Step {
+    // reads the working set
+    Touch,
+    // maps and dirties a small anonymous region
+    Alloc,
+    // writes and fsyncs a small file
+    File,
+}
+
+impl Step {
+    const ALL: [Step; 3] = [Step::Touch, Step::Alloc, Step::File];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Step::Touch => "touch",
+            Step::Alloc => "alloc",
+            Step::File => "file",
+        }
+    }
+}
+
+// latencies of a step
+#[derive(Clone, Default)]
+struct Stat {
+    hist: rustest::Histogram,
+    max: time::Duration,
+}
+
+impl Stat {
+    fn record(&mut self, dur: time::Duration) {
+        self.hist.record(dur);
+        self.max = self.max.max(dur);
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.hist.add(&other.hist);
+        self.max = self.max.max(other.max);
+    }
+}
+
+struct Probe {
+    wss: rustest::Mmap,
+    path: path::PathBuf,
+    buf: Vec<u8>,
+}
+
+impl Probe {
+    fn new(args: &Args) -> Result<Self, io::Error> {
+        let mut wss = rustest::Mmap::anonymous(args.wss_mb * 1024 * 1024)?;
+        wss.fill(1);
+
+        Ok(Probe {
+            wss,
+            path: path::Path::new(&args.dir).join(format!("latprobe-{}", process::id())),
+            buf: vec![1; FILE_SIZE],
+        })
+    }
+
+    fn run(&self, step: &Step) -> Result<(), io::Error> {
+        match step {
+            Step::Touch => self.wss.populate(),
+            Step::Alloc => rustest::Mmap::anonymous(ALLOC_SIZE)?.fill(1),
+            Step::File => {
+                let mut fp = fs::File::create(&self.path)?;
+                fp.write_all(&self.buf)?;
+                fp.sync_all()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Probe {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn print_help() {
+    println!("usage: latprobe [options]");
+    println!("  --wss MB: working set touched by each probe (default: 16)");
+    println!("  --interval MS: time between probes (default: 100)");
+    println!("  --duration SECS: how long to probe (default: 60)");
+    println!("  --dir DIR: where the probe file is written (default: /var/tmp)");
+    println!("  --json: print samples and the summary as JSON lines");
+    println!("run other tools alongside to measure how their pressure delays the probes");
+}
+
+struct Args {
+    wss_mb: usize,
+    interval: time::Duration,
+    duration: time::Duration,
+    dir: String,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        wss_mb: 16,
+        interval: time::Duration::from_millis(100),
+        duration: time::Duration::from_secs(60),
+        dir: String::from("/var/tmp"),
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--wss" => args.wss_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--interval" => {
+                let ms = rustest::parse_num(&iter.next().unwrap_or_default())?;
+                args.interval = time::Duration::from_millis(ms);
+            }
+            "--duration" => {
+                args.duration = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--dir" => args.dir = iter.next().unwrap_or(args.dir),
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let probe = Probe::new(&args)?;
+
+    let mut totals = vec![Stat::default(); Step::ALL.len()];
+    let mut stats = totals.clone();
+    let start = time::Instant::now();
+    let mut next_report = REPORT_INTERVAL;
+    while start.elapsed() < args.duration {
+        for (step, stat) in Step::ALL.iter().zip(&mut stats) {
+            let begin = time::Instant::now();
+            probe.run(step)?;
+            stat.record(begin.elapsed());
+        }
+
+        if start.elapsed() >= next_report {
+            if args.json {
+                let mut line =
+                    rustest::JsonLine::new("sample").field("elapsed_ns", start.elapsed());
+                for (step, stat) in Step::ALL.iter().zip(&stats) {
+                    line.push(step.name(), &stat.hist);
+                    line.push(&format!("{}_max_ns", step.name()), stat.max);
+                }
+                line.emit();
+            } else {
+                let row: Vec<String> = Step::ALL
+                    .iter()
+                    .zip(&stats)
+                    .map(|(step, stat)| {
+                        format!(
+                            "{} p99 < {:>8} max {:>8}",
+                            step.name(),
+                            rustest::Histogram::format_ns(stat.hist.percentile(99)),
+                            rustest::Histogram::format_ns(stat.max.as_nanos() as u64)
+                        )
+                    })
+                    .collect();
+                println!("{:5.1}s: {}", start.elapsed().as_secs_f64(), row.join(", "));
+            }
+
+            for (total, stat) in totals.iter_mut().zip(&mut stats) {
+                total.add(stat);
+                *stat = Stat::default();
+            }
+            next_report += REPORT_INTERVAL;
+        }
+
+        thread::sleep(args.interval);
+    }
+
+    for (total, stat) in totals.iter_mut().zip(&stats) {
+        total.add(stat);
+    }
+
+    if args.json {
+        let mut line = rustest::JsonLine::new("summary");
+        for (step, total) in Step::ALL.iter().zip(&totals) {
+            line.push(step.name(), &total.hist);
+            line.push(&format!("{}_max_ns", step.name()), total.max);
+        }
+        line.emit();
+
+        return Ok(());
+    }
+
+    println!();
+    for (step, total) in Step::ALL.iter().zip(&totals) {
+        println!(
+            "{}: {}, max {}",
+            step.name(),
+            total.hist,
+            rustest::Histogram::format_ns(total.max.as_nanos() as u64)
+        );
+        total.hist.print();
+    }
+
+    Ok(())
+}
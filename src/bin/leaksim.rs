@@ -0,0 +1,203 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, thread, time};
+
+const CHUNK_SIZE_MB: usize = 1;
+
+fn rss_kb() -> u64 {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("VmRSS:"))
+                .and_then(|val| val.split_ascii_whitespace().next())
+                .and_then(|val| val.parse().ok())
+        })
+        .unwrap_or_default()
+}
+
+fn print_help() {
+    println!("usage: leaksim [options]");
+    println!("  --rate MB/min: how fast memory leaks (default: 60)");
+    println!("  --limit MB: stop leaking and hold once this much has leaked (default: none)");
+    println!("  --duration SECS: how long to run (default: until killed)");
+    println!("  --fill MODE: zero, byte, text, random[:SEED], mixed:PCT (default: random)");
+    println!("  --cgroup NAME: create and enter a cgroup first");
+    println!("  --max MB: memory.max of the cgroup (default: unchanged)");
+    println!("  --nice N: nice value, -20 to 19 (default: unchanged)");
+    println!("  --ionice CLASS[:LEVEL]: io priority, idle, be:0-7, or rt:0-7 (default: unchanged)");
+    println!("  --interval SECS: time between reports (default: 10)");
+    println!("  --json: print reports as JSON lines");
+}
+
+struct Args {
+    rate: f64,
+    limit_mb: Option<usize>,
+    duration: Option<time::Duration>,
+    fill: rustest::Fill,
+    cgroup: Option<String>,
+    max_mb: Option<u64>,
+    nice: Option<i32>,
+    ioprio: Option<rustest::IoPrio>,
+    interval: time::Duration,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        rate: 60.0,
+        limit_mb: None,
+        duration: None,
+        fill: rustest::Fill::Random(0),
+        cgroup: None,
+        max_mb: None,
+        nice: None,
+        ioprio: None,
+        interval: time::Duration::from_secs(10),
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--rate" => {
+                let rate = iter.next().unwrap_or_default();
+                args.rate = rate
+                    .parse()
+                    .ok()
+                    .filter(|&rate| rate > 0.0)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("invalid rate {}", rate),
+                        )
+                    })?;
+            }
+            "--limit" => args.limit_mb = Some(rustest::parse_mb(&iter.next().unwrap_or_default())?),
+            "--duration" => {
+                args.duration = Some(rustest::parse_duration(&iter.next().unwrap_or_default())?)
+            }
+            "--fill" => {
+                let fill = iter.next().unwrap_or_default();
+                args.fill = rustest::Fill::parse(&fill).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid fill {}", fill),
+                    )
+                })?;
+            }
+            "--cgroup" => args.cgroup = iter.next(),
+            "--max" => args.max_mb = Some(rustest::parse_mb(&iter.next().unwrap_or_default())?),
+            "--nice" => args.nice = Some(rustest::parse_nice(&iter.next().unwrap_or_default())?),
+            "--ionice" => {
+                args.ioprio = Some(rustest::IoPrio::parse(&iter.next().unwrap_or_default())?)
+            }
+            "--interval" => {
+                let interval = rustest::parse_duration(&iter.next().unwrap_or_default())?;
+                if !interval.is_zero() {
+                    args.interval = interval;
+                }
+            }
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    rustest::set_priority(args.nice, args.ioprio)?;
+
+    let cgroup = match &args.cgroup {
+        Some(name) => {
+            let mut cgroup = rustest::Cgroup::create(name)?;
+            if let Some(max_mb) = args.max_mb {
+                cgroup.write_value("memory.max", max_mb * 1024 * 1024)?;
+            }
+            cgroup.enter()?;
+            Some(cgroup)
+        }
+        None => None,
+    };
+
+    if !args.json {
+        println!(
+            "leaking {} memory at {} MB/min{}",
+            args.fill,
+            args.rate,
+            cgroup
+                .as_ref()
+                .map_or(String::new(), |cgroup| format!(" in {}", cgroup.name()))
+        );
+    }
+
+    let chunk_interval = time::Duration::from_secs_f64(CHUNK_SIZE_MB as f64 * 60.0 / args.rate);
+    let mut leaked = Vec::new();
+    let start = time::Instant::now();
+    let mut next_leak = time::Duration::ZERO;
+    let mut next_report = time::Duration::ZERO;
+    loop {
+        let elapsed = start.elapsed();
+        if args.duration.is_some_and(|duration| elapsed >= duration) {
+            break;
+        }
+
+        let leaked_mb = leaked.len() * CHUNK_SIZE_MB;
+        let holding = args.limit_mb.is_some_and(|limit| leaked_mb >= limit);
+        if !holding && elapsed >= next_leak {
+            let mut mmap = rustest::Mmap::anonymous(CHUNK_SIZE_MB * 1024 * 1024)?;
+            // vary the content between chunks
+            mmap.fill_with(args.fill, (leaked.len() % 255 + 1) as u8);
+            leaked.push(mmap);
+            next_leak += chunk_interval;
+            continue;
+        }
+
+        if elapsed >= next_report {
+            let current = cgroup
+                .as_ref()
+                .map(|cgroup| cgroup.read_value("memory.current").unwrap_or_default());
+            if args.json {
+                rustest::JsonLine::new("sample")
+                    .field("elapsed_ns", elapsed)
+                    .field("leaked_mb", leaked_mb)
+                    .field("holding", holding)
+                    .field("rss_kb", rss_kb())
+                    .field("memory_current_bytes", current)
+                    .emit();
+            } else {
+                let current = current.map_or(String::new(), |current| {
+                    format!(", memory.current {:5} MB", current / 1024 / 1024)
+                });
+                println!(
+                    "{:8.1}s: leaked {:5} MB{}, rss {:5} MB{}",
+                    elapsed.as_secs_f64(),
+                    leaked_mb,
+                    if holding { " (holding)" } else { "" },
+                    rss_kb() / 1024,
+                    current
+                );
+            }
+            next_report += args.interval;
+        }
+
+        let next = if holding {
+            next_report
+        } else {
+            next_leak.min(next_report)
+        };
+        thread::sleep(next.saturating_sub(start.elapsed()));
+    }
+
+    Ok(())
+}
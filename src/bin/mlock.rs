@@ -1,78 +1,831 @@
 // Copyright 2025 Google LLC
 // SPDX-License-Identifier: MIT
 
-use crossterm::event;
+use crossterm::{event, style::Stylize};
 use std::{
+    collections::{HashMap, VecDeque},
     env, fmt, fs,
-    io::{self, BufRead},
+    io::{self, BufRead, Read, Write},
+    mem,
+    os::unix::{fs::FileTypeExt, net},
+    process, ptr, time,
 };
 
+// default size of the mappings that heaps grow and shrink by
 const CHUNK_SIZE_MB: usize = 256;
+const HISTORY_LEN: usize = 60;
+const LOG_ROWS: usize = 5;
+// events included in the http status
+const STATUS_EVENTS: usize = 20;
+// trace events logged per sample, beyond which they are only counted
+const TRACE_EVENTS: usize = 20;
+const PSI_SPIKE_PCT: f64 = 10.0;
+const OOM_ADJ_STEP: i32 = 100;
+// how often the screen is redrawn without keys or script actions
+const POLL_TIMEOUT_MS: i32 = 1000;
+// exit status when an --exit-when threshold is reached
+const EXIT_THRESHOLD: u8 = 2;
 
-enum MlockHeap {
-    Locked,
-    Unlocked,
+#[derive(Clone, Copy)]
+enum HugeMode {
+    None,
+    Thp,
+    Hugetlb,
+}
+
+impl HugeMode {
+    fn next(self) -> Self {
+        match self {
+            HugeMode::None => HugeMode::Thp,
+            HugeMode::Thp => HugeMode::Hugetlb,
+            HugeMode::Hugetlb => HugeMode::None,
+        }
+    }
+}
+
+impl fmt::Display for HugeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let name = match self {
+            HugeMode::None => "none",
+            HugeMode::Thp => "thp",
+            HugeMode::Hugetlb => "hugetlb",
+        };
+        f.write_str(name)
+    }
+}
+
+struct HeapSpec {
+    name: String,
+    // mlock chunks after allocation
+    locked: bool,
+    // back chunks by unlinked files in the file dir
+    file: bool,
+    // overrides the global hugepage mode for anonymous chunks
+    huge: Option<HugeMode>,
+    // madvise chunks after allocation
+    advice: Option<i32>,
+    // allocate each chunk in a forked child
+    child: bool,
+    // map anonymous chunks with MAP_NORESERVE
+    noreserve: bool,
+
+    add_keys: Vec<char>,
+    remove_keys: Vec<char>,
+}
+
+impl HeapSpec {
+    fn new(name: &str, locked: bool, file: bool, add_keys: &str, remove_keys: &str) -> Self {
+        HeapSpec {
+            name: name.to_string(),
+            locked,
+            file,
+            huge: None,
+            advice: None,
+            child: false,
+            noreserve: false,
+            add_keys: add_keys.chars().collect(),
+            remove_keys: remove_keys.chars().collect(),
+        }
+    }
+
+    fn defaults() -> Vec<Self> {
+        vec![
+            HeapSpec::new("locked", true, false, "+=", "-_"),
+            HeapSpec::new("unlocked", false, false, "]}", "[{"),
+            HeapSpec::new("file", true, true, ")0", "(9"),
+        ]
+    }
+
+    // parses NAME:PROP,PROP,...:ADD-KEYS/REMOVE-KEYS
+    fn parse(spec: &str) -> Result<Self, io::Error> {
+        let invalid = |what: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid heap spec {}: {}", spec, what),
+            )
+        };
+
+        let mut parts = spec.splitn(3, ':');
+        let name = parts.next().filter(|name| !name.is_empty());
+        let props = parts.next();
+        let keys = parts.next().and_then(|keys| keys.split_once('/'));
+        let (Some(name), Some(props), Some((add_keys, remove_keys))) = (name, props, keys) else {
+            return Err(invalid("expected NAME:PROPS:ADD/REMOVE"));
+        };
+
+        let mut heap = HeapSpec::new(name, false, false, add_keys, remove_keys);
+        for prop in props.split(',').filter(|prop| !prop.is_empty()) {
+            match prop {
+                "locked" => heap.locked = true,
+                "file" => heap.file = true,
+                "child" => heap.child = true,
+                "noreserve" => heap.noreserve = true,
+                "nohuge" => heap.huge = Some(HugeMode::None),
+                "thp" => heap.huge = Some(HugeMode::Thp),
+                "hugetlb" => heap.huge = Some(HugeMode::Hugetlb),
+                _ => {
+                    let advice = prop
+                        .strip_prefix("madv=")
+                        .and_then(parse_advice)
+                        .ok_or_else(|| invalid(prop))?;
+                    heap.advice = Some(advice);
+                }
+            }
+        }
+
+        if heap.add_keys.is_empty() || heap.remove_keys.is_empty() {
+            return Err(invalid("missing keys"));
+        }
+
+        Ok(heap)
+    }
+
+    // returns the spec in the form that parse accepts
+    fn to_spec(&self) -> String {
+        let mut props = Vec::new();
+        if self.locked {
+            props.push("locked".to_string());
+        }
+        if self.file {
+            props.push("file".to_string());
+        }
+        if self.child {
+            props.push("child".to_string());
+        }
+        if self.noreserve {
+            props.push("noreserve".to_string());
+        }
+        match self.huge {
+            Some(HugeMode::None) => props.push("nohuge".to_string()),
+            Some(huge) => props.push(huge.to_string()),
+            None => (),
+        }
+        if let Some(name) = self.advice.and_then(advice_name) {
+            props.push(format!("madv={}", name));
+        }
+
+        format!(
+            "{}:{}:{}/{}",
+            self.name,
+            props.join(","),
+            self.add_keys.iter().collect::<String>(),
+            self.remove_keys.iter().collect::<String>()
+        )
+    }
+
+    // locked anonymous chunks are populated by mlock and need no fill
+    fn needs_fill(&self) -> bool {
+        !self.locked || self.file
+    }
+}
+
+// advice accepted by madv= in heap specs
+const ADVICES: [(&str, i32); 9] = [
+    ("normal", libc::MADV_NORMAL),
+    ("random", libc::MADV_RANDOM),
+    ("sequential", libc::MADV_SEQUENTIAL),
+    ("willneed", libc::MADV_WILLNEED),
+    ("mergeable", libc::MADV_MERGEABLE),
+    ("hugepage", libc::MADV_HUGEPAGE),
+    ("nohugepage", libc::MADV_NOHUGEPAGE),
+    ("dontfork", libc::MADV_DONTFORK),
+    ("dontdump", libc::MADV_DONTDUMP),
+];
+
+fn parse_advice(name: &str) -> Option<i32> {
+    ADVICES
+        .iter()
+        .find_map(|(n, advice)| (*n == name).then_some(*advice))
+}
+
+fn advice_name(advice: i32) -> Option<&'static str> {
+    ADVICES
+        .iter()
+        .find_map(|(name, a)| (*a == advice).then_some(*name))
+}
+
+// a chunk owned by a forked child, driven by single-op commands over a pipe
+struct ChildChunk {
+    pid: libc::pid_t,
+    // closed on drop to tell the child to exit
+    cmd: Option<fs::File>,
+    reply: fs::File,
+    exited: bool,
+}
+
+impl ChildChunk {
+    const OP_PAGE_IN: u8 = b'i';
+    const OP_ADVISE: u8 = b'a';
+    const OP_VERIFY: u8 = b'v';
+
+    fn spawn<F>(create: F, fill: rustest::Fill) -> Result<Self, io::Error>
+    where
+        F: FnOnce() -> Result<rustest::Mmap, io::Error>,
+    {
+        let (cmd_rd, cmd_wr) = rustest::pipe()?;
+        let (reply_rd, reply_wr) = rustest::pipe()?;
+
+        // SAFETY: we are single-threaded and the child never returns
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            return Err(io::Error::last_os_error());
+        } else if pid == 0 {
+            drop(cmd_wr);
+            drop(reply_rd);
+            Self::serve(create, fill, cmd_rd, reply_wr);
+        }
+
+        let mut child = ChildChunk {
+            pid,
+            cmd: Some(cmd_wr),
+            reply: reply_rd,
+            exited: false,
+        };
+
+        match child.read_reply()? {
+            0 => Ok(child),
+            errno => Err(io::Error::from_raw_os_error(errno as i32)),
+        }
+    }
+
+    fn serve<F>(create: F, fill: rustest::Fill, mut cmd: fs::File, mut reply: fs::File) -> !
+    where
+        F: FnOnce() -> Result<rustest::Mmap, io::Error>,
+    {
+        let mut mmap = match create() {
+            Ok(mmap) => {
+                let _ = reply.write_all(&0u64.to_ne_bytes());
+                mmap
+            }
+            Err(err) => {
+                let errno = err.raw_os_error().unwrap_or(libc::ENOMEM);
+                let _ = reply.write_all(&(errno as u64).to_ne_bytes());
+                // SAFETY: valid _exit call
+                unsafe { libc::_exit(1) };
+            }
+        };
+
+        let mut op = [0u8; 2];
+        while cmd.read_exact(&mut op).is_ok() {
+            let val = match op[0] {
+                Self::OP_PAGE_IN => {
+                    mmap.populate_parallel(op[1].max(1) as usize);
+                    0
+                }
+                Self::OP_ADVISE => mmap.madvise(op[1] as i32).is_err() as u64,
+                Self::OP_VERIFY => mmap.verify_with(fill, op[1]) as u64,
+                _ => 0,
+            };
+            if reply.write_all(&val.to_ne_bytes()).is_err() {
+                break;
+            }
+        }
+
+        // SAFETY: valid _exit call
+        unsafe { libc::_exit(0) };
+    }
+
+    fn read_reply(&mut self) -> Result<u64, io::Error> {
+        let mut val = [0u8; 8];
+        self.reply.read_exact(&mut val)?;
+        Ok(u64::from_ne_bytes(val))
+    }
+
+    fn request(&mut self, op: u8, arg: u8) -> Result<u64, io::Error> {
+        if let Some(cmd) = &mut self.cmd {
+            cmd.write_all(&[op, arg])?;
+        }
+        self.read_reply()
+    }
+
+    // returns the wait status once the child has exited
+    fn try_wait(&mut self) -> Option<i32> {
+        let mut status = 0;
+        // SAFETY: valid waitpid call
+        let ret = unsafe { libc::waitpid(self.pid, &mut status, libc::WNOHANG) };
+        if ret == self.pid {
+            self.exited = true;
+            Some(status)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for ChildChunk {
+    fn drop(&mut self) {
+        self.cmd.take();
+        if self.exited {
+            return;
+        }
+
+        // this fails with ECHILD when a sibling drops its inherited copy
+        // SAFETY: valid waitpid call
+        unsafe { libc::waitpid(self.pid, ptr::null_mut(), 0) };
+    }
+}
+
+enum Chunk {
+    Local(rustest::Mmap),
+    Child(ChildChunk),
+}
+
+impl Chunk {
+    fn page_in(&mut self, threads: usize) {
+        match self {
+            Chunk::Local(mmap) => {
+                mmap.populate_parallel(threads);
+            }
+            Chunk::Child(child) => {
+                let _ = child.request(ChildChunk::OP_PAGE_IN, threads.min(255) as u8);
+            }
+        }
+    }
+
+    fn advise(&mut self, advice: i32) {
+        match self {
+            Chunk::Local(mmap) => {
+                let _ = mmap.madvise(advice);
+            }
+            Chunk::Child(child) => {
+                let _ = child.request(ChildChunk::OP_ADVISE, advice as u8);
+            }
+        }
+    }
+
+    fn verify(&mut self, fill: rustest::Fill, val: u8) -> usize {
+        match self {
+            Chunk::Local(mmap) => mmap.verify_with(fill, val),
+            Chunk::Child(child) => child
+                .request(ChildChunk::OP_VERIFY, val)
+                .map_or(0, |count| count as usize),
+        }
+    }
+}
+
+struct Heap {
+    spec: HeapSpec,
+    chunks: Vec<Chunk>,
+    chunk_mb: usize,
+}
+
+impl Heap {
+    fn size_mb(&self) -> usize {
+        self.chunks.len() * self.chunk_mb
+    }
+
+    fn child_pids(&self) -> impl Iterator<Item = libc::pid_t> {
+        self.chunks.iter().filter_map(|chunk| match chunk {
+            Chunk::Local(_) => None,
+            Chunk::Child(child) => Some(child.pid),
+        })
+    }
+
+    fn props(&self) -> Vec<String> {
+        let mut props = Vec::new();
+        if self.spec.locked {
+            props.push("locked".to_string());
+        }
+        if self.spec.file {
+            props.push("file".to_string());
+        }
+        if let Some(huge) = self.spec.huge {
+            props.push(huge.to_string());
+        }
+        if self.spec.advice.is_some() {
+            props.push("madvised".to_string());
+        }
+        if self.spec.child {
+            props.push("child".to_string());
+        }
+        if self.spec.noreserve {
+            props.push("noreserve".to_string());
+        }
+
+        props
+    }
+
+    fn status(&self) -> rustest::JsonLine {
+        rustest::JsonLine::nested()
+            .field("name", &self.spec.name)
+            .field("size_mb", self.size_mb())
+            .field("props", self.props())
+            .field("child_pids", self.child_pids().collect::<Vec<_>>())
+    }
+}
+
+impl fmt::Display for Heap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{:5} MB", self.size_mb())?;
+
+        let props = self.props();
+        if !props.is_empty() {
+            write!(f, " ({})", props.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn format_limit(val: u64) -> String {
+    if val == u64::MAX {
+        "max".to_string()
+    } else {
+        format!("{} MB", val / 1024 / 1024)
+    }
+}
+
+// lowers or raises a memory.max/memory.high limit by a chunk
+fn adjust_cgroup_limit(
+    cgroup: &rustest::Cgroup,
+    file: &str,
+    raise: bool,
+    chunk_mb: usize,
+) -> Result<u64, io::Error> {
+    let chunk = (chunk_mb * 1024 * 1024) as u64;
+    let cur = cgroup.read_value(file).unwrap_or(u64::MAX);
+
+    let limit = if cur == u64::MAX {
+        if raise {
+            return Ok(cur);
+        }
+        // start just below the current usage
+        let usage = cgroup.read_value("memory.current").unwrap_or_default();
+        usage / chunk * chunk
+    } else if raise {
+        cur + chunk
+    } else {
+        cur.saturating_sub(chunk)
+    };
+
+    cgroup.write_value(file, limit)?;
+
+    Ok(limit)
 }
 
 struct Mlock {
-    locked: Vec<rustest::Mmap>,
-    unlocked: Vec<rustest::Mmap>,
+    heaps: Vec<Heap>,
+    // oom_score_adj applied to children, if different from ours
+    child_oom_adj: Option<i32>,
+
+    file_dir: String,
+    file_seq: usize,
+
+    chunk_mb: usize,
+    huge: HugeMode,
+    fill: rustest::Fill,
+    // threads that fill and page in each chunk
+    threads: usize,
+    // fill with stores that bypass the CPU caches
+    nontemporal: bool,
 }
 
 impl Mlock {
-    fn new() -> Mlock {
+    fn new(
+        specs: Vec<HeapSpec>,
+        file_dir: String,
+        chunk_mb: usize,
+        huge: HugeMode,
+        fill: rustest::Fill,
+    ) -> Mlock {
+        let heaps = specs
+            .into_iter()
+            .map(|spec| Heap {
+                spec,
+                chunks: Vec::new(),
+                chunk_mb,
+            })
+            .collect();
+
         Mlock {
-            locked: Vec::new(),
-            unlocked: Vec::new(),
+            heaps,
+            child_oom_adj: None,
+
+            file_dir,
+            file_seq: 0,
+
+            chunk_mb,
+            huge,
+            fill,
+            threads: 1,
+            nontemporal: false,
+        }
+    }
+
+    fn create_anonymous(
+        &self,
+        huge: HugeMode,
+        noreserve: bool,
+    ) -> Result<rustest::Mmap, io::Error> {
+        let len = self.chunk_mb * 1024 * 1024;
+        let mut flags = if noreserve { libc::MAP_NORESERVE } else { 0 };
+        if let HugeMode::Hugetlb = huge {
+            flags |= libc::MAP_HUGETLB;
+        }
+
+        let mut mmap = rustest::Mmap::anonymous_with_flags(len, flags)?;
+        if let HugeMode::Thp = huge {
+            mmap.madvise(libc::MADV_HUGEPAGE)?;
+        }
+
+        Ok(mmap)
+    }
+
+    fn create_file(&mut self) -> Result<rustest::Mmap, io::Error> {
+        let path = format!(
+            "{}/mlock-{}-{}",
+            self.file_dir,
+            process::id(),
+            self.file_seq
+        );
+        self.file_seq += 1;
+
+        // the mapping keeps the pages alive after the file is unlinked
+        let mmap = rustest::Mmap::create(&path, self.chunk_mb * 1024 * 1024);
+        let _ = fs::remove_file(&path);
+
+        mmap
+    }
+
+    fn create_chunk(&mut self, idx: usize, val: u8) -> Result<rustest::Mmap, io::Error> {
+        let spec = &self.heaps[idx].spec;
+        let (locked, advice, needs_fill) = (spec.locked, spec.advice, spec.needs_fill());
+
+        let mut mmap = if spec.file {
+            self.create_file()?
+        } else {
+            self.create_anonymous(spec.huge.unwrap_or(self.huge), spec.noreserve)?
+        };
+        if let Some(advice) = advice {
+            mmap.madvise(advice)?;
+        }
+
+        if needs_fill {
+            mmap.fill_parallel(self.fill, val, self.threads, self.nontemporal);
+        }
+        if locked {
+            mmap.mlock()?;
+        }
+
+        Ok(mmap)
+    }
+
+    fn add(&mut self, idx: usize) -> Result<(), io::Error> {
+        let val = (self.heaps[idx].chunks.len() + 1) as u8;
+        let chunk = if self.heaps[idx].spec.child {
+            let fill = self.fill;
+            let child = ChildChunk::spawn(
+                || {
+                    // release the parent's chunks and pipes that the child inherited
+                    for heap in &mut self.heaps {
+                        heap.chunks.clear();
+                    }
+                    self.create_chunk(idx, val)
+                },
+                fill,
+            )?;
+            if let Some(adj) = self.child_oom_adj {
+                let _ = rustest::write_oom_score_adj(&child.pid.to_string(), adj);
+            }
+            Chunk::Child(child)
+        } else {
+            Chunk::Local(self.create_chunk(idx, val)?)
+        };
+        self.heaps[idx].chunks.push(chunk);
+
+        Ok(())
+    }
+
+    fn remove(&mut self, idx: usize) -> bool {
+        self.heaps[idx].chunks.pop().is_some()
+    }
+
+    fn child_pids(&self) -> impl Iterator<Item = libc::pid_t> {
+        self.heaps.iter().flat_map(|heap| heap.child_pids())
+    }
+
+    fn adjust_oom(&mut self, children: bool, delta: i32) -> Result<i32, io::Error> {
+        let cur = rustest::read_oom("self", "oom_score_adj").unwrap_or_default();
+        if !children {
+            let adj = (cur + delta).clamp(-1000, 1000);
+            rustest::write_oom_score_adj("self", adj)?;
+            return Ok(adj);
+        }
+
+        let adj = (self.child_oom_adj.unwrap_or(cur) + delta).clamp(-1000, 1000);
+        for pid in self.child_pids() {
+            rustest::write_oom_score_adj(&pid.to_string(), adj)?;
+        }
+        self.child_oom_adj = Some(adj);
+
+        Ok(adj)
+    }
+
+    fn find_key(&self, key: char) -> Option<Action> {
+        self.heaps.iter().enumerate().find_map(|(idx, heap)| {
+            if heap.spec.add_keys.contains(&key) {
+                Some(Action::Add(idx))
+            } else if heap.spec.remove_keys.contains(&key) {
+                Some(Action::Remove(idx))
+            } else {
+                None
+            }
+        })
+    }
+
+    // the first locked anonymous heap backs the balloon
+    fn balloon_heap(&self) -> Option<usize> {
+        self.heaps
+            .iter()
+            .position(|heap| heap.spec.locked && !heap.spec.file)
+    }
+
+    fn unlocked(&mut self) -> impl Iterator<Item = &mut Heap> {
+        self.heaps.iter_mut().filter(|heap| !heap.spec.locked)
+    }
+
+    // drops chunks whose child has exited, e.g. due to the OOM killer
+    fn reap(&mut self, log: &mut EventLog) {
+        for heap in &mut self.heaps {
+            heap.chunks.retain_mut(|chunk| {
+                let Chunk::Child(child) = chunk else {
+                    return true;
+                };
+                let Some(status) = child.try_wait() else {
+                    return true;
+                };
+
+                if libc::WIFSIGNALED(status) {
+                    log.alert(
+                        "child_killed",
+                        &[
+                            ("HEAP", &heap.spec.name),
+                            ("CHILD_PID", &child.pid.to_string()),
+                            ("SIGNAL", &libc::WTERMSIG(status).to_string()),
+                        ],
+                        format_args!(
+                            "{} child {} killed by signal {}",
+                            heap.spec.name,
+                            child.pid,
+                            libc::WTERMSIG(status)
+                        ),
+                    );
+                } else {
+                    log.record(format_args!(
+                        "{} child {} exited with {}",
+                        heap.spec.name,
+                        child.pid,
+                        libc::WEXITSTATUS(status)
+                    ));
+                }
+
+                false
+            });
         }
     }
 
-    fn add(&mut self, heap: MlockHeap) -> Result<(), io::Error> {
-        let mut mmap = rustest::Mmap::anonymous(CHUNK_SIZE_MB * 1024 * 1024)?;
-        match heap {
-            MlockHeap::Locked => {
-                mmap.mlock()?;
-                self.locked.push(mmap);
+    // keep MemAvailable near the target by growing or shrinking the locked heap
+    fn balloon(&mut self, target_kb: u64, avail_kb: u64) {
+        let Some(idx) = self.balloon_heap() else {
+            return;
+        };
+        let chunk_kb = (self.chunk_mb * 1024) as u64;
+
+        if avail_kb > target_kb + chunk_kb {
+            let count = (avail_kb - target_kb) / chunk_kb;
+            for _ in 0..count {
+                if self.add(idx).is_err() {
+                    break;
+                }
             }
-            MlockHeap::Unlocked => {
-                mmap.fill((self.unlocked.len() + 1) as u8);
-                self.unlocked.push(mmap);
+        } else if avail_kb < target_kb {
+            let count = (target_kb - avail_kb).div_ceil(chunk_kb);
+            for _ in 0..count {
+                if !self.remove(idx) {
+                    break;
+                }
             }
         }
+    }
 
-        Ok(())
+    fn page_in(&mut self) {
+        let threads = self.threads;
+        for heap in self.unlocked() {
+            for chunk in &mut heap.chunks {
+                chunk.page_in(threads);
+            }
+        }
+    }
+
+    // returns the number of pages checked and the number of mismatches
+    fn verify(&mut self) -> (usize, usize) {
+        let fill = self.fill;
+        let page_count = (self.chunk_mb * 1024 * 1024).div_ceil(rustest::page_size());
+
+        let mut checked = 0;
+        let mut mismatches = 0;
+        for heap in self.unlocked() {
+            for (idx, chunk) in heap.chunks.iter_mut().enumerate() {
+                checked += page_count;
+                mismatches += chunk.verify(fill, (idx + 1) as u8);
+            }
+        }
+
+        (checked, mismatches)
     }
 
-    fn remove(&mut self, heap: MlockHeap) -> bool {
-        match heap {
-            MlockHeap::Locked => self.locked.pop(),
-            MlockHeap::Unlocked => self.unlocked.pop(),
+    fn advise(&mut self, advice: i32) {
+        for heap in self.unlocked() {
+            for chunk in &mut heap.chunks {
+                chunk.advise(advice);
+            }
         }
-        .is_some()
     }
 
-    fn page_in(&self) {
-        for mmap in &self.unlocked {
-            let _ = mmap.populate();
+    // returns the heap configuration and sizes as an [mlock] config table, for --save
+    fn snapshot(&self) -> String {
+        // a basic string of the config format
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let list = |items: Vec<String>| {
+            let items: Vec<String> = items.iter().map(|item| quote(item)).collect();
+            format!("[{}]", items.join(", "))
+        };
+
+        let mut text = String::from("[mlock]\n");
+        text += &format!("chunk-size = {}\n", self.chunk_mb);
+        text += &format!("fill = {}\n", quote(&self.fill.to_string()));
+        text += &format!("file-dir = {}\n", quote(&self.file_dir));
+        match self.huge {
+            HugeMode::None => (),
+            HugeMode::Thp => text += "thp = true\n",
+            HugeMode::Hugetlb => text += "hugetlb = true\n",
         }
+        text += &format!(
+            "heap = {}\n",
+            list(self.heaps.iter().map(|heap| heap.spec.to_spec()).collect())
+        );
+        text += &format!(
+            "chunks = {}\n",
+            list(
+                self.heaps
+                    .iter()
+                    .filter(|heap| !heap.chunks.is_empty())
+                    .map(|heap| format!("{}:{}", heap.spec.name, heap.chunks.len()))
+                    .collect()
+            )
+        );
+
+        text
     }
 }
 
-impl fmt::Display for Mlock {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        let [locked_mb, unlocked_mb] =
-            [self.locked.len(), self.unlocked.len()].map(|len| len * CHUNK_SIZE_MB);
-        write!(
-            f,
-            "locked {:5} MB, unlocked {:5} MB",
-            locked_mb, unlocked_mb,
-        )
+// writes snapshots for --save, replacing the file atomically so that a crash leaves the
+// previous snapshot intact
+struct Snapshot {
+    path: String,
+    prev: String,
+}
+
+impl Snapshot {
+    fn new(path: &str) -> Self {
+        Snapshot {
+            path: path.to_string(),
+            prev: String::new(),
+        }
+    }
+
+    // writes the snapshot of mlock if it changed
+    fn save(&mut self, mlock: &Mlock) -> Result<(), io::Error> {
+        let text = mlock.snapshot();
+        if text == self.prev {
+            return Ok(());
+        }
+
+        let tmp = format!("{}.tmp", self.path);
+        let mut fp = fs::File::create(&tmp)?;
+        writeln!(
+            fp,
+            "# saved by mlock; restore with mlock --restore {}",
+            self.path
+        )?;
+        fp.write_all(text.as_bytes())?;
+        fp.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+
+        self.prev = text;
+        Ok(())
     }
 }
 
 struct Proc {
     page_size: usize,
 
+    // memory available for new workloads
+    mem_available: u64,
+    // pages used by block device buffers
+    buffers: u64,
+    // pages in the page cache
+    cached: u64,
     // pages that are mlock'ed
     mlocked: u64,
     // swap usage
@@ -80,6 +833,14 @@ struct Proc {
     swap_free: u64,
     // pages that are anonymous and resident
     anon_pages: u64,
+    // overcommit accounting
+    commit_limit: u64,
+    committed_as: u64,
+    // pages that are anonymous and backed by THP
+    anon_huge_pages: u64,
+    // hugetlb pool, in hugepages
+    huge_pages_total: u64,
+    huge_pages_free: u64,
 
     // accumulated pages swapped in/out to block devices
     pswpin: u64,
@@ -87,6 +848,10 @@ struct Proc {
 
     pswpin_delta: u64,
     pswpout_delta: u64,
+
+    // accumulated pages freed with MADV_FREE, and those reclaimed afterward
+    pglazyfree: u64,
+    pglazyfreed: u64,
 }
 
 impl Proc {
@@ -94,16 +859,27 @@ impl Proc {
         let mut proc = Proc {
             page_size: rustest::page_size(),
 
+            mem_available: 0,
+            buffers: 0,
+            cached: 0,
             mlocked: 0,
             swap_total: 0,
             swap_free: 0,
             anon_pages: 0,
+            commit_limit: 0,
+            committed_as: 0,
+            anon_huge_pages: 0,
+            huge_pages_total: 0,
+            huge_pages_free: 0,
 
             pswpin: 0,
             pswpout: 0,
 
             pswpin_delta: 0,
             pswpout_delta: 0,
+
+            pglazyfree: 0,
+            pglazyfreed: 0,
         };
 
         let _ = proc.collect_meminfo();
@@ -131,7 +907,13 @@ impl Proc {
                     .unwrap_or_default()
             };
 
-            if line.starts_with("Mlocked:") {
+            if line.starts_with("MemAvailable:") {
+                self.mem_available = extract_val(&line);
+            } else if line.starts_with("Buffers:") {
+                self.buffers = extract_val(&line);
+            } else if line.starts_with("Cached:") {
+                self.cached = extract_val(&line);
+            } else if line.starts_with("Mlocked:") {
                 self.mlocked = extract_val(&line);
             } else if line.starts_with("SwapTotal:") {
                 self.swap_total = extract_val(&line);
@@ -139,6 +921,16 @@ impl Proc {
                 self.swap_free = extract_val(&line);
             } else if line.starts_with("AnonPages:") {
                 self.anon_pages = extract_val(&line);
+            } else if line.starts_with("CommitLimit:") {
+                self.commit_limit = extract_val(&line);
+            } else if line.starts_with("Committed_AS:") {
+                self.committed_as = extract_val(&line);
+            } else if line.starts_with("AnonHugePages:") {
+                self.anon_huge_pages = extract_val(&line);
+            } else if line.starts_with("HugePages_Total:") {
+                self.huge_pages_total = extract_val(&line);
+            } else if line.starts_with("HugePages_Free:") {
+                self.huge_pages_free = extract_val(&line);
                 break;
             }
         }
@@ -156,6 +948,10 @@ impl Proc {
                 self.pswpin = val.parse().unwrap_or_default();
             } else if let Some(val) = line.strip_prefix("pswpout ") {
                 self.pswpout = val.parse().unwrap_or_default();
+            } else if let Some(val) = line.strip_prefix("pglazyfree ") {
+                self.pglazyfree = val.parse().unwrap_or_default();
+            } else if let Some(val) = line.strip_prefix("pglazyfreed ") {
+                self.pglazyfreed = val.parse().unwrap_or_default();
                 break;
             }
         }
@@ -164,13 +960,42 @@ impl Proc {
     }
 }
 
+impl Proc {
+    fn push_json(&self, line: &mut rustest::JsonLine) {
+        let page_kb = self.page_size as u64 / 1024;
+        line.push("mem_available_kb", self.mem_available);
+        line.push("cached_kb", self.cached);
+        line.push("mlocked_kb", self.mlocked);
+        line.push("anon_pages_kb", self.anon_pages);
+        line.push("swap_used_kb", self.swap_total - self.swap_free);
+        line.push("swap_in_kb", self.pswpin_delta * page_kb);
+        line.push("swap_out_kb", self.pswpout_delta * page_kb);
+        line.push("committed_as_kb", self.committed_as);
+        line.push("commit_limit_kb", self.commit_limit);
+        line.push("anon_huge_pages_kb", self.anon_huge_pages);
+        line.push("pglazyfree", self.pglazyfree);
+        line.push("pglazyfreed", self.pglazyfreed);
+    }
+}
+
 impl fmt::Display for Proc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        let [mlocked, swap_total, swap_free, anon_pages] = [
+        let [
+            cached,
+            mlocked,
+            swap_total,
+            swap_free,
+            anon_pages,
+            committed_as,
+            commit_limit,
+        ] = [
+            self.cached,
             self.mlocked,
             self.swap_total,
             self.swap_free,
             self.anon_pages,
+            self.committed_as,
+            self.commit_limit,
         ]
         .map(|kb| kb / 1024);
 
@@ -179,12 +1004,15 @@ impl fmt::Display for Proc {
 
         write!(
             f,
-            "locked {:5} MB, unlocked {:5} MB, swap {:5} MB, swap i/o +{}/+{} MB",
+            "locked {:5} MB, unlocked {:5} MB, cached {:5} MB, swap {:5} MB, swap i/o +{}/+{} MB, commit {}/{} MB",
             mlocked,
-            anon_pages.checked_sub(mlocked).unwrap_or(0),
+            anon_pages.saturating_sub(mlocked),
+            cached,
             swap_total - swap_free,
             swap_in,
             swap_out,
+            committed_as,
+            commit_limit,
         )
     }
 }
@@ -194,25 +1022,48 @@ struct ProcSelf {
     vm_lck: u64,
     // pages that are anonymous and resident
     rss_anon: u64,
+    // pages that are file-backed or shmem and resident
+    rss_file: u64,
     // pages that are anonymous and swapped out
     vm_swap: u64,
 }
 
 impl ProcSelf {
     fn collect() -> Self {
-        let mut pid = ProcSelf {
+        Self::collect_pid("self")
+    }
+
+    // sums the status of the given processes
+    fn collect_pids<I: Iterator<Item = libc::pid_t>>(pids: I) -> Self {
+        let mut sum = Self::collect_pid("");
+        for pid in pids {
+            let proc = Self::collect_pid(&pid.to_string());
+            sum.vm_lck += proc.vm_lck;
+            sum.rss_anon += proc.rss_anon;
+            sum.rss_file += proc.rss_file;
+            sum.vm_swap += proc.vm_swap;
+        }
+
+        sum
+    }
+
+    fn collect_pid(pid: &str) -> Self {
+        let mut proc = ProcSelf {
             vm_lck: 0,
             rss_anon: 0,
+            rss_file: 0,
             vm_swap: 0,
         };
 
-        let _ = pid.collect_status();
+        if !pid.is_empty() {
+            let _ = proc.collect_status(pid);
+        }
 
-        pid
+        proc
     }
 
-    fn collect_status(&mut self) -> Result<(), io::Error> {
-        let fp = fs::File::open("/proc/self/status")?;
+    fn collect_status(&mut self, pid: &str) -> Result<(), io::Error> {
+        let fp = fs::File::open(format!("/proc/{}/status", pid))?;
         let reader = io::BufReader::new(fp);
 
         for line in reader.lines() {
@@ -229,6 +1080,8 @@ impl ProcSelf {
                 self.vm_lck = extract_val(&line);
             } else if line.starts_with("RssAnon:") {
                 self.rss_anon = extract_val(&line);
+            } else if line.starts_with("RssFile:") || line.starts_with("RssShmem:") {
+                self.rss_file += extract_val(&line);
             } else if line.starts_with("VmSwap:") {
                 self.vm_swap = extract_val(&line);
                 break;
@@ -239,53 +1092,1227 @@ impl ProcSelf {
     }
 }
 
+impl ProcSelf {
+    fn push_json(&self, prefix: &str, line: &mut rustest::JsonLine) {
+        line.push(&format!("{}_locked_kb", prefix), self.vm_lck);
+        line.push(&format!("{}_rss_anon_kb", prefix), self.rss_anon);
+        line.push(&format!("{}_rss_file_kb", prefix), self.rss_file);
+        line.push(&format!("{}_swap_kb", prefix), self.vm_swap);
+    }
+}
+
 impl fmt::Display for ProcSelf {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        let [vm_lck, rss_anon, vm_swap] =
-            [self.vm_lck, self.rss_anon, self.vm_swap].map(|kb| kb / 1024);
+        let [vm_lck, rss_anon, rss_file, vm_swap] =
+            [self.vm_lck, self.rss_anon, self.rss_file, self.vm_swap].map(|kb| kb / 1024);
         write!(
             f,
-            "locked {:5} MB, unlocked {:5} MB, swap {:5} MB",
+            "locked {:5} MB, unlocked {:5} MB, file {:5} MB, swap {:5} MB",
             vm_lck,
-            rss_anon.checked_sub(vm_lck).unwrap_or(0),
+            rss_anon.saturating_sub(vm_lck),
+            rss_file,
             vm_swap,
         )
     }
 }
 
-enum Action {
-    Redraw,
-    Quit,
-    Add(MlockHeap),
-    Remove(MlockHeap),
-    PageIn,
+struct EventLog {
+    start: time::Instant,
+    events: Vec<String>,
+    // number of events hidden below the pane
+    scroll: usize,
+    file: Option<fs::File>,
+    // also emit each event as a JSON line
+    json: bool,
+    // mirrors alerts to the system log
+    journal: Option<rustest::Journal>,
 }
 
-fn term_wait_action(term: &mut rustest::Term) -> Action {
-    let key = match term.poll(1000) {
-        Ok(Some(key)) => key,
-        Ok(None) => return Action::Redraw,
-        Err(_) => return Action::Quit,
-    };
+impl EventLog {
+    fn new(path: Option<&str>, json: bool, syslog: bool) -> Result<Self, io::Error> {
+        let file = match path {
+            Some(path) => Some(fs::File::create(path)?),
+            None => None,
+        };
+        let journal = if syslog {
+            Some(rustest::Journal::open("mlock")?)
+        } else {
+            None
+        };
 
-    match key.modifiers {
-        event::KeyModifiers::CONTROL => match key.code {
-            event::KeyCode::Char('c') | event::KeyCode::Char('d') => Action::Quit,
-            _ => Action::Redraw,
-        },
-        event::KeyModifiers::SHIFT | event::KeyModifiers::NONE => match key.code {
-            event::KeyCode::Char('+') | event::KeyCode::Char('=') => Action::Add(MlockHeap::Locked),
-            event::KeyCode::Char('-') | event::KeyCode::Char('_') => {
-                Action::Remove(MlockHeap::Locked)
-            }
-            event::KeyCode::Char(']') | event::KeyCode::Char('}') => {
-                Action::Add(MlockHeap::Unlocked)
-            }
-            event::KeyCode::Char('[') | event::KeyCode::Char('{') => {
-                Action::Remove(MlockHeap::Unlocked)
-            }
-            event::KeyCode::Char('p') | event::KeyCode::Char('P') => Action::PageIn,
-            event::KeyCode::Char('q') | event::KeyCode::Esc => Action::Quit,
+        Ok(EventLog {
+            start: time::Instant::now(),
+            events: Vec::new(),
+            scroll: 0,
+            file,
+            json,
+            journal,
+        })
+    }
+
+    // records an event that is also sent to the system log, with kind as RUSTEST_EVENT
+    fn alert(&mut self, kind: &str, fields: &[(&str, &str)], msg: fmt::Arguments) {
+        if let Some(journal) = &self.journal {
+            let mut fields = fields.to_vec();
+            fields.push(("RUSTEST_EVENT", kind));
+            let _ = journal.send(libc::LOG_WARNING, &msg.to_string(), &fields);
+        }
+
+        self.record(msg);
+    }
+
+    fn record(&mut self, msg: fmt::Arguments) {
+        self.record_at(self.start.elapsed(), msg);
+    }
+
+    // records an event that happened at elapsed, such as a trace event read later
+    fn record_at(&mut self, elapsed: time::Duration, msg: fmt::Arguments) {
+        let secs = elapsed.as_secs_f64();
+        let event = format!("[{:9.3}] {}", secs, msg);
+
+        if let Some(file) = &mut self.file {
+            let _ = writeln!(file, "{}", event);
+        }
+        if self.json {
+            rustest::JsonLine::new("event")
+                .field("elapsed_ns", elapsed)
+                .field("msg", msg.to_string())
+                .emit();
+        }
+        self.events.push(event);
+
+        // keep the pane pinned to the newest events unless scrolled
+        if self.scroll > 0 {
+            self.scroll += 1;
+        }
+    }
+
+    fn scroll_up(&mut self) {
+        if self.scroll + LOG_ROWS < self.events.len() {
+            self.scroll += 1;
+        }
+    }
+
+    fn scroll_down(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    fn visible(&self) -> &[String] {
+        let end = self.events.len() - self.scroll;
+        let begin = end.saturating_sub(LOG_ROWS);
+        &self.events[begin..end]
+    }
+}
+
+// renders labeled rows and highlights the numbers that changed since the
+// previous refresh
+struct Screen {
+    prev: HashMap<String, String>,
+    rows: u32,
+}
+
+impl Screen {
+    fn new() -> Self {
+        Screen {
+            prev: HashMap::new(),
+            rows: 0,
+        }
+    }
+
+    fn begin(&mut self) {
+        self.rows = 0;
+    }
+
+    fn plain_row(&mut self, term: &mut rustest::Term, label: &str, args: fmt::Arguments) {
+        term.cmd_fmt(format_args!("{:11}{}\r\n", label, args));
+        self.rows += 1;
+    }
+
+    fn row(&mut self, term: &mut rustest::Term, label: &str, args: fmt::Arguments) {
+        let cur = args.to_string();
+        let row = match self.prev.get(label) {
+            Some(prev) => Self::highlight(prev, &cur),
+            None => cur.clone(),
+        };
+        self.prev.insert(label.to_string(), cur);
+
+        self.plain_row(term, label, format_args!("{}", row));
+    }
+
+    fn highlight(prev: &str, cur: &str) -> String {
+        let mut prev_words = prev.split_ascii_whitespace();
+        let mut out = String::with_capacity(cur.len());
+
+        let mut rest = cur;
+        while !rest.is_empty() {
+            let word_start = rest
+                .find(|c: char| !c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            out.push_str(&rest[..word_start]);
+            rest = &rest[word_start..];
+
+            let word_end = rest
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            let word = &rest[..word_end];
+            rest = &rest[word_end..];
+            if word.is_empty() {
+                break;
+            }
+
+            let Some(prev_word) = prev_words.next().filter(|prev_word| *prev_word != word) else {
+                out.push_str(word);
+                continue;
+            };
+
+            let num = word.trim_end_matches([',', '%']);
+            let suffix = &word[num.len()..];
+            out.push_str(&num.reverse().to_string());
+            if let (Ok(val), Ok(prev_val)) = (
+                num.parse::<i64>(),
+                prev_word.trim_end_matches([',', '%']).parse::<i64>(),
+            ) {
+                out.push_str(&format!("({:+})", val - prev_val));
+            }
+            out.push_str(suffix);
+        }
+
+        out
+    }
+}
+
+struct History {
+    samples: VecDeque<u64>,
+}
+
+impl History {
+    fn new() -> Self {
+        History {
+            samples: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+
+    fn push(&mut self, val: u64) {
+        if self.samples.len() == HISTORY_LEN {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(val);
+    }
+}
+
+impl fmt::Display for History {
+    // renders a sparkline scaled to the max sample, padded to HISTORY_LEN
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        let max = self.samples.iter().copied().max().unwrap_or_default();
+        for _ in self.samples.len()..HISTORY_LEN {
+            f.write_str(" ")?;
+        }
+        for &val in &self.samples {
+            let bar = if val == 0 {
+                ' '
+            } else {
+                BARS[(val * (BARS.len() as u64 - 1) / max) as usize]
+            };
+            write!(f, "{}", bar)?;
+        }
+
+        write!(f, " peak {:7} KB", max)
+    }
+}
+
+struct Psi {
+    // share of time stalled over the last 10 seconds, in percent
+    some_avg10: f64,
+    full_avg10: f64,
+
+    // accumulated stall time, in us
+    some_total: u64,
+    full_total: u64,
+
+    some_delta: u64,
+    full_delta: u64,
+}
+
+impl Psi {
+    // returns None when PSI is unavailable
+    fn collect(prev: Option<&Psi>) -> Option<Self> {
+        let mut psi = Psi {
+            some_avg10: 0.0,
+            full_avg10: 0.0,
+
+            some_total: 0,
+            full_total: 0,
+
+            some_delta: 0,
+            full_delta: 0,
+        };
+
+        psi.collect_pressure().ok()?;
+
+        if let Some(prev) = prev {
+            psi.some_delta = psi.some_total.saturating_sub(prev.some_total);
+            psi.full_delta = psi.full_total.saturating_sub(prev.full_total);
+        }
+
+        Some(psi)
+    }
+
+    fn collect_pressure(&mut self) -> Result<(), io::Error> {
+        let fp = fs::File::open("/proc/pressure/memory")?;
+        let reader = io::BufReader::new(fp);
+
+        for line in reader.lines() {
+            let line = line?;
+
+            let mut avg10 = 0.0;
+            let mut total = 0;
+            for field in line.split_ascii_whitespace().skip(1) {
+                if let Some(val) = field.strip_prefix("avg10=") {
+                    avg10 = val.parse().unwrap_or_default();
+                } else if let Some(val) = field.strip_prefix("total=") {
+                    total = val.parse().unwrap_or_default();
+                }
+            }
+
+            if line.starts_with("some ") {
+                self.some_avg10 = avg10;
+                self.some_total = total;
+            } else if line.starts_with("full ") {
+                self.full_avg10 = avg10;
+                self.full_total = total;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Psi {
+    fn push_json(&self, line: &mut rustest::JsonLine) {
+        line.push("psi_some_avg10", self.some_avg10);
+        line.push("psi_full_avg10", self.full_avg10);
+        line.push("psi_some_us", self.some_delta);
+        line.push("psi_full_us", self.full_delta);
+    }
+}
+
+impl fmt::Display for Psi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "some {:6.2}% +{} ms, full {:6.2}% +{} ms",
+            self.some_avg10,
+            self.some_delta / 1000,
+            self.full_avg10,
+            self.full_delta / 1000,
+        )
+    }
+}
+
+struct Zram {
+    // bytes stored, before and after compression
+    orig_data_size: u64,
+    compr_data_size: u64,
+    // bytes consumed by zram, including allocator overhead
+    mem_used: u64,
+}
+
+impl Zram {
+    // returns None unless zram is used for swap
+    fn collect() -> Option<Self> {
+        let mut zram = Zram {
+            orig_data_size: 0,
+            compr_data_size: 0,
+            mem_used: 0,
+        };
+
+        let devs = Self::swap_devices().ok()?;
+        if devs.is_empty() {
+            return None;
+        }
+
+        for dev in devs {
+            let _ = zram.collect_mm_stat(&dev);
+        }
+
+        Some(zram)
+    }
+
+    fn swap_devices() -> Result<Vec<String>, io::Error> {
+        let fp = fs::File::open("/proc/swaps")?;
+        let reader = io::BufReader::new(fp);
+
+        let mut devs = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+
+            if let Some(dev) = line
+                .split_ascii_whitespace()
+                .next()
+                .and_then(|name| name.strip_prefix("/dev/"))
+                .filter(|dev| dev.starts_with("zram"))
+            {
+                devs.push(dev.to_string());
+            }
+        }
+
+        Ok(devs)
+    }
+
+    fn collect_mm_stat(&mut self, dev: &str) -> Result<(), io::Error> {
+        let mm_stat = fs::read_to_string(format!("/sys/block/{}/mm_stat", dev))?;
+        let vals: Vec<u64> = mm_stat
+            .split_ascii_whitespace()
+            .map(|val| val.parse().unwrap_or_default())
+            .collect();
+
+        if vals.len() >= 3 {
+            self.orig_data_size += vals[0];
+            self.compr_data_size += vals[1];
+            self.mem_used += vals[2];
+        }
+
+        Ok(())
+    }
+}
+
+impl Zram {
+    fn push_json(&self, line: &mut rustest::JsonLine) {
+        line.push("zram_orig_bytes", self.orig_data_size);
+        line.push("zram_compr_bytes", self.compr_data_size);
+        line.push("zram_used_bytes", self.mem_used);
+    }
+}
+
+impl fmt::Display for Zram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let [orig, compr, mem_used] = [self.orig_data_size, self.compr_data_size, self.mem_used]
+            .map(|bytes| bytes / 1024 / 1024);
+        let ratio = if self.compr_data_size > 0 {
+            self.orig_data_size as f64 / self.compr_data_size as f64
+        } else {
+            0.0
+        };
+
+        write!(
+            f,
+            "orig {:5} MB, compr {:5} MB, used {:5} MB, ratio {:.2}",
+            orig, compr, mem_used, ratio,
+        )
+    }
+}
+
+// the low-memory notifier of ChromeOS kernels, which tab discarding is based on, in MB
+struct LowMem {
+    available: u64,
+    // critical first, then moderate
+    margins: Vec<u64>,
+}
+
+impl LowMem {
+    const DIR: &str = "/sys/kernel/mm/chromeos-low-mem";
+
+    // returns None on kernels without the notifier
+    fn collect() -> Option<Self> {
+        let read = |name| fs::read_to_string(format!("{}/{}", Self::DIR, name)).ok();
+
+        let available = read("available")?.trim().parse().ok()?;
+        let margins = read("margin")
+            .unwrap_or_default()
+            .split_ascii_whitespace()
+            .filter_map(|val| val.parse().ok())
+            .collect();
+
+        Some(LowMem { available, margins })
+    }
+
+    fn margin_name(idx: usize) -> String {
+        match idx {
+            0 => "critical".to_string(),
+            1 => "moderate".to_string(),
+            _ => format!("margin{}", idx),
+        }
+    }
+
+    // returns the name of the most severe margin that available is below
+    fn level(&self) -> Option<String> {
+        (0..self.margins.len())
+            .find(|&idx| self.available < self.margins[idx])
+            .map(Self::margin_name)
+    }
+
+    fn push_json(&self, line: &mut rustest::JsonLine) {
+        line.push("chromeos_available_mb", self.available);
+        for (idx, margin) in self.margins.iter().enumerate() {
+            line.push(
+                &format!("chromeos_{}_margin_mb", Self::margin_name(idx)),
+                margin,
+            );
+        }
+    }
+}
+
+impl fmt::Display for LowMem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "available {:5} MB", self.available)?;
+
+        // how far available is above each margin
+        for (idx, &margin) in self.margins.iter().enumerate() {
+            write!(
+                f,
+                ", {} {:5} MB ({:+} MB)",
+                Self::margin_name(idx),
+                margin,
+                self.available as i64 - margin as i64
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn drop_caches() -> Result<String, io::Error> {
+    let before = Proc::collect(None);
+
+    // SAFETY: valid sync call
+    unsafe { libc::sync() };
+    fs::write("/proc/sys/vm/drop_caches", "3")?;
+
+    let after = Proc::collect(None);
+
+    Ok(format!(
+        "cached {} -> {} MB, buffers {} -> {} MB",
+        before.cached / 1024,
+        after.cached / 1024,
+        before.buffers / 1024,
+        after.buffers / 1024,
+    ))
+}
+
+struct Stat {
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u64,
+}
+
+impl Stat {
+    fn new() -> Self {
+        Stat {
+            min: f64::MAX,
+            max: f64::MIN,
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn add(&mut self, val: f64) {
+        self.min = self.min.min(val);
+        self.max = self.max.max(val);
+        self.sum += val;
+        self.count += 1;
+    }
+}
+
+impl rustest::JsonValue for Stat {
+    fn write_json(&self, buf: &mut String) {
+        if self.count == 0 {
+            return buf.push_str("null");
+        }
+
+        buf.push_str(&format!(
+            "{{\"min\":{},\"max\":{},\"avg\":{}}}",
+            self.min,
+            self.max,
+            self.sum / self.count as f64
+        ));
+    }
+}
+
+impl fmt::Display for Stat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        if self.count == 0 {
+            return f.write_str("no samples");
+        }
+
+        write!(
+            f,
+            "min {:8.2}, max {:8.2}, avg {:8.2}",
+            self.min,
+            self.max,
+            self.sum / self.count as f64
+        )
+    }
+}
+
+// accumulates the tracked counters over a soak run
+struct Summary {
+    swap_mb: Stat,
+    psi_some: Stat,
+    psi_full: Stat,
+    swap_in_rate: Stat,
+    swap_out_rate: Stat,
+
+    // totals over the run, in KB and us
+    swap_in_kb: u64,
+    swap_out_kb: u64,
+    psi_some_us: u64,
+    psi_full_us: u64,
+
+    // script commands of the actions taken and their counts
+    actions: Vec<(String, u64)>,
+}
+
+impl Summary {
+    fn new() -> Self {
+        Summary {
+            swap_mb: Stat::new(),
+            psi_some: Stat::new(),
+            psi_full: Stat::new(),
+            swap_in_rate: Stat::new(),
+            swap_out_rate: Stat::new(),
+
+            swap_in_kb: 0,
+            swap_out_kb: 0,
+            psi_some_us: 0,
+            psi_full_us: 0,
+
+            actions: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, sys: &Proc, psi: Option<&Psi>, secs: f64) {
+        self.swap_mb
+            .add(((sys.swap_total - sys.swap_free) / 1024) as f64);
+        if let Some(psi) = psi {
+            self.psi_some.add(psi.some_avg10);
+            self.psi_full.add(psi.full_avg10);
+            self.psi_some_us += psi.some_delta;
+            self.psi_full_us += psi.full_delta;
+        }
+
+        let page_kb = sys.page_size as u64 / 1024;
+        self.swap_in_kb += sys.pswpin_delta * page_kb;
+        self.swap_out_kb += sys.pswpout_delta * page_kb;
+
+        if secs > 0.0 {
+            let [swap_in, swap_out] = [sys.pswpin_delta, sys.pswpout_delta]
+                .map(|page_count| (page_count * sys.page_size as u64) as f64 / 1024.0 / 1024.0);
+            self.swap_in_rate.add(swap_in / secs);
+            self.swap_out_rate.add(swap_out / secs);
+        }
+    }
+
+    fn action(&mut self, cmd: String) {
+        match self.actions.iter_mut().find(|(key, _)| *key == cmd) {
+            Some((_, count)) => *count += 1,
+            None => self.actions.push((cmd, 1)),
+        }
+    }
+
+    fn print(&self) {
+        println!("swap used (MB):     {}", self.swap_mb);
+        println!("psi some avg10 (%): {}", self.psi_some);
+        println!("psi full avg10 (%): {}", self.psi_full);
+        println!("swap in (MB/s):     {}", self.swap_in_rate);
+        println!("swap out (MB/s):    {}", self.swap_out_rate);
+        println!(
+            "swap in/out (MB):   {} / {}",
+            self.swap_in_kb / 1024,
+            self.swap_out_kb / 1024
+        );
+    }
+
+    fn line(&self) -> rustest::JsonLine {
+        let mut actions = rustest::JsonLine::nested();
+        for (cmd, count) in &self.actions {
+            actions.push(cmd, count);
+        }
+
+        rustest::JsonLine::new("summary")
+            .field("swap_mb", &self.swap_mb)
+            .field("psi_some_avg10", &self.psi_some)
+            .field("psi_full_avg10", &self.psi_full)
+            .field("swap_in_mbps", &self.swap_in_rate)
+            .field("swap_out_mbps", &self.swap_out_rate)
+            .field("swap_in_kb", self.swap_in_kb)
+            .field("swap_out_kb", self.swap_out_kb)
+            .field("psi_some_us", self.psi_some_us)
+            .field("psi_full_us", self.psi_full_us)
+            .field("actions", actions)
+    }
+
+    fn emit(&self) {
+        self.line().emit();
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Metric {
+    // swap used, in MB
+    Swap,
+    // MemAvailable, in MB
+    Available,
+    // PSI avg10, in percent
+    PsiSome,
+    PsiFull,
+}
+
+// a METRIC>VALUE or METRIC<VALUE condition on the system state
+struct Threshold {
+    metric: Metric,
+    above: bool,
+    value: f64,
+}
+
+impl Threshold {
+    fn parse(s: &str) -> Result<Self, io::Error> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid threshold {}", s),
+            )
+        };
+
+        let (above, (name, value)) = match (s.split_once('>'), s.split_once('<')) {
+            (Some(pair), None) => (true, pair),
+            (None, Some(pair)) => (false, pair),
+            _ => return Err(invalid()),
+        };
+
+        let metric = match name {
+            "swap" => Metric::Swap,
+            "available" => Metric::Available,
+            "psi-some" => Metric::PsiSome,
+            "psi-full" => Metric::PsiFull,
+            _ => return Err(invalid()),
+        };
+        let value = match metric {
+            Metric::Swap | Metric::Available => {
+                rustest::parse_mb::<u64>(value).map(|mb| mb as f64).ok()
+            }
+            Metric::PsiSome | Metric::PsiFull => value.trim_end_matches('%').parse().ok(),
+        }
+        .ok_or_else(invalid)?;
+
+        Ok(Threshold {
+            metric,
+            above,
+            value,
+        })
+    }
+
+    fn sample(&self, sys: &Proc, psi: Option<&Psi>) -> Option<f64> {
+        let val = match self.metric {
+            Metric::Swap => ((sys.swap_total - sys.swap_free) / 1024) as f64,
+            Metric::Available => (sys.mem_available / 1024) as f64,
+            Metric::PsiSome => psi?.some_avg10,
+            Metric::PsiFull => psi?.full_avg10,
+        };
+
+        Some(val)
+    }
+
+    fn check(&self, sys: &Proc, psi: Option<&Psi>) -> bool {
+        match self.sample(sys, psi) {
+            Some(val) if self.above => val > self.value,
+            Some(val) => val < self.value,
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let (name, unit) = match self.metric {
+            Metric::Swap => ("swap", "MB"),
+            Metric::Available => ("available", "MB"),
+            Metric::PsiSome => ("psi-some", "%"),
+            Metric::PsiFull => ("psi-full", "%"),
+        };
+        let op = if self.above { '>' } else { '<' };
+        write!(f, "{}{}{}{}", name, op, self.value, unit)
+    }
+}
+
+// a command that --on-threshold runs each time its threshold starts to hold
+struct Hook {
+    threshold: Threshold,
+    cmd: String,
+    // whether the threshold held at the previous sample
+    held: bool,
+    children: Vec<process::Child>,
+}
+
+impl Hook {
+    // parses COND:CMD
+    fn parse(s: &str) -> Result<Self, io::Error> {
+        let (cond, cmd) = s.split_once(':').ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid hook {}: expected COND:CMD", s),
+            )
+        })?;
+
+        Ok(Hook {
+            threshold: Threshold::parse(cond)?,
+            cmd: cmd.to_string(),
+            held: false,
+            children: Vec::new(),
+        })
+    }
+
+    // runs the command when the threshold is crossed, and reaps the previous runs
+    fn check(&mut self, sys: &Proc, psi: Option<&Psi>, log: &mut EventLog) {
+        self.children.retain_mut(|child| match child.try_wait() {
+            Ok(Some(status)) => {
+                log.record(format_args!("hook {} {}", child.id(), status));
+                false
+            }
+            Ok(None) => true,
+            Err(_) => false,
+        });
+
+        let held = self.threshold.check(sys, psi);
+        let crossed = held && !self.held;
+        self.held = held;
+        if !crossed {
+            return;
+        }
+
+        let value = self.threshold.sample(sys, psi).unwrap_or_default();
+        // the output would garble the screen and the JSON lines
+        let ret = process::Command::new("sh")
+            .args(["-c", &self.cmd])
+            .env("RUSTEST_THRESHOLD", self.threshold.to_string())
+            .env("RUSTEST_VALUE", value.to_string())
+            .stdin(process::Stdio::null())
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .spawn();
+        match ret {
+            Ok(child) => {
+                log.alert(
+                    "hook",
+                    &[
+                        ("THRESHOLD", &self.threshold.to_string()),
+                        ("HOOK_PID", &child.id().to_string()),
+                    ],
+                    format_args!(
+                        "{} reached, hook {} running {}",
+                        self.threshold,
+                        child.id(),
+                        self.cmd
+                    ),
+                );
+                self.children.push(child);
+            }
+            Err(err) => log.record(format_args!("hook {} failed: {}", self.cmd, err)),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    Redraw,
+    Quit,
+    Add(usize),
+    Remove(usize),
+    PageIn,
+    Advise(i32),
+    DropCaches,
+    ToggleHuge,
+    ScrollUp,
+    ScrollDown,
+    OomAdj(bool, i32),
+    CgroupLimit(&'static str, bool),
+}
+
+enum Command {
+    Action(Action),
+    Wait(time::Duration),
+}
+
+// a queue of commands that drive the same actions as the keyboard
+struct Script {
+    cmds: VecDeque<Command>,
+    wait_until: Option<time::Instant>,
+}
+
+impl Script {
+    fn new() -> Self {
+        Script {
+            cmds: VecDeque::new(),
+            wait_until: None,
+        }
+    }
+
+    fn load(path: &str, mlock: &Mlock) -> Result<Self, io::Error> {
+        let text = if path == "-" {
+            io::read_to_string(io::stdin())?
+        } else {
+            fs::read_to_string(path)?
+        };
+
+        let mut script = Self::new();
+        for cmd in text.split([';', '\n']).map(str::trim) {
+            if !cmd.is_empty() && !cmd.starts_with('#') {
+                script.parse(cmd, mlock)?;
+            }
+        }
+
+        Ok(script)
+    }
+
+    fn parse(&mut self, cmd: &str, mlock: &Mlock) -> Result<(), io::Error> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid command {}", cmd),
+            )
+        };
+
+        let words: Vec<&str> = cmd.split_ascii_whitespace().collect();
+        let heap = |name: &str| {
+            mlock
+                .heaps
+                .iter()
+                .position(|heap| heap.spec.name == name)
+                .ok_or_else(invalid)
+        };
+        let count = |idx: usize| -> Result<usize, io::Error> {
+            words
+                .get(idx)
+                .map_or(Ok(1), |count| count.parse().map_err(|_| invalid()))
+        };
+
+        let (action, count) = match words[..] {
+            ["wait", dur] => {
+                self.cmds.push_back(Command::Wait(
+                    rustest::parse_duration(dur).map_err(|_| invalid())?,
+                ));
+                return Ok(());
+            }
+            ["add", name, ..] => (Action::Add(heap(name)?), count(2)?),
+            ["remove", name, ..] => (Action::Remove(heap(name)?), count(2)?),
+            ["pagein"] => (Action::PageIn, 1),
+            ["pageout"] => (Action::Advise(libc::MADV_PAGEOUT), 1),
+            ["free"] => (Action::Advise(libc::MADV_FREE), 1),
+            ["dontneed"] => (Action::Advise(libc::MADV_DONTNEED), 1),
+            ["dropcaches"] => (Action::DropCaches, 1),
+            ["huge"] => (Action::ToggleHuge, 1),
+            ["oom", who, delta] => {
+                let delta = delta.parse().map_err(|_| invalid())?;
+                match who {
+                    "self" => (Action::OomAdj(false, delta), 1),
+                    "children" => (Action::OomAdj(true, delta), 1),
+                    _ => return Err(invalid()),
+                }
+            }
+            ["cgroup", file, dir] => {
+                let file = match file {
+                    "max" => "memory.max",
+                    "high" => "memory.high",
+                    _ => return Err(invalid()),
+                };
+                match dir {
+                    "raise" => (Action::CgroupLimit(file, true), 1),
+                    "lower" => (Action::CgroupLimit(file, false), 1),
+                    _ => return Err(invalid()),
+                }
+            }
+            ["quit"] => (Action::Quit, 1),
+            _ => return Err(invalid()),
+        };
+
+        for _ in 0..count {
+            self.cmds.push_back(Command::Action(action));
+        }
+
+        Ok(())
+    }
+
+    // returns the next action unless waiting
+    fn next_action(&mut self) -> Option<Action> {
+        if self
+            .wait_until
+            .is_some_and(|until| time::Instant::now() < until)
+        {
+            return None;
+        }
+        self.wait_until = None;
+
+        match self.cmds.pop_front()? {
+            Command::Action(action) => Some(action),
+            Command::Wait(dur) => {
+                self.wait_until = Some(time::Instant::now() + dur);
+                None
+            }
+        }
+    }
+
+    // returns how long the keyboard can be polled before the script resumes, or None when the
+    // script has nothing left to do
+    fn poll_timeout_ms(&self) -> Option<i32> {
+        match self.wait_until {
+            Some(until) => {
+                let remaining = until.saturating_duration_since(time::Instant::now());
+                Some((remaining.as_millis() as i32).min(POLL_TIMEOUT_MS))
+            }
+            None if self.cmds.is_empty() => None,
+            None => Some(0),
+        }
+    }
+}
+
+// records actions as a script with waits that reproduce their timing
+struct Recorder {
+    file: fs::File,
+    last: time::Instant,
+    // an action was recorded and has not finished
+    pending: bool,
+}
+
+impl Recorder {
+    fn new(path: &str) -> Result<Self, io::Error> {
+        Ok(Recorder {
+            file: fs::File::create(path)?,
+            last: time::Instant::now(),
+            pending: false,
+        })
+    }
+
+    fn command(action: Action, mlock: &Mlock) -> Option<String> {
+        let cmd = match action {
+            Action::Redraw | Action::ScrollUp | Action::ScrollDown => return None,
+            Action::Quit => "quit".to_string(),
+            Action::Add(idx) => format!("add {}", mlock.heaps[idx].spec.name),
+            Action::Remove(idx) => format!("remove {}", mlock.heaps[idx].spec.name),
+            Action::PageIn => "pagein".to_string(),
+            Action::Advise(libc::MADV_PAGEOUT) => "pageout".to_string(),
+            Action::Advise(libc::MADV_FREE) => "free".to_string(),
+            Action::Advise(libc::MADV_DONTNEED) => "dontneed".to_string(),
+            Action::Advise(_) => return None,
+            Action::DropCaches => "dropcaches".to_string(),
+            Action::ToggleHuge => "huge".to_string(),
+            Action::OomAdj(children, delta) => {
+                let who = if children { "children" } else { "self" };
+                format!("oom {} {}", who, delta)
+            }
+            Action::CgroupLimit(file, raise) => {
+                let file = file.trim_start_matches("memory.");
+                let dir = if raise { "raise" } else { "lower" };
+                format!("cgroup {} {}", file, dir)
+            }
+        };
+
+        Some(cmd)
+    }
+
+    fn record(&mut self, action: Action, mlock: &Mlock) {
+        let Some(cmd) = Self::command(action, mlock) else {
+            return;
+        };
+
+        let _ = writeln!(
+            self.file,
+            "wait {}ms\n{}",
+            self.last.elapsed().as_millis(),
+            cmd
+        );
+        self.pending = true;
+    }
+
+    // waits are measured from when the previous action finished, matching
+    // how a script resumes
+    fn finish(&mut self) {
+        if self.pending {
+            self.last = time::Instant::now();
+            self.pending = false;
+        }
+    }
+}
+
+// a unix socket that accepts script commands, one per line, so that other processes can drive
+// the heaps
+//
+// Each command is answered with "ok" once queued or "error: ..." when invalid, except "status",
+// which is answered with the latest sample as a JSON line.
+struct Control {
+    path: String,
+    listener: net::UnixListener,
+    // clients and their partial lines
+    clients: Vec<(net::UnixStream, Vec<u8>)>,
+}
+
+impl Control {
+    fn bind(path: &str) -> Result<Self, io::Error> {
+        // a stale socket from a previous run
+        if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+            fs::remove_file(path)?;
+        }
+
+        let listener = net::UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Control {
+            path: path.to_string(),
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    // reads the pending commands without blocking and queues them to script
+    fn poll(&mut self, script: &mut Script, mlock: &Mlock, status: &str, log: &mut EventLog) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push((stream, Vec::new()));
+            }
+        }
+
+        self.clients.retain_mut(|(stream, pending)| {
+            let mut buf = [0; 1024];
+            let alive = loop {
+                match stream.read(&mut buf) {
+                    Ok(0) => break false,
+                    Ok(len) => pending.extend_from_slice(&buf[..len]),
+                    Err(err) => break err.kind() == io::ErrorKind::WouldBlock,
+                }
+            };
+
+            while let Some(end) = pending.iter().position(|&c| c == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let cmd = String::from_utf8_lossy(&line);
+                let cmd = cmd.trim();
+                if cmd.is_empty() {
+                    continue;
+                }
+
+                let reply = if cmd == "status" {
+                    status.to_string()
+                } else {
+                    match script.parse(cmd, mlock) {
+                        Ok(()) => {
+                            log.record(format_args!("control: {}", cmd));
+                            String::from("ok")
+                        }
+                        Err(err) => format!("error: {}", err),
+                    }
+                };
+                let _ = writeln!(stream, "{}", reply);
+            }
+
+            alive
+        });
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// returns the swap and psi summary for the systemd status
+fn notify_status(sys: &Proc, psi: Option<&Psi>) -> String {
+    let page_kb = sys.page_size as u64 / 1024;
+    let mut status = format!(
+        "swap used {} MB, in {} KB, out {} KB",
+        (sys.swap_total - sys.swap_free) / 1024,
+        sys.pswpin_delta * page_kb,
+        sys.pswpout_delta * page_kb
+    );
+    if let Some(psi) = psi {
+        status.push_str(&format!(
+            ", psi some {:.2}%, full {:.2}%",
+            psi.some_avg10, psi.full_avg10
+        ));
+    }
+
+    status
+}
+
+// returns the latest sample and the heap sizes in the Prometheus format
+fn metrics_text(sample: Option<&rustest::JsonLine>, mlock: &Mlock) -> String {
+    let mut metrics = rustest::Metrics::new("rustest_mlock");
+    if let Some(sample) = sample {
+        metrics.push_line(sample);
+    }
+    for heap in &mlock.heaps {
+        metrics.gauge(
+            "heap_bytes",
+            &[("heap", &heap.spec.name)],
+            heap.size_mb() * 1024 * 1024,
+        );
+    }
+
+    metrics.render()
+}
+
+// returns the heaps, the recent samples, and the recent events as a JSON object
+fn status_json(samples: &VecDeque<rustest::JsonLine>, mlock: &Mlock, log: &EventLog) -> String {
+    let heaps: Vec<_> = mlock.heaps.iter().map(Heap::status).collect();
+    let samples: Vec<_> = samples.iter().collect();
+    let events = &log.events[log.events.len().saturating_sub(STATUS_EVENTS)..];
+
+    rustest::JsonLine::new("status")
+        .field("heaps", heaps)
+        .field("samples", samples)
+        .field("events", events)
+        .to_string()
+}
+
+fn wait_action(term: &mut rustest::Term, mlock: &Mlock, script: &mut Script) -> Action {
+    match script.next_action() {
+        Some(action) => action,
+        None => term_wait_action(term, mlock, script.poll_timeout_ms()),
+    }
+}
+
+fn term_wait_action(term: &mut rustest::Term, mlock: &Mlock, timeout_ms: Option<i32>) -> Action {
+    // the script resumes on time even when plain frames are further apart
+    let key = match timeout_ms {
+        Some(timeout_ms) => term.poll_within(timeout_ms),
+        None => term.poll(POLL_TIMEOUT_MS),
+    };
+    let key = match key {
+        Ok(Some(key)) => key,
+        Ok(None) => return Action::Redraw,
+        Err(_) => return Action::Quit,
+    };
+
+    match key.modifiers {
+        event::KeyModifiers::CONTROL => match key.code {
+            event::KeyCode::Char('c') | event::KeyCode::Char('d') => Action::Quit,
+            _ => Action::Redraw,
+        },
+        event::KeyModifiers::SHIFT | event::KeyModifiers::NONE => match key.code {
+            event::KeyCode::Char('p') | event::KeyCode::Char('P') => Action::PageIn,
+            event::KeyCode::Char('o') | event::KeyCode::Char('O') => {
+                Action::Advise(libc::MADV_PAGEOUT)
+            }
+            event::KeyCode::Char('f') | event::KeyCode::Char('F') => {
+                Action::Advise(libc::MADV_FREE)
+            }
+            event::KeyCode::Char('x') | event::KeyCode::Char('X') => {
+                Action::Advise(libc::MADV_DONTNEED)
+            }
+            event::KeyCode::Char('h') | event::KeyCode::Char('H') => Action::ToggleHuge,
+            event::KeyCode::Char('d') | event::KeyCode::Char('D') => Action::DropCaches,
+            event::KeyCode::Char('>') => Action::OomAdj(false, OOM_ADJ_STEP),
+            event::KeyCode::Char('<') => Action::OomAdj(false, -OOM_ADJ_STEP),
+            event::KeyCode::Char('.') => Action::OomAdj(true, OOM_ADJ_STEP),
+            event::KeyCode::Char(',') => Action::OomAdj(true, -OOM_ADJ_STEP),
+            event::KeyCode::Char('m') => Action::CgroupLimit("memory.max", false),
+            event::KeyCode::Char('M') => Action::CgroupLimit("memory.max", true),
+            event::KeyCode::Char('n') => Action::CgroupLimit("memory.high", false),
+            event::KeyCode::Char('N') => Action::CgroupLimit("memory.high", true),
+            event::KeyCode::Up => Action::ScrollUp,
+            event::KeyCode::Down => Action::ScrollDown,
+            event::KeyCode::Char('q') | event::KeyCode::Esc => Action::Quit,
+            event::KeyCode::Char(key) => mlock.find_key(key).unwrap_or(Action::Redraw),
             _ => Action::Redraw,
         },
         _ => Action::Redraw,
@@ -293,63 +2320,834 @@ fn term_wait_action(term: &mut rustest::Term) -> Action {
 }
 
 fn print_help() {
-    println!("usage:");
-    println!("  +/-: add/remove locked mappings");
-    println!("  ]/[: add/remove unlocked mappings");
-    println!("  p: page in unlocked mappings");
+    println!("usage: mlock [init-MB] [options]");
+    println!("  sizes in MB also take K, M, G, or T suffixes, such as 1.5G");
+    println!("  durations in SECS also take ms, s, m, h, or d suffixes, such as 10m");
+    println!("  --config PATH: read options from the [mlock] table of a TOML file, where keys are");
+    println!("    option names, true enables a flag, and arrays repeat an option");
+    println!(
+        "  --chunk-size MB: size of the mappings heaps grow and shrink by (default: {})",
+        CHUNK_SIZE_MB
+    );
+    println!("  --balloon MB: keep MemAvailable near MB with locked mappings");
+    println!("  --chunks HEAP:N: start with N chunks in HEAP, repeatable");
+    println!("  --save PATH: keep the chunk size, fill, hugepage mode, heaps, and their chunks in");
+    println!("    PATH, updated as they change");
+    println!("  --restore PATH: rebuild the heaps saved with --save; options given after it win");
+    println!("  --threads N: threads that fill and page in each mapping (default: 1)");
+    println!("  --file-dir DIR: directory for file-backed locked mappings");
+    println!("  --event-log PATH: also write the event log to PATH");
+    println!("  --cgroup NAME: run in a new cgroup v2 leaf under /sys/fs/cgroup");
+    println!("  --cgroup-trigger some|full:STALL_MS:WINDOW_MS: a memory.pressure trigger of the");
+    println!("    cgroup to log (default: some:150:2000)");
+    println!("  --fill zero|byte|text|random[:SEED]|mixed:PCT: content of filled mappings");
+    println!("  --nontemporal: fill with non-temporal stores, to keep the CPU caches of the rest");
+    println!("    of the system intact");
+    println!("  --heap NAME:PROPS:ADD/REMOVE: define a heap, replacing the defaults");
+    println!("    PROPS: comma-separated locked, file, child, noreserve, nohuge, thp, hugetlb,");
+    println!("    madv=ADVICE");
+    println!(
+        "  --exit-when METRIC>VALUE: exit with status {} once the condition holds",
+        EXIT_THRESHOLD
+    );
+    println!("    METRIC: swap (MB), available (MB), psi-some (%), psi-full (%); < also works");
+    println!("  --on-threshold METRIC>VALUE:CMD: run CMD with sh -c each time the condition");
+    println!("    starts to hold, with RUSTEST_THRESHOLD and RUSTEST_VALUE set; its output is");
+    println!("    discarded, repeatable");
+    println!("  --soak SECS: exit after SECS and print a summary of the counters");
+    println!("  --script PATH: run commands from PATH (- for stdin), separated by ; or lines");
+    println!("    add|remove HEAP [N], wait SECS[ms|s|m|h|d], pagein, pageout, free, dontneed,");
+    println!("    dropcaches, huge, oom self|children DELTA, cgroup max|high raise|lower, quit");
+    println!("  --record PATH: record actions to PATH as a script");
+    println!("  --replay PATH: replay actions recorded with --record");
+    println!("  --thp: allocate new anonymous mappings with MADV_HUGEPAGE");
+    println!("  --hugetlb: allocate new anonymous mappings from hugetlb");
+    println!("  --plain: print a line per sample rather than redrawing");
+    println!("  --interval SECS: time between plain samples (default: 1)");
+    println!("  --json: print samples, events, and the soak summary as JSON lines");
+    println!("  --log FILE: append a CSV row per sample to FILE");
+    println!("  --http [HOST]:PORT: serve /metrics in the Prometheus format and /status as JSON");
+    println!("    on HOST, or on localhost without it; 0.0.0.0 listens on all interfaces");
+    println!("  --control PATH: accept script commands and status, one per line, on a unix socket");
+    println!(
+        "  --summary FILE: on exit, write the summary, exit reason, and events to FILE as JSON"
+    );
+    println!("  --trace: log the vmscan, compaction, and oom tracepoints of tracefs as events");
+    println!("  --syslog: also send failed adds, killed children, oom kills, psi spikes, and");
+    println!("    thresholds to journald with RUSTEST_EVENT and other fields, or to syslog");
+    println!(
+        "under systemd, READY, WATCHDOG, and STATUS with swap and psi are sent to NOTIFY_SOCKET"
+    );
+    println!("keys:");
+    println!("  +/-: add/remove locked mappings (default heaps)");
+    println!("  ]/[: add/remove unlocked mappings (default heaps)");
+    println!("  )/(: add/remove file-backed locked mappings (default heaps)");
+    println!("  p: page in and verify unlocked mappings");
+    println!("  o: page out unlocked mappings with MADV_PAGEOUT");
+    println!("  f: lazily free unlocked mappings with MADV_FREE");
+    println!("  x: discard unlocked mappings with MADV_DONTNEED");
+    println!("  d: drop page cache (requires root)");
+    println!("  h: cycle hugepage mode (none/thp/hugetlb) for new mappings");
+    println!("  >/<: raise/lower our oom_score_adj (inherited by new children)");
+    println!("  ./,: raise/lower oom_score_adj of child heaps");
+    println!("  m/M: lower/raise cgroup memory.max");
+    println!("  n/N: lower/raise cgroup memory.high");
+    println!("  up/down: scroll the event log");
     println!("  q: quit");
 }
 
-fn main() -> Result<(), io::Error> {
-    let init_mb: usize = env::args()
-        .nth(1)
-        .map(|s| s.parse().unwrap_or_default())
-        .unwrap_or_default();
-    let init_count = init_mb / CHUNK_SIZE_MB;
+struct Args {
+    init_mb: usize,
+    balloon_mb: Option<u64>,
+    file_dir: String,
+    huge: HugeMode,
+    event_log: Option<String>,
+    heaps: Vec<HeapSpec>,
+    cgroup: Option<String>,
+    cgroup_trigger: String,
+    fill: rustest::Fill,
+    exit_when: Vec<Threshold>,
+    hooks: Vec<Hook>,
+    soak: Option<time::Duration>,
+    script: Option<String>,
+    record: Option<String>,
+    plain: bool,
+    interval: time::Duration,
+    json: bool,
+    log: Option<String>,
+    http: Option<String>,
+    control: Option<String>,
+    syslog: bool,
+    trace: bool,
+    summary: Option<String>,
+    chunk_mb: usize,
+    chunks: Vec<(String, usize)>,
+    save: Option<String>,
+    threads: usize,
+    nontemporal: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        init_mb: 0,
+        balloon_mb: None,
+        file_dir: env::temp_dir().to_string_lossy().into_owned(),
+        huge: HugeMode::None,
+        event_log: None,
+        heaps: Vec::new(),
+        cgroup: None,
+        cgroup_trigger: String::from("some:150:2000"),
+        fill: rustest::Fill::Zero,
+        exit_when: Vec::new(),
+        hooks: Vec::new(),
+        soak: None,
+        script: None,
+        record: None,
+        plain: false,
+        interval: time::Duration::from_secs(1),
+        json: false,
+        log: None,
+        http: None,
+        control: None,
+        syslog: false,
+        trace: false,
+        summary: None,
+        chunk_mb: CHUNK_SIZE_MB,
+        chunks: Vec::new(),
+        save: None,
+        threads: 1,
+        nontemporal: false,
+    };
 
-    let mut mlock = Mlock::new();
-    for _ in 0..init_count {
-        let _ = mlock.add(MlockHeap::Locked);
+    // config files and saved snapshots provide defaults that the command line overrides
+    let cmdline: Vec<String> = env::args().skip(1).collect();
+    let mut all_args = Vec::new();
+    for (pos, _) in cmdline
+        .iter()
+        .enumerate()
+        .filter(|(_, arg)| *arg == "--config" || *arg == "--restore")
+    {
+        let path = cmdline.get(pos + 1).map_or("", String::as_str);
+        all_args.extend(rustest::Config::load(path)?.args("mlock"));
     }
+    let cmdline_len = cmdline.len();
+    all_args.extend(cmdline);
 
-    print_help();
-    println!();
+    // the first --heap or --chunks on the command line replaces, rather than adds to, those
+    // from the files
+    let mut replaced = Vec::new();
+    let mut iter = all_args.into_iter();
+    while let Some(arg) = iter.next() {
+        let from_cmdline = iter.len() < cmdline_len;
+        if from_cmdline && matches!(arg.as_str(), "--heap" | "--chunks") && !replaced.contains(&arg)
+        {
+            if arg == "--heap" {
+                args.heaps.clear();
+            } else {
+                args.chunks.clear();
+            }
+            replaced.push(arg.clone());
+        }
+
+        match arg.as_str() {
+            "--balloon" => {
+                args.balloon_mb = Some(rustest::parse_mb(&iter.next().unwrap_or_default())?);
+            }
+            "--file-dir" => {
+                if let Some(dir) = iter.next() {
+                    args.file_dir = dir;
+                }
+            }
+            "--event-log" => args.event_log = iter.next(),
+            "--cgroup" => args.cgroup = iter.next(),
+            "--cgroup-trigger" => args.cgroup_trigger = iter.next().unwrap_or_default(),
+            "--fill" => {
+                let fill = iter.next().unwrap_or_default();
+                args.fill = rustest::Fill::parse(&fill).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid fill {}", fill),
+                    )
+                })?;
+            }
+            "--heap" => {
+                let spec = iter.next().unwrap_or_default();
+                args.heaps.push(HeapSpec::parse(&spec)?);
+            }
+            "--exit-when" => {
+                let cond = iter.next().unwrap_or_default();
+                args.exit_when.push(Threshold::parse(&cond)?);
+            }
+            "--on-threshold" => args
+                .hooks
+                .push(Hook::parse(&iter.next().unwrap_or_default())?),
+            "--soak" => {
+                args.soak = Some(rustest::parse_duration(&iter.next().unwrap_or_default())?);
+            }
+            "--script" | "--replay" => args.script = iter.next(),
+            "--record" => args.record = iter.next(),
+            "--thp" => args.huge = HugeMode::Thp,
+            "--hugetlb" => args.huge = HugeMode::Hugetlb,
+            "--plain" => args.plain = true,
+            "--nontemporal" => args.nontemporal = true,
+            "--json" => args.json = true,
+            "--log" => args.log = iter.next(),
+            "--http" => args.http = iter.next(),
+            "--control" => args.control = iter.next(),
+            "--syslog" => args.syslog = true,
+            "--trace" => args.trace = true,
+            "--summary" => args.summary = iter.next(),
+            "--config" | "--restore" => {
+                iter.next();
+            }
+            "--chunks" => {
+                let spec = iter.next().unwrap_or_default();
+                let chunks = spec
+                    .rsplit_once(':')
+                    .and_then(|(name, count)| Some((name.to_string(), count.parse().ok()?)))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("invalid chunks {}", spec),
+                        )
+                    })?;
+                args.chunks.push(chunks);
+            }
+            "--save" => args.save = iter.next(),
+            "--threads" => {
+                args.threads =
+                    rustest::parse_num::<usize>(&iter.next().unwrap_or_default())?.max(1);
+            }
+            "--chunk-size" => args.chunk_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--interval" => {
+                args.interval = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.init_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    if args.heaps.is_empty() {
+        args.heaps = HeapSpec::defaults();
+    }
+    args.chunk_mb = args.chunk_mb.max(1);
+
+    Ok(args)
+}
+
+fn main() -> Result<process::ExitCode, io::Error> {
+    let mut args = parse_args()?;
+    let init_count = args.init_mb / args.chunk_mb;
+
+    let mut log = EventLog::new(args.event_log.as_deref(), args.json, args.syslog)?;
+    let mut csv = args.log.as_deref().map(rustest::CsvLog::open).transpose()?;
+    let http = args
+        .http
+        .as_deref()
+        .map(rustest::HttpServer::bind)
+        .transpose()?;
+    if let Some(http) = &http {
+        log.record(format_args!(
+            "serving /metrics and /status on http://{}",
+            http.local_addr()?
+        ));
+    }
+    let mut control = args.control.as_deref().map(Control::bind).transpose()?;
+    let mut kmsg = rustest::Kmsg::open()
+        .inspect_err(|err| log.record(format_args!("not watching /dev/kmsg: {}", err)))
+        .ok();
+    let mut tracefs = args
+        .trace
+        .then(|| rustest::Tracefs::open(&rustest::MM_TRACEPOINTS))
+        .transpose()?;
+
+    let cgroup = match &args.cgroup {
+        Some(name) => {
+            let mut cgroup = rustest::Cgroup::create(name)?;
+            cgroup.enter()?;
+            Some(cgroup)
+        }
+        None => None,
+    };
+    let mut watcher = cgroup
+        .as_ref()
+        .map(|cgroup| cgroup.watch(Some(&args.cgroup_trigger)))
+        .transpose()?;
 
-    let mut term = rustest::Term::new()?;
+    let specs = mem::take(&mut args.heaps);
+    let mut mlock = Mlock::new(
+        specs,
+        args.file_dir.clone(),
+        args.chunk_mb,
+        args.huge,
+        args.fill,
+    );
+    mlock.threads = args.threads;
+    mlock.nontemporal = args.nontemporal;
+
+    // locked heaps grow on demand, so lift RLIMIT_MEMLOCK as far as allowed
+    if mlock.heaps.iter().any(|heap| heap.spec.locked) {
+        rustest::Rlimit::Memlock.reserve(None)?;
+        if let (Some(cur), _) = rustest::Rlimit::Memlock.get()? {
+            log.record(format_args!("RLIMIT_MEMLOCK is {} MB", cur / 1024 / 1024));
+        }
+    }
+
+    let mut init_counts: Vec<(usize, usize)> = mlock
+        .balloon_heap()
+        .map(|idx| (idx, init_count))
+        .into_iter()
+        .collect();
+    for (name, count) in &args.chunks {
+        let idx = mlock
+            .heaps
+            .iter()
+            .position(|heap| heap.spec.name == *name)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown heap {}", name),
+                )
+            })?;
+        init_counts.push((idx, *count));
+    }
+    for (idx, count) in init_counts {
+        for _ in 0..count {
+            if let Err(err) = mlock.add(idx) {
+                let name = &mlock.heaps[idx].spec.name;
+                log.alert(
+                    "add_failed",
+                    &[("HEAP", name), ("ERROR", &err.to_string())],
+                    format_args!("add {} failed: {}", name, err),
+                );
+                break;
+            }
+        }
+    }
+
+    let mut script = match &args.script {
+        Some(path) => Script::load(path, &mlock)?,
+        None => Script::new(),
+    };
+
+    let mut snapshot = args.save.as_deref().map(Snapshot::new);
+
+    let mut recorder = match &args.record {
+        Some(path) => Some(Recorder::new(path)?),
+        None => None,
+    };
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let mut term = if args.json {
+        rustest::Term::new_json(args.interval)
+    } else if args.plain {
+        rustest::Term::new_plain(args.interval)
+    } else {
+        rustest::Term::new()?
+    };
+
+    let mut notify = rustest::SdNotify::from_env();
+    if let Some(notify) = &notify {
+        notify.ready("started");
+    }
 
     let mut sys_prev = None;
+    let mut psi_prev: Option<Psi> = None;
+    let mut rusage_prev = rustest::Rusage::collect().unwrap_or_default();
+    let mut swap_in_history = History::new();
+    let mut swap_out_history = History::new();
+    let mut swap_out_seen = false;
+    let mut low_mem_level = None;
+    let mut screen = Screen::new();
+    let mut exit_code = process::ExitCode::SUCCESS;
+    let mut exit_reason = "quit";
+    let mut summary = Summary::new();
+    let mut samples = VecDeque::new();
+    let start = time::Instant::now();
+    let mut sample_prev: Option<time::Instant> = None;
     loop {
+        mlock.reap(&mut log);
+        screen.begin();
+
         let sys = Proc::collect(sys_prev);
         let pid = ProcSelf::collect();
+        let rusage = rustest::Rusage::collect().unwrap_or_default();
+        let rusage_delta = rusage.delta(&rusage_prev);
 
-        term.cmd_fmt(format_args!("mlock:     {}\r\n", &mlock));
-        term.cmd_fmt(format_args!("proc self: {}\r\n", &pid));
-        term.cmd_fmt(format_args!("proc sys:  {}\r\n", &sys));
+        for heap in &mlock.heaps {
+            let label = format!("{}:", heap.spec.name);
+            if heap.spec.child {
+                let children = ProcSelf::collect_pids(heap.child_pids());
+                screen.row(
+                    &mut term,
+                    &label,
+                    format_args!("{}, children {}", heap, &children),
+                );
+            } else {
+                screen.row(&mut term, &label, format_args!("{}", heap));
+            }
+        }
+        screen.row(
+            &mut term,
+            "proc self:",
+            format_args!("{}, {}", &pid, &rusage_delta),
+        );
+        screen.row(&mut term, "proc sys:", format_args!("{}", &sys));
+        screen.row(
+            &mut term,
+            "lazyfree:",
+            format_args!(
+                "pglazyfree {:5} MB, pglazyfreed {:5} MB",
+                sys.pglazyfree * sys.page_size as u64 / 1024 / 1024,
+                sys.pglazyfreed * sys.page_size as u64 / 1024 / 1024,
+            ),
+        );
+        swap_in_history.push(sys.pswpin_delta * sys.page_size as u64 / 1024);
+        swap_out_history.push(sys.pswpout_delta * sys.page_size as u64 / 1024);
+        screen.plain_row(&mut term, "swap in:", format_args!("{}", &swap_in_history));
+        screen.plain_row(
+            &mut term,
+            "swap out:",
+            format_args!("{}", &swap_out_history),
+        );
+        let psi = Psi::collect(psi_prev.as_ref());
+        if let Some(psi) = &psi {
+            screen.row(&mut term, "psi:", format_args!("{}", psi));
+        }
+        screen.row(
+            &mut term,
+            "hugepage:",
+            format_args!(
+                "mode {}, thp {:5} MB, hugetlb {}/{} free",
+                mlock.huge,
+                sys.anon_huge_pages / 1024,
+                sys.huge_pages_free,
+                sys.huge_pages_total,
+            ),
+        );
+        let self_adj = rustest::read_oom("self", "oom_score_adj").unwrap_or_default();
+        let self_score = rustest::read_oom("self", "oom_score").unwrap_or_default();
+        if let Some(adj) = mlock.child_oom_adj {
+            screen.row(
+                &mut term,
+                "oom:",
+                format_args!(
+                    "self adj {:5}, score {:5}, children adj {:5}",
+                    self_adj, self_score, adj
+                ),
+            );
+        } else {
+            screen.row(
+                &mut term,
+                "oom:",
+                format_args!("self adj {:5}, score {:5}", self_adj, self_score),
+            );
+        }
+        if let Some(cgroup) = &cgroup {
+            screen.row(
+                &mut term,
+                "cgroup:",
+                format_args!(
+                    "{} current {} MB, high {}, max {}, oom {}, oom_kill {}",
+                    cgroup.name(),
+                    cgroup.read_value("memory.current").unwrap_or_default() / 1024 / 1024,
+                    format_limit(cgroup.read_value("memory.high").unwrap_or(u64::MAX)),
+                    format_limit(cgroup.read_value("memory.max").unwrap_or(u64::MAX)),
+                    cgroup
+                        .read_keyed("memory.events", "oom")
+                        .unwrap_or_default(),
+                    cgroup
+                        .read_keyed("memory.events", "oom_kill")
+                        .unwrap_or_default(),
+                ),
+            );
+        }
+        if let Some(zram) = Zram::collect() {
+            screen.row(&mut term, "zram:", format_args!("{}", &zram));
+        }
+        // Android kernels report DMA-BUF/ION memory that does not show up in the rows above
+        let meminfo = rustest::Meminfo::collect().unwrap_or_default();
+        let dmabuf = rustest::DmaBufInfo::collect(&meminfo);
+        if dmabuf.is_present() {
+            let [heaps, pools] = [dmabuf.heaps, dmabuf.pools]
+                .map(|kb| kb.map_or("-".to_string(), |kb| (kb / 1024).to_string()));
+            screen.row(
+                &mut term,
+                "dmabuf:",
+                format_args!(
+                    "heaps {:>5} MB, pools {:>5} MB, kreclaimable non-slab {:5} MB",
+                    heaps,
+                    pools,
+                    meminfo.k_reclaimable_other() / 1024
+                ),
+            );
+        }
+        let low_mem = LowMem::collect();
+        if let Some(low_mem) = &low_mem {
+            screen.row(&mut term, "chromeos:", format_args!("{}", low_mem));
+
+            let level = low_mem.level();
+            if level != low_mem_level {
+                match &level {
+                    Some(level) => log.alert(
+                        "chromeos_margin",
+                        &[("MARGIN", level)],
+                        format_args!("chromeos below {} margin", level),
+                    ),
+                    None => log.record(format_args!("chromeos above all margins")),
+                }
+                low_mem_level = level;
+            }
+        }
+        if let Some(target_mb) = args.balloon_mb {
+            screen.row(
+                &mut term,
+                "balloon:",
+                format_args!(
+                    "target {:5} MB, available {:5} MB",
+                    target_mb,
+                    sys.mem_available / 1024,
+                ),
+            );
+
+            mlock.balloon(target_mb * 1024, sys.mem_available);
+        }
+
+        if let Some(notify) = &mut notify {
+            notify.status(&notify_status(&sys, psi.as_ref()));
+            notify.watchdog();
+        }
+
+        for event in watcher
+            .as_mut()
+            .map(|watcher| watcher.poll())
+            .unwrap_or_default()
+        {
+            let msg = format_args!(
+                "cgroup {} +{}, total {}",
+                event.key, event.delta, event.total
+            );
+            match event.key.as_str() {
+                "oom" | "oom_kill" | "oom_group_kill" | "max" | "pressure" => log.alert(
+                    &format!("cgroup_{}", event.key),
+                    &[("CGROUP_TOTAL", &event.total.to_string())],
+                    msg,
+                ),
+                _ => log.record(msg),
+            }
+        }
+        for msg in kmsg.as_mut().map(|kmsg| kmsg.read()).unwrap_or_default() {
+            if rustest::is_oom_msg(&msg) {
+                log.alert("oom", &[], format_args!("kmsg: {}", msg));
+            } else if rustest::is_memory_msg(&msg) {
+                log.record(format_args!("kmsg: {}", msg));
+            }
+        }
+        // trace events are logged at the time they happened rather than when they were read
+        let events = tracefs
+            .as_mut()
+            .map(|tracefs| tracefs.read())
+            .unwrap_or_default();
+        for event in events.iter().take(TRACE_EVENTS) {
+            let elapsed = log.start.elapsed().saturating_sub(event.age());
+            log.record_at(
+                elapsed,
+                format_args!("trace: {}: {}", event.name, event.msg),
+            );
+        }
+        if events.len() > TRACE_EVENTS {
+            log.record(format_args!(
+                "trace: {} more events",
+                events.len() - TRACE_EVENTS
+            ));
+        }
+
+        if !swap_out_seen && sys.pswpout_delta > 0 {
+            log.record(format_args!("first swap-out observed"));
+            swap_out_seen = true;
+        }
+        if let Some(psi) = &psi {
+            let prev_avg10 = psi_prev.as_ref().map_or(0.0, |prev| prev.some_avg10);
+            if psi.some_avg10 >= PSI_SPIKE_PCT && prev_avg10 < PSI_SPIKE_PCT {
+                log.alert(
+                    "psi_spike",
+                    &[("PSI_SOME_AVG10", &psi.some_avg10.to_string())],
+                    format_args!("psi spike: some avg10 {:.2}%", psi.some_avg10),
+                );
+            }
+        }
+
+        let mut status = String::new();
+        if args.json || csv.is_some() || http.is_some() || control.is_some() {
+            let mut line = rustest::JsonLine::new("sample").field("elapsed_ns", start.elapsed());
+            pid.push_json("self", &mut line);
+            line.push("self_maxrss_kb", rusage_delta.maxrss_kb);
+            line.push("self_minflt", rusage_delta.minflt);
+            line.push("self_majflt", rusage_delta.majflt);
+            line.push("self_nvcsw", rusage_delta.nvcsw);
+            ProcSelf::collect_pids(mlock.child_pids()).push_json("children", &mut line);
+            sys.push_json(&mut line);
+            if let Some(psi) = &psi {
+                psi.push_json(&mut line);
+            }
+            line.push("oom_score_adj", self_adj);
+            line.push("oom_score", self_score);
+            if let Some(cgroup) = &cgroup {
+                line.push(
+                    "memory_current_bytes",
+                    cgroup.read_value("memory.current").unwrap_or_default(),
+                );
+            }
+            if let Some(zram) = Zram::collect() {
+                zram.push_json(&mut line);
+            }
+            if dmabuf.is_present() {
+                line.push("dmabuf_heaps_kb", dmabuf.heaps);
+                line.push("dmabuf_pools_kb", dmabuf.pools);
+                line.push("kreclaimable_kb", meminfo.k_reclaimable);
+            }
+            if let Some(low_mem) = &low_mem {
+                low_mem.push_json(&mut line);
+            }
+
+            // the heaps are reported separately
+            if http.is_some() {
+                if samples.len() == HISTORY_LEN {
+                    samples.pop_front();
+                }
+                samples.push_back(line.clone());
+            }
+
+            for heap in &mlock.heaps {
+                line.push(&format!("heap_{}_mb", heap.spec.name), heap.size_mb());
+            }
+            if control.is_some() {
+                status = line.to_string();
+            }
+            if let Some(csv) = &mut csv {
+                csv.append(&line)?;
+            }
+            if args.json {
+                line.emit();
+            }
+        }
+
+        for (idx, event) in log.visible().iter().enumerate() {
+            let label = if idx == 0 { "log:" } else { "" };
+            screen.plain_row(&mut term, label, format_args!("{}", event));
+        }
         term.cmd_flush();
 
+        for hook in &mut args.hooks {
+            hook.check(&sys, psi.as_ref(), &mut log);
+        }
+        if let Some(threshold) = args
+            .exit_when
+            .iter()
+            .find(|threshold| threshold.check(&sys, psi.as_ref()))
+        {
+            log.alert(
+                "threshold",
+                &[("THRESHOLD", &threshold.to_string())],
+                format_args!("{} reached, exiting", threshold),
+            );
+            term.cmd_fmt(format_args!("{} reached, exiting\r\n", threshold));
+            exit_code = process::ExitCode::from(EXIT_THRESHOLD);
+            exit_reason = "threshold";
+            break;
+        }
+
+        let now = time::Instant::now();
+        let secs = sample_prev.map_or(0.0, |prev| (now - prev).as_secs_f64());
+        summary.add(&sys, psi.as_ref(), secs);
+        sample_prev = Some(now);
+        if args.soak.is_some_and(|soak| now - start >= soak) {
+            log.record(format_args!("soak finished"));
+            exit_reason = "soak";
+            break;
+        }
+
         sys_prev = Some(sys);
+        psi_prev = psi;
+        rusage_prev = rusage;
+
+        if let Some(http) = &http {
+            http.poll(|path| match path {
+                "/metrics" => Some((
+                    "text/plain; version=0.0.4",
+                    metrics_text(samples.back(), &mlock),
+                )),
+                "/status" => Some(("application/json", status_json(&samples, &mlock, &log))),
+                _ => None,
+            });
+        }
+        if let Some(control) = &mut control {
+            control.poll(&mut script, &mlock, &status, &mut log);
+        }
 
-        match term_wait_action(&mut term) {
+        let action = wait_action(&mut term, &mlock, &mut script);
+        if let Some(recorder) = &mut recorder {
+            recorder.record(action, &mlock);
+        }
+        if let Some(cmd) = Recorder::command(action, &mlock) {
+            summary.action(cmd);
+        }
+
+        match action {
             Action::Redraw => (),
             Action::Quit => break,
-            Action::Add(heap) => {
-                let _ = mlock.add(heap);
+            Action::Add(idx) => {
+                let ret = mlock.add(idx);
+                let name = &mlock.heaps[idx].spec.name;
+                match ret {
+                    Ok(()) => log.record(format_args!("add {}", name)),
+                    Err(err) => log.alert(
+                        "add_failed",
+                        &[("HEAP", name), ("ERROR", &err.to_string())],
+                        format_args!("add {} failed: {}", name, err),
+                    ),
+                }
+            }
+            Action::Remove(idx) => {
+                if mlock.remove(idx) {
+                    log.record(format_args!("remove {}", mlock.heaps[idx].spec.name));
+                }
             }
-            Action::Remove(heap) => {
-                mlock.remove(heap);
+            Action::ToggleHuge => {
+                mlock.huge = mlock.huge.next();
+                log.record(format_args!("hugepage mode {}", mlock.huge));
             }
             Action::PageIn => {
                 term.cmd_str(" ... paging in ...");
                 term.cmd_flush();
                 mlock.page_in();
+
+                term.cmd_str(" ... verifying ...");
+                term.cmd_flush();
+                let (checked, mismatches) = mlock.verify();
+                log.record(format_args!(
+                    "page in: {} pages checked, {} mismatches",
+                    checked, mismatches
+                ));
+            }
+            Action::Advise(advice) => {
+                let name = match advice {
+                    libc::MADV_PAGEOUT => "MADV_PAGEOUT",
+                    libc::MADV_FREE => "MADV_FREE",
+                    libc::MADV_DONTNEED => "MADV_DONTNEED",
+                    _ => "madvise",
+                };
+                term.cmd_fmt(format_args!(" ... {} ...", name));
+                term.cmd_flush();
+                mlock.advise(advice);
+                log.record(format_args!("{} unlocked heaps", name));
             }
+            Action::DropCaches => {
+                term.cmd_str(" ... dropping caches ...");
+                term.cmd_flush();
+                match drop_caches() {
+                    Ok(msg) => log.record(format_args!("drop_caches: {}", msg)),
+                    Err(err) => log.record(format_args!("drop_caches failed: {}", err)),
+                }
+            }
+            Action::OomAdj(children, delta) => {
+                let who = if children { "children" } else { "self" };
+                match mlock.adjust_oom(children, delta) {
+                    Ok(adj) => log.record(format_args!("{} oom_score_adj {}", who, adj)),
+                    Err(err) => log.record(format_args!("{} oom_score_adj failed: {}", who, err)),
+                }
+            }
+            Action::CgroupLimit(file, raise) => {
+                if let Some(cgroup) = &cgroup {
+                    match adjust_cgroup_limit(cgroup, file, raise, mlock.chunk_mb) {
+                        Ok(limit) => log.record(format_args!("{} {}", file, format_limit(limit))),
+                        Err(err) => log.record(format_args!("{} failed: {}", file, err)),
+                    }
+                }
+            }
+            Action::ScrollUp => log.scroll_up(),
+            Action::ScrollDown => log.scroll_down(),
         }
 
-        term.cmd_clear(3);
+        if let Some(recorder) = &mut recorder {
+            recorder.finish();
+        }
+        if let Some(Err(err)) = snapshot.as_mut().map(|snapshot| snapshot.save(&mlock)) {
+            log.record(format_args!("save failed: {}", err));
+        }
+
+        term.cmd_clear(screen.rows);
+    }
+
+    if let Some(notify) = &notify {
+        notify.stopping();
+    }
+
+    if let Some(path) = &args.summary {
+        let line = summary
+            .line()
+            .field("exit", exit_reason)
+            .field("elapsed_ns", start.elapsed())
+            .field("events", &log.events);
+        fs::write(path, format!("{}\n", line))?;
     }
 
     term.reset();
+    if args.json {
+        if args.soak.is_some() {
+            summary.emit();
+        }
+        return Ok(exit_code);
+    }
     println!();
 
-    Ok(())
+    if args.soak.is_some() {
+        summary.print();
+    }
+
+    Ok(exit_code)
 }
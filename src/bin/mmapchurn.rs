@@ -0,0 +1,209 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    collections::VecDeque,
+    env, fs, io,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread, time,
+};
+
+const REPORT_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+// returns the number of vmas of this process
+fn vma_count() -> usize {
+    fs::read_to_string("/proc/self/maps")
+        .map(|maps| maps.lines().count())
+        .unwrap_or_default()
+}
+
+struct Latency {
+    map: rustest::Histogram,
+    unmap: rustest::Histogram,
+}
+
+// maps and unmaps until stopped, keeping up to args.live mappings alive
+fn churn(args: &Args, seed: u64, ops: &AtomicU64, stop: &AtomicBool) -> Latency {
+    let page_size = rustest::page_size();
+    let min_pages = (args.min_kb * 1024 / page_size).max(1);
+    let max_pages = (args.max_kb * 1024 / page_size).max(min_pages);
+
+    let mut latency = Latency {
+        map: rustest::Histogram::new(),
+        unmap: rustest::Histogram::new(),
+    };
+    let mut live = VecDeque::new();
+    let mut state = seed | 1;
+    while !stop.load(Ordering::Relaxed) {
+        let pages = min_pages
+            + (rustest::xorshift(&mut state) % (max_pages - min_pages + 1) as u64) as usize;
+
+        let begin = time::Instant::now();
+        let Ok(mut mmap) = rustest::Mmap::anonymous(pages * page_size) else {
+            break;
+        };
+        latency.map.record(begin.elapsed());
+
+        if args.touch {
+            mmap.as_bytes_mut()[0] = 1;
+        }
+        live.push_back(mmap);
+
+        if live.len() > args.live {
+            let mmap = live.pop_front();
+            let begin = time::Instant::now();
+            drop(mmap);
+            latency.unmap.record(begin.elapsed());
+        }
+
+        ops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    latency
+}
+
+fn print_help() {
+    println!("usage: mmapchurn [options]");
+    println!("  --threads N: number of threads (default: 4)");
+    println!("  --min KB: smallest mapping (default: 4)");
+    println!("  --max KB: largest mapping (default: 1024)");
+    println!("  --live N: mappings each thread keeps alive (default: 256)");
+    println!("  --duration SECS: how long to churn (default: 5)");
+    println!("  --touch: write the first page of each mapping");
+    println!("  --nice N: nice value, -20 to 19 (default: unchanged)");
+    println!("  --ionice CLASS[:LEVEL]: io priority, idle, be:0-7, or rt:0-7 (default: unchanged)");
+    println!("  --json: print samples and the summary as JSON lines");
+}
+
+struct Args {
+    threads: usize,
+    min_kb: usize,
+    max_kb: usize,
+    live: usize,
+    duration: time::Duration,
+    touch: bool,
+    nice: Option<i32>,
+    ioprio: Option<rustest::IoPrio>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        threads: 4,
+        min_kb: 4,
+        max_kb: 1024,
+        live: 256,
+        duration: time::Duration::from_secs(5),
+        touch: false,
+        nice: None,
+        ioprio: None,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--threads" => args.threads = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--min" => args.min_kb = rustest::parse_kb(&iter.next().unwrap_or_default())?,
+            "--max" => args.max_kb = rustest::parse_kb(&iter.next().unwrap_or_default())?,
+            "--live" => args.live = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--duration" => {
+                args.duration = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--touch" => args.touch = true,
+            "--nice" => args.nice = Some(rustest::parse_nice(&iter.next().unwrap_or_default())?),
+            "--ionice" => {
+                args.ioprio = Some(rustest::IoPrio::parse(&iter.next().unwrap_or_default())?)
+            }
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    args.threads = args.threads.max(1);
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    if !args.json {
+        println!(
+            "{} threads mapping {} KB to {} KB, {} live mappings each",
+            args.threads, args.min_kb, args.max_kb, args.live
+        );
+    }
+
+    let ops = AtomicU64::new(0);
+    let stop = AtomicBool::new(false);
+    let mut map = rustest::Histogram::new();
+    let mut unmap = rustest::Histogram::new();
+    thread::scope(|scope| {
+        let threads: Vec<_> = (0..args.threads)
+            .map(|idx| {
+                let (args, ops, stop) = (&args, &ops, &stop);
+                scope.spawn(move || {
+                    // only the churning threads run at the priority, not the reporting one;
+                    // a failure stops the others
+                    rustest::set_priority(args.nice, args.ioprio)
+                        .inspect_err(|_| stop.store(true, Ordering::Relaxed))?;
+                    Ok::<_, io::Error>(churn(args, idx as u64 + 1, ops, stop))
+                })
+            })
+            .collect();
+
+        let start = time::Instant::now();
+        let mut prev = 0;
+        while start.elapsed() < args.duration && !stop.load(Ordering::Relaxed) {
+            thread::sleep(REPORT_INTERVAL);
+
+            let cur = ops.load(Ordering::Relaxed);
+            if args.json {
+                rustest::JsonLine::new("sample")
+                    .field("elapsed_ns", start.elapsed())
+                    .field("maps_per_sec", cur - prev)
+                    .field("vmas", vma_count())
+                    .emit();
+            } else {
+                println!(
+                    "{:5.1}s: {:8} maps/s, {:6} vmas",
+                    start.elapsed().as_secs_f64(),
+                    cur - prev,
+                    vma_count()
+                );
+            }
+            prev = cur;
+        }
+        stop.store(true, Ordering::Relaxed);
+
+        for thread in threads {
+            let latency = thread.join().unwrap()?;
+            map.add(&latency.map);
+            unmap.add(&latency.unmap);
+        }
+
+        Ok::<_, io::Error>(())
+    })?;
+
+    if args.json {
+        rustest::JsonLine::new("summary")
+            .field("mmap", &map)
+            .field("munmap", &unmap)
+            .emit();
+        return Ok(());
+    }
+
+    println!();
+    println!("mmap: {}", map);
+    map.print();
+    println!("munmap: {}", unmap);
+    unmap.print();
+
+    Ok(())
+}
@@ -0,0 +1,438 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crossterm::event;
+use std::{
+    env, fmt, fs,
+    io::{self, BufRead},
+    time,
+};
+
+const CHUNK_SIZE_MB: usize = 256;
+const NODE_DIR: &str = "/sys/devices/system/node";
+
+// returns the ids of the online nodes, parsed from a list such as "0-3,5"
+fn online_nodes() -> Vec<usize> {
+    let list = fs::read_to_string(format!("{}/online", NODE_DIR)).unwrap_or_default();
+
+    let mut nodes = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        if let (Ok(first), Ok(last)) = (first.parse::<usize>(), last.parse()) {
+            nodes.extend(first..=last);
+        }
+    }
+
+    if nodes.is_empty() {
+        nodes.push(0);
+    }
+
+    nodes
+}
+
+struct Node {
+    id: usize,
+    mem_free: u64,
+    // accumulated page allocations, from numastat
+    numa_hit: u64,
+    numa_miss: u64,
+    numa_foreign: u64,
+    other_node: u64,
+}
+
+impl Node {
+    fn collect(id: usize) -> Self {
+        let mut node = Node {
+            id,
+            mem_free: 0,
+            numa_hit: 0,
+            numa_miss: 0,
+            numa_foreign: 0,
+            other_node: 0,
+        };
+
+        let _ = node.collect_meminfo();
+        let _ = node.collect_numastat();
+
+        node
+    }
+
+    fn collect_meminfo(&mut self) -> Result<(), io::Error> {
+        let fp = fs::File::open(format!("{}/node{}/meminfo", NODE_DIR, self.id))?;
+        let reader = io::BufReader::new(fp);
+
+        // lines are in the form of "Node 0 MemFree: 3914032 kB"
+        let prefix = format!("Node {} MemFree:", self.id);
+        for line in reader.lines() {
+            let line = line?;
+
+            if let Some(val) = line.strip_prefix(&prefix) {
+                self.mem_free = val
+                    .split_ascii_whitespace()
+                    .next()
+                    .and_then(|val| val.parse().ok())
+                    .unwrap_or_default();
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn collect_numastat(&mut self) -> Result<(), io::Error> {
+        let fp = fs::File::open(format!("{}/node{}/numastat", NODE_DIR, self.id))?;
+        let reader = io::BufReader::new(fp);
+
+        for line in reader.lines() {
+            let line = line?;
+
+            let extract_val = |line: &str| {
+                line.split_ascii_whitespace()
+                    .nth(1)
+                    .and_then(|val| val.parse::<u64>().ok())
+                    .unwrap_or_default()
+            };
+
+            if line.starts_with("numa_hit ") {
+                self.numa_hit = extract_val(&line);
+            } else if line.starts_with("numa_miss ") {
+                self.numa_miss = extract_val(&line);
+            } else if line.starts_with("numa_foreign ") {
+                self.numa_foreign = extract_val(&line);
+            } else if line.starts_with("other_node ") {
+                self.other_node = extract_val(&line);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// a chunk of anonymous memory bound to a node
+struct Chunk {
+    mmap: rustest::Mmap,
+    node: usize,
+}
+
+struct Numa {
+    nodes: Vec<usize>,
+    // index into nodes of the node that new and migrated chunks are bound to
+    target: usize,
+    chunks: Vec<Chunk>,
+    seq: usize,
+}
+
+impl Numa {
+    fn new(nodes: Vec<usize>) -> Self {
+        Numa {
+            nodes,
+            target: 0,
+            chunks: Vec::new(),
+            seq: 0,
+        }
+    }
+
+    fn target_node(&self) -> usize {
+        self.nodes[self.target]
+    }
+
+    fn next_target(&mut self) {
+        self.target = (self.target + 1) % self.nodes.len();
+    }
+
+    fn add(&mut self) -> Result<(), io::Error> {
+        let node = self.target_node();
+        let mut mmap = rustest::Mmap::anonymous(CHUNK_SIZE_MB * 1024 * 1024)?;
+        // bind before faulting in, so that the pages are allocated on the node
+        mmap.mbind(node, false)?;
+
+        self.seq += 1;
+        mmap.fill(self.seq as u8);
+
+        self.chunks.push(Chunk { mmap, node });
+
+        Ok(())
+    }
+
+    fn remove(&mut self) -> bool {
+        self.chunks.pop().is_some()
+    }
+
+    fn migrate(&mut self, all: bool) -> Result<(), io::Error> {
+        let node = self.target_node();
+        let count = if all { self.chunks.len() } else { 1 };
+
+        for chunk in self.chunks.iter_mut().rev().take(count) {
+            chunk.mmap.mbind(node, true)?;
+            chunk.node = node;
+        }
+
+        Ok(())
+    }
+
+    // returns the number of resident pages of the chunks on each node
+    fn placement(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.nodes.len()];
+        for chunk in &self.chunks {
+            for node in chunk.mmap.page_nodes().unwrap_or_default() {
+                if let Some(idx) = self.nodes.iter().position(|&id| id as i32 == node) {
+                    counts[idx] += 1;
+                }
+            }
+        }
+
+        counts
+    }
+}
+
+impl fmt::Display for Numa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let bound: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|&id| {
+                let count = self.chunks.iter().filter(|chunk| chunk.node == id).count();
+                format!("node{} {} MB", id, count * CHUNK_SIZE_MB)
+            })
+            .collect();
+
+        write!(
+            f,
+            "target node{}, chunks {:5} MB, bound to {}",
+            self.target_node(),
+            self.chunks.len() * CHUNK_SIZE_MB,
+            bound.join(", ")
+        )
+    }
+}
+
+enum Action {
+    Redraw,
+    Quit,
+    Add,
+    Remove,
+    NextNode,
+    Migrate,
+    MigrateAll,
+}
+
+fn term_wait_action(term: &mut rustest::Term) -> Action {
+    let key = match term.poll(1000) {
+        Ok(Some(key)) => key,
+        Ok(None) => return Action::Redraw,
+        Err(_) => return Action::Quit,
+    };
+
+    match key.modifiers {
+        event::KeyModifiers::CONTROL => match key.code {
+            event::KeyCode::Char('c') | event::KeyCode::Char('d') => Action::Quit,
+            _ => Action::Redraw,
+        },
+        event::KeyModifiers::SHIFT | event::KeyModifiers::NONE => match key.code {
+            event::KeyCode::Char('+') | event::KeyCode::Char('=') => Action::Add,
+            event::KeyCode::Char('-') | event::KeyCode::Char('_') => Action::Remove,
+            event::KeyCode::Char('n') | event::KeyCode::Char('N') => Action::NextNode,
+            event::KeyCode::Char('m') => Action::Migrate,
+            event::KeyCode::Char('M') => Action::MigrateAll,
+            event::KeyCode::Char('q') | event::KeyCode::Esc => Action::Quit,
+            _ => Action::Redraw,
+        },
+        _ => Action::Redraw,
+    }
+}
+
+fn print_help() {
+    println!("usage: numatest [--node N] [INIT_MB]");
+    println!(
+        "  +/-: allocate/free a {} MB chunk bound to the target node",
+        CHUNK_SIZE_MB
+    );
+    println!("  n: cycle the target node");
+    println!("  m: migrate the last chunk to the target node");
+    println!("  M: migrate all chunks to the target node");
+    println!("  q: quit");
+    println!("  --cpus LIST: run on the cpus of LIST, such as 0-3,8, to fix the local node");
+    println!("  --plain: print a line per sample rather than redrawing");
+    println!("  --interval SECS: time between plain samples (default: 1)");
+    println!("  --json: print samples as JSON lines");
+    println!("  --log FILE: append a CSV row per sample to FILE");
+    println!("numastat counters are deltas since start");
+}
+
+struct Args {
+    init_mb: usize,
+    node: Option<usize>,
+    cpus: Vec<usize>,
+    plain: bool,
+    interval: time::Duration,
+    json: bool,
+    log: Option<String>,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        init_mb: 0,
+        node: None,
+        cpus: Vec::new(),
+        plain: false,
+        interval: time::Duration::from_secs(1),
+        json: false,
+        log: None,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--node" => args.node = Some(rustest::parse_num(&iter.next().unwrap_or_default())?),
+            "--cpus" => args.cpus = rustest::parse_cpu_list(&iter.next().unwrap_or_default())?,
+            "--plain" => args.plain = true,
+            "--interval" => {
+                args.interval = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--json" => args.json = true,
+            "--log" => args.log = iter.next(),
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.init_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+    if !args.cpus.is_empty() {
+        rustest::set_affinity(&args.cpus)?;
+    }
+
+    let mut numa = Numa::new(online_nodes());
+    if let Some(node) = args.node {
+        numa.target = numa
+            .nodes
+            .iter()
+            .position(|&id| id == node)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("node {} is not online", node),
+                )
+            })?;
+    }
+    for _ in 0..args.init_mb / CHUNK_SIZE_MB {
+        numa.add()?;
+    }
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let base: Vec<Node> = numa.nodes.iter().map(|&id| Node::collect(id)).collect();
+    let mut log = args.log.as_deref().map(rustest::CsvLog::open).transpose()?;
+    let mut term = if args.json {
+        rustest::Term::new_json(args.interval)
+    } else if args.plain {
+        rustest::Term::new_plain(args.interval)
+    } else {
+        rustest::Term::new()?
+    };
+
+    loop {
+        let placement = numa.placement();
+
+        // the csv row has the columns of all nodes
+        let mut row = rustest::JsonLine::new("sample").field("target", numa.target_node());
+
+        term.cmd_fmt(format_args!("numatest: {}\r\n", &numa));
+        for ((base, count), &id) in base.iter().zip(&placement).zip(&numa.nodes) {
+            let node = Node::collect(id);
+            let bound_mb =
+                numa.chunks.iter().filter(|chunk| chunk.node == id).count() * CHUNK_SIZE_MB;
+            if log.is_some() {
+                row.push(&format!("node{}_bound_mb", id), bound_mb);
+                row.push(
+                    &format!("node{}_resident_kb", id),
+                    count * rustest::page_size() / 1024,
+                );
+                row.push(&format!("node{}_free_kb", id), node.mem_free);
+                row.push(
+                    &format!("node{}_numa_hit", id),
+                    node.numa_hit - base.numa_hit,
+                );
+                row.push(
+                    &format!("node{}_numa_miss", id),
+                    node.numa_miss - base.numa_miss,
+                );
+                row.push(
+                    &format!("node{}_numa_foreign", id),
+                    node.numa_foreign - base.numa_foreign,
+                );
+                row.push(
+                    &format!("node{}_other_node", id),
+                    node.other_node - base.other_node,
+                );
+            }
+            if args.json {
+                rustest::JsonLine::new("sample")
+                    .field("node", id)
+                    .field("target", id == numa.target_node())
+                    .field("bound_mb", bound_mb)
+                    .field("resident_kb", count * rustest::page_size() / 1024)
+                    .field("free_kb", node.mem_free)
+                    .field("numa_hit", node.numa_hit - base.numa_hit)
+                    .field("numa_miss", node.numa_miss - base.numa_miss)
+                    .field("numa_foreign", node.numa_foreign - base.numa_foreign)
+                    .field("other_node", node.other_node - base.other_node)
+                    .emit();
+            }
+
+            term.cmd_fmt(format_args!(
+                "  node{}: free {:6} MB, chunks resident {:5} MB, numa_hit +{}, numa_miss +{}, numa_foreign +{}, other_node +{}\r\n",
+                id,
+                node.mem_free / 1024,
+                count * rustest::page_size() / 1024 / 1024,
+                node.numa_hit - base.numa_hit,
+                node.numa_miss - base.numa_miss,
+                node.numa_foreign - base.numa_foreign,
+                node.other_node - base.other_node,
+            ));
+        }
+        if let Some(log) = &mut log {
+            log.append(&row)?;
+        }
+        term.cmd_flush();
+
+        match term_wait_action(&mut term) {
+            Action::Redraw => (),
+            Action::Quit => break,
+            Action::Add => {
+                term.cmd_str(" ... allocating ...");
+                term.cmd_flush();
+                let _ = numa.add();
+            }
+            Action::Remove => {
+                numa.remove();
+            }
+            Action::NextNode => numa.next_target(),
+            Action::Migrate => {
+                term.cmd_str(" ... migrating ...");
+                term.cmd_flush();
+                let _ = numa.migrate(false);
+            }
+            Action::MigrateAll => {
+                term.cmd_str(" ... migrating ...");
+                term.cmd_flush();
+                let _ = numa.migrate(true);
+            }
+        }
+
+        term.cmd_clear(1 + numa.nodes.len() as u32);
+    }
+
+    term.reset();
+    if !args.json {
+        println!();
+    }
+
+    Ok(())
+}
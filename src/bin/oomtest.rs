@@ -0,0 +1,374 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    mem, ptr, thread, time,
+};
+
+const CHUNK_SIZE_MB: usize = 64;
+// how often the hog grows by a chunk
+const HOG_INTERVAL: time::Duration = time::Duration::from_millis(100);
+const POLL_INTERVAL: time::Duration = time::Duration::from_millis(200);
+
+// a forked child that allocates memory once told to go
+struct Child {
+    role: String,
+    pid: libc::pid_t,
+    adj: i32,
+    // written to and closed to tell the child to go
+    go: Option<fs::File>,
+    score: i32,
+    // the wait status once the child has exited
+    status: Option<i32>,
+}
+
+impl Child {
+    // the child allocates size_mb, or grows until killed when size_mb is None
+    fn spawn(role: String, adj: i32, size_mb: Option<usize>) -> Result<Self, io::Error> {
+        let (go_rd, go_wr) = rustest::pipe()?;
+
+        // SAFETY: we are single-threaded and the child never returns
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            return Err(io::Error::last_os_error());
+        } else if pid == 0 {
+            drop(go_wr);
+            Self::serve(go_rd, adj, size_mb);
+        }
+
+        Ok(Child {
+            role,
+            pid,
+            adj,
+            go: Some(go_wr),
+            score: 0,
+            status: None,
+        })
+    }
+
+    fn serve(mut go: fs::File, adj: i32, size_mb: Option<usize>) -> ! {
+        let mut buf = [0u8; 1];
+        let _ = go.read(&mut buf);
+        let _ = rustest::write_oom_score_adj("self", adj);
+
+        let mut allocated = 0;
+        while size_mb.is_none_or(|size_mb| allocated < size_mb) {
+            let Ok(mut mmap) = rustest::Mmap::anonymous(CHUNK_SIZE_MB * 1024 * 1024) else {
+                break;
+            };
+            // incompressible, so that zram does not delay the oom
+            mmap.fill_with(rustest::Fill::Random(allocated as u64), 1);
+            mem::forget(mmap);
+            allocated += CHUNK_SIZE_MB;
+
+            if size_mb.is_none() {
+                thread::sleep(HOG_INTERVAL);
+            }
+        }
+
+        loop {
+            // SAFETY: valid pause call
+            unsafe { libc::pause() };
+        }
+    }
+
+    fn go(&mut self) -> Result<(), io::Error> {
+        if let Some(mut go) = self.go.take() {
+            go.write_all(b"g")?;
+        }
+
+        Ok(())
+    }
+
+    // reads back the actual values, as lowering oom_score_adj needs CAP_SYS_RESOURCE
+    fn update_score(&mut self) {
+        if self.status.is_some() {
+            return;
+        }
+
+        let pid = self.pid.to_string();
+        if let Some(adj) = rustest::read_oom(&pid, "oom_score_adj") {
+            self.adj = adj;
+        }
+        if let Some(score) = rustest::read_oom(&pid, "oom_score") {
+            self.score = score;
+        }
+    }
+
+    // returns true when the child has just exited
+    fn try_wait(&mut self) -> bool {
+        if self.status.is_some() {
+            return false;
+        }
+
+        let mut status = 0;
+        // SAFETY: valid waitpid call
+        let ret = unsafe { libc::waitpid(self.pid, &mut status, libc::WNOHANG) };
+        if ret == self.pid {
+            self.status = Some(status);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn fate(&self) -> String {
+        match self.status {
+            None => "alive".to_string(),
+            Some(status) if libc::WIFSIGNALED(status) => {
+                format!("killed by signal {}", libc::WTERMSIG(status))
+            }
+            Some(status) => format!("exited with {}", libc::WEXITSTATUS(status)),
+        }
+    }
+
+    fn emit(&self, kind: &str) {
+        rustest::JsonLine::new(kind)
+            .field("role", &self.role)
+            .field("pid", self.pid)
+            .field("oom_score_adj", self.adj)
+            .field("oom_score", self.score)
+            .field("fate", self.fate())
+            .emit();
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if self.status.is_some() {
+            return;
+        }
+
+        // SAFETY: valid kill call
+        unsafe { libc::kill(self.pid, libc::SIGKILL) };
+        // SAFETY: valid waitpid call
+        unsafe { libc::waitpid(self.pid, ptr::null_mut(), 0) };
+    }
+}
+
+fn print_help() {
+    println!("usage: oomtest [options]");
+    println!("  --victims ADJ,...: oom_score_adj of each victim (default: -500,0,500)");
+    println!("  --victim-mb MB: memory each victim holds (default: 256)");
+    println!("  --hog-adj ADJ: oom_score_adj of the hog, which grows until killed (default: 0)");
+    println!("  --cgroup NAME: run the children in a cgroup rather than system-wide");
+    println!("  --limit MB: memory.max of the cgroup");
+    println!("  --timeout SECS: give up after this long (default: 60)");
+    println!("  --json: print events and the summary as JSON lines");
+    println!("warning: without --cgroup, this drives the whole system to oom");
+}
+
+struct Args {
+    victims: Vec<i32>,
+    victim_mb: usize,
+    hog_adj: i32,
+    cgroup: Option<String>,
+    limit_mb: Option<u64>,
+    timeout: time::Duration,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        victims: vec![-500, 0, 500],
+        victim_mb: 256,
+        hog_adj: 0,
+        cgroup: None,
+        limit_mb: None,
+        timeout: time::Duration::from_secs(60),
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--victims" => {
+                let list = iter.next().unwrap_or_default();
+                args.victims = list
+                    .split(',')
+                    .filter(|adj| !adj.is_empty())
+                    .map(|adj| {
+                        adj.parse().map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!("invalid oom_score_adj {}", adj),
+                            )
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--victim-mb" => args.victim_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--hog-adj" => args.hog_adj = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--cgroup" => args.cgroup = iter.next(),
+            "--limit" => args.limit_mb = Some(rustest::parse_mb(&iter.next().unwrap_or_default())?),
+            "--timeout" => {
+                args.timeout = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let mut kmsg = rustest::Kmsg::open()
+        .inspect_err(|err| {
+            if !args.json {
+                println!("not watching /dev/kmsg: {}", err)
+            }
+        })
+        .ok();
+
+    let cgroup = match &args.cgroup {
+        Some(name) => {
+            let cgroup = rustest::Cgroup::create(name)?;
+            if let Some(limit_mb) = args.limit_mb {
+                cgroup.write_value("memory.max", limit_mb * 1024 * 1024)?;
+            }
+            Some(cgroup)
+        }
+        None => None,
+    };
+
+    let start = |child: &mut Child| -> Result<(), io::Error> {
+        if let Some(cgroup) = &cgroup {
+            cgroup.add_pid(child.pid as u32)?;
+        }
+        child.go()
+    };
+
+    let mut children = Vec::new();
+    for (idx, &adj) in args.victims.iter().enumerate() {
+        let mut child = Child::spawn(format!("victim{}", idx), adj, Some(args.victim_mb))?;
+        start(&mut child)?;
+        children.push(child);
+    }
+
+    // let the victims settle before the hog starts
+    thread::sleep(time::Duration::from_secs(1));
+
+    let mut hog = Child::spawn("hog".to_string(), args.hog_adj, None)?;
+    start(&mut hog)?;
+    children.push(hog);
+
+    for child in &mut children {
+        child.update_score();
+        if args.json {
+            child.emit("child");
+        } else {
+            println!(
+                "{:8} pid {:7}, oom_score_adj {:5}, oom_score {:5}",
+                child.role, child.pid, child.adj, child.score
+            );
+        }
+    }
+    if !args.json {
+        println!();
+    }
+
+    let mut reports = Vec::new();
+    let begin = time::Instant::now();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        for msg in kmsg.as_mut().map(|kmsg| kmsg.read()).unwrap_or_default() {
+            if rustest::is_oom_msg(&msg) {
+                if args.json {
+                    rustest::JsonLine::new("kmsg").field("msg", &msg).emit();
+                } else {
+                    println!("kmsg: {}", msg);
+                }
+                reports.push(msg);
+            }
+        }
+
+        for child in &mut children {
+            child.update_score();
+            if child.try_wait() {
+                if args.json {
+                    child.emit("exit");
+                } else {
+                    println!("{} (pid {}) {}", child.role, child.pid, child.fate());
+                }
+            }
+        }
+
+        let hog = children.last().unwrap();
+        if hog.status.is_some() {
+            break;
+        }
+        if begin.elapsed() >= args.timeout {
+            if !args.json {
+                println!("timed out");
+            }
+            break;
+        }
+    }
+
+    if args.json {
+        for child in &children {
+            child.emit("result");
+        }
+
+        let chosen: Vec<&String> = reports
+            .iter()
+            .filter(|msg| rustest::is_oom_kill_msg(msg))
+            .collect();
+        let mut line = rustest::JsonLine::new("summary")
+            .field("timed_out", children.last().unwrap().status.is_none())
+            .field("chosen", chosen);
+        if let Some(cgroup) = &cgroup {
+            for key in ["oom", "oom_kill"] {
+                line.push(
+                    key,
+                    cgroup.read_keyed("memory.events", key).unwrap_or_default(),
+                );
+            }
+        }
+        line.emit();
+
+        drop(children);
+        return Ok(());
+    }
+
+    println!();
+    println!("summary:");
+    for child in &children {
+        println!(
+            "  {:8} pid {:7}, oom_score_adj {:5}, last oom_score {:5}, {}",
+            child.role,
+            child.pid,
+            child.adj,
+            child.score,
+            child.fate()
+        );
+    }
+    for msg in reports.iter().filter(|msg| rustest::is_oom_kill_msg(msg)) {
+        println!("  chosen: {}", msg);
+    }
+    if let Some(cgroup) = &cgroup {
+        let [oom, oom_kill] =
+            ["oom", "oom_kill"].map(|key| cgroup.read_keyed("memory.events", key));
+        println!(
+            "  memory.events: oom {}, oom_kill {}",
+            oom.unwrap_or_default(),
+            oom_kill.unwrap_or_default()
+        );
+    }
+
+    // the cgroup can only be removed once the children are gone
+    drop(children);
+
+    Ok(())
+}
@@ -0,0 +1,389 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crossterm::event;
+use std::{
+    env, fmt, fs,
+    io::{self, BufRead, Read, Write},
+    path, process, time,
+};
+
+const CHUNK_SIZE_MB: usize = 256;
+const IO_SIZE: usize = 1024 * 1024;
+
+// a temp file that is removed on drop
+struct CacheFile {
+    path: path::PathBuf,
+    fp: fs::File,
+}
+
+impl CacheFile {
+    fn create(path: path::PathBuf, seq: usize) -> Result<Self, io::Error> {
+        let mut fp = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        let buf = vec![seq as u8; IO_SIZE];
+        for _ in 0..CHUNK_SIZE_MB * 1024 * 1024 / IO_SIZE {
+            if let Err(err) = fp.write_all(&buf) {
+                let _ = fs::remove_file(&path);
+                return Err(err);
+            }
+        }
+
+        Ok(CacheFile { path, fp })
+    }
+
+    fn read(&self) -> Result<(), io::Error> {
+        let mut buf = vec![0; IO_SIZE];
+        let mut fp = &self.fp;
+        io::Seek::rewind(&mut fp)?;
+        while fp.read(&mut buf)? > 0 {}
+
+        Ok(())
+    }
+}
+
+impl Drop for CacheFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+struct PageCache {
+    dir: path::PathBuf,
+    files: Vec<CacheFile>,
+    seq: usize,
+}
+
+impl PageCache {
+    fn new(dir: &str) -> PageCache {
+        PageCache {
+            dir: path::PathBuf::from(dir),
+            files: Vec::new(),
+            seq: 0,
+        }
+    }
+
+    fn add(&mut self) -> Result<(), io::Error> {
+        self.seq += 1;
+        let name = format!("pagecache-{}-{}", process::id(), self.seq);
+        let file = CacheFile::create(self.dir.join(name), self.seq)?;
+        self.files.push(file);
+
+        Ok(())
+    }
+
+    fn remove(&mut self) -> bool {
+        self.files.pop().is_some()
+    }
+
+    fn read(&self) {
+        for file in &self.files {
+            let _ = file.read();
+        }
+    }
+
+    fn sync(&self) {
+        for file in &self.files {
+            let _ = file.fp.sync_data();
+        }
+    }
+
+    fn fadvise(&self) {
+        for file in &self.files {
+            let _ = rustest::fadvise(&file.fp, libc::POSIX_FADV_DONTNEED);
+        }
+    }
+
+    fn cachestat(&self) -> rustest::CacheStat {
+        let mut total = rustest::CacheStat::default();
+        for file in &self.files {
+            if let Ok(cstat) = rustest::cachestat(&file.fp) {
+                total.cache += cstat.cache;
+                total.dirty += cstat.dirty;
+                total.writeback += cstat.writeback;
+            }
+        }
+
+        total
+    }
+}
+
+impl fmt::Display for PageCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let cstat = self.cachestat();
+        let [cache, dirty, writeback] = [cstat.cache, cstat.dirty, cstat.writeback]
+            .map(|page_count| page_count as usize * rustest::page_size() / 1024 / 1024);
+
+        write!(
+            f,
+            "files {:5} MB, cached {:5} MB, dirty {:5} MB, writeback {:5} MB",
+            self.files.len() * CHUNK_SIZE_MB,
+            cache,
+            dirty,
+            writeback,
+        )
+    }
+}
+
+struct Proc {
+    mem_available: u64,
+    cached: u64,
+    dirty: u64,
+    writeback: u64,
+    active_file: u64,
+    inactive_file: u64,
+}
+
+impl Proc {
+    fn collect() -> Self {
+        let mut proc = Proc {
+            mem_available: 0,
+            cached: 0,
+            dirty: 0,
+            writeback: 0,
+            active_file: 0,
+            inactive_file: 0,
+        };
+
+        let _ = proc.collect_meminfo();
+
+        proc
+    }
+
+    fn collect_meminfo(&mut self) -> Result<(), io::Error> {
+        let fp = fs::File::open("/proc/meminfo")?;
+        let reader = io::BufReader::new(fp);
+
+        for line in reader.lines() {
+            let line = line?;
+
+            let extract_val = |line: &str| {
+                line.split_ascii_whitespace()
+                    .nth(1)
+                    .and_then(|val| val.parse::<u64>().ok())
+                    .unwrap_or_default()
+            };
+
+            if line.starts_with("MemAvailable:") {
+                self.mem_available = extract_val(&line);
+            } else if line.starts_with("Cached:") {
+                self.cached = extract_val(&line);
+            } else if line.starts_with("Active(file):") {
+                self.active_file = extract_val(&line);
+            } else if line.starts_with("Inactive(file):") {
+                self.inactive_file = extract_val(&line);
+            } else if line.starts_with("Dirty:") {
+                self.dirty = extract_val(&line);
+            } else if line.starts_with("Writeback:") {
+                self.writeback = extract_val(&line);
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Proc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let [
+            mem_available,
+            cached,
+            dirty,
+            writeback,
+            active_file,
+            inactive_file,
+        ] = [
+            self.mem_available,
+            self.cached,
+            self.dirty,
+            self.writeback,
+            self.active_file,
+            self.inactive_file,
+        ]
+        .map(|kb| kb / 1024);
+
+        write!(
+            f,
+            "avail {:5} MB, cached {:5} MB (active {} / inactive {}), dirty {:5} MB, writeback {:5} MB",
+            mem_available, cached, active_file, inactive_file, dirty, writeback,
+        )
+    }
+}
+
+enum Action {
+    Redraw,
+    Quit,
+    Add,
+    Remove,
+    Read,
+    Sync,
+    Fadvise,
+}
+
+fn term_wait_action(term: &mut rustest::Term) -> Action {
+    let key = match term.poll(1000) {
+        Ok(Some(key)) => key,
+        Ok(None) => return Action::Redraw,
+        Err(_) => return Action::Quit,
+    };
+
+    match key.modifiers {
+        event::KeyModifiers::CONTROL => match key.code {
+            event::KeyCode::Char('c') | event::KeyCode::Char('d') => Action::Quit,
+            _ => Action::Redraw,
+        },
+        event::KeyModifiers::SHIFT | event::KeyModifiers::NONE => match key.code {
+            event::KeyCode::Char('+') | event::KeyCode::Char('=') => Action::Add,
+            event::KeyCode::Char('-') | event::KeyCode::Char('_') => Action::Remove,
+            event::KeyCode::Char('r') | event::KeyCode::Char('R') => Action::Read,
+            event::KeyCode::Char('s') | event::KeyCode::Char('S') => Action::Sync,
+            event::KeyCode::Char('f') | event::KeyCode::Char('F') => Action::Fadvise,
+            event::KeyCode::Char('q') | event::KeyCode::Esc => Action::Quit,
+            _ => Action::Redraw,
+        },
+        _ => Action::Redraw,
+    }
+}
+
+fn print_help() {
+    println!("usage: pagecache [--dir DIR] [INIT_MB]");
+    println!("  +/-: write/remove a {} MB file", CHUNK_SIZE_MB);
+    println!("  r: read all files back into the page cache");
+    println!("  s: write back dirty pages");
+    println!("  f: fadvise DONTNEED all files (dirty pages stay until written back)");
+    println!("  q: quit");
+    println!("  --plain: print a line per sample rather than redrawing");
+    println!("  --interval SECS: time between plain samples (default: 1)");
+    println!("  --json: print samples as JSON lines");
+    println!("  --log FILE: append a CSV row per sample to FILE");
+}
+
+struct Args {
+    init_mb: usize,
+    dir: String,
+    plain: bool,
+    interval: time::Duration,
+    json: bool,
+    log: Option<String>,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        init_mb: 0,
+        dir: String::from("/var/tmp"),
+        plain: false,
+        interval: time::Duration::from_secs(1),
+        json: false,
+        log: None,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--dir" => args.dir = iter.next().unwrap_or(args.dir),
+            "--plain" => args.plain = true,
+            "--interval" => {
+                args.interval = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--json" => args.json = true,
+            "--log" => args.log = iter.next(),
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.init_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+    let init_count = args.init_mb / CHUNK_SIZE_MB;
+
+    let mut cache = PageCache::new(&args.dir);
+    for _ in 0..init_count {
+        cache.add()?;
+    }
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let mut log = args.log.as_deref().map(rustest::CsvLog::open).transpose()?;
+    let mut term = if args.json {
+        rustest::Term::new_json(args.interval)
+    } else if args.plain {
+        rustest::Term::new_plain(args.interval)
+    } else {
+        rustest::Term::new()?
+    };
+
+    loop {
+        let sys = Proc::collect();
+        if args.json || log.is_some() {
+            let cstat = cache.cachestat();
+            let [cache_kb, dirty_kb, writeback_kb] = [cstat.cache, cstat.dirty, cstat.writeback]
+                .map(|page_count| page_count as usize * rustest::page_size() / 1024);
+            let line = rustest::JsonLine::new("sample")
+                .field("files_mb", cache.files.len() * CHUNK_SIZE_MB)
+                .field("files_cached_kb", cache_kb)
+                .field("files_dirty_kb", dirty_kb)
+                .field("files_writeback_kb", writeback_kb)
+                .field("mem_available_kb", sys.mem_available)
+                .field("cached_kb", sys.cached)
+                .field("active_file_kb", sys.active_file)
+                .field("inactive_file_kb", sys.inactive_file)
+                .field("dirty_kb", sys.dirty)
+                .field("writeback_kb", sys.writeback);
+
+            if let Some(log) = &mut log {
+                log.append(&line)?;
+            }
+            if args.json {
+                line.emit();
+            }
+        }
+
+        term.cmd_fmt(format_args!("pagecache: {}\r\n", &cache));
+        term.cmd_fmt(format_args!("proc sys:  {}\r\n", &sys));
+        term.cmd_flush();
+
+        match term_wait_action(&mut term) {
+            Action::Redraw => (),
+            Action::Quit => break,
+            Action::Add => {
+                term.cmd_str(" ... writing ...");
+                term.cmd_flush();
+                let _ = cache.add();
+            }
+            Action::Remove => {
+                cache.remove();
+            }
+            Action::Read => {
+                term.cmd_str(" ... reading ...");
+                term.cmd_flush();
+                cache.read();
+            }
+            Action::Sync => {
+                term.cmd_str(" ... syncing ...");
+                term.cmd_flush();
+                cache.sync();
+            }
+            Action::Fadvise => cache.fadvise(),
+        }
+
+        term.cmd_clear(2);
+    }
+
+    term.reset();
+    if !args.json {
+        println!();
+    }
+
+    Ok(())
+}
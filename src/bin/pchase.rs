@@ -0,0 +1,176 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, hint, io, mem, time};
+
+const LINE_SIZE: usize = 64;
+// the minimum number of loads timed per working set
+const MIN_LOADS: usize = 1 << 22;
+
+// returns the number of pages swapped in so far
+fn pswpin() -> u64 {
+    fs::read_to_string("/proc/vmstat")
+        .ok()
+        .and_then(|vmstat| {
+            vmstat
+                .lines()
+                .find_map(|line| line.strip_prefix("pswpin "))
+                .and_then(|val| val.parse().ok())
+        })
+        .unwrap_or_default()
+}
+
+// a random cyclic chain of cache lines, where the first word of each line holds the index of
+// the next line
+struct Chain {
+    mmap: rustest::Mmap,
+    line_count: usize,
+}
+
+impl Chain {
+    fn new(size: usize, seed: u64) -> Result<Self, io::Error> {
+        let line_count = size / LINE_SIZE;
+        let mut mmap = rustest::Mmap::anonymous(line_count * LINE_SIZE)?;
+
+        let mut order: Vec<usize> = (0..line_count).collect();
+        rustest::shuffle(&mut order, seed);
+
+        // SAFETY: the mapping is page-aligned and any bit pattern is a valid usize
+        let (_, words, _) = unsafe { mmap.as_bytes_mut().align_to_mut::<usize>() };
+        let stride = LINE_SIZE / mem::size_of::<usize>();
+        for (cur, next) in order.iter().zip(order.iter().cycle().skip(1)) {
+            words[cur * stride] = *next;
+        }
+
+        Ok(Chain { mmap, line_count })
+    }
+
+    // follows the chain for count loads and returns the average latency of a load in ns
+    fn chase(&self, count: usize) -> f64 {
+        // SAFETY: the mapping is page-aligned and any bit pattern is a valid usize
+        let (_, words, _) = unsafe { self.mmap.as_bytes().align_to::<usize>() };
+        let stride = LINE_SIZE / mem::size_of::<usize>();
+
+        let mut idx = 0;
+        let start = time::Instant::now();
+        for _ in 0..count {
+            idx = words[idx * stride];
+        }
+        let elapsed = start.elapsed();
+        hint::black_box(idx);
+
+        elapsed.as_secs_f64() * 1e9 / count as f64
+    }
+}
+
+fn format_size(size: usize) -> String {
+    if size >= 1024 * 1024 * 1024 {
+        format!("{}G", size / 1024 / 1024 / 1024)
+    } else if size >= 1024 * 1024 {
+        format!("{}M", size / 1024 / 1024)
+    } else {
+        format!("{}K", size / 1024)
+    }
+}
+
+fn print_help() {
+    println!("usage: pchase [options]");
+    println!("  --min KB: smallest working set (default: 4)");
+    println!("  --max MB: largest working set (default: 1024)");
+    println!("  --pageout: also chase after paging the working set out to swap");
+    println!("  --json: print the results as JSON lines");
+}
+
+struct Args {
+    min_kb: usize,
+    max_mb: usize,
+    pageout: bool,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        min_kb: 4,
+        max_mb: 1024,
+        pageout: false,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--min" => args.min_kb = rustest::parse_kb(&iter.next().unwrap_or_default())?,
+            "--max" => args.max_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--pageout" => args.pageout = true,
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    if !args.json {
+        if args.pageout {
+            println!(
+                "{:>6} {:>10} {:>14} {:>10}",
+                "size", "ns/load", "paged ns/load", "swapin MB"
+            );
+        } else {
+            println!("{:>6} {:>10}", "size", "ns/load");
+        }
+    }
+
+    let page_size = rustest::page_size();
+    let mut size = (args.min_kb * 1024).max(page_size);
+    while size <= args.max_mb * 1024 * 1024 {
+        let mut chain = Chain::new(size, size as u64)?;
+        let count = chain.line_count.max(MIN_LOADS);
+
+        // one pass over the chain to warm up caches and TLBs
+        chain.chase(chain.line_count);
+        let warm = chain.chase(count);
+
+        let mut paged = None;
+        if args.pageout {
+            chain.mmap.madvise(libc::MADV_PAGEOUT)?;
+
+            // a single pass, as every load after it hits memory again
+            let before = pswpin();
+            let ns = chain.chase(chain.line_count);
+            let swapin = (pswpin() - before) as usize * page_size;
+            paged = Some((ns, swapin));
+        }
+
+        if args.json {
+            rustest::JsonLine::new("result")
+                .field("size_bytes", size)
+                .field("ns_per_load", warm)
+                .field("paged_ns_per_load", paged.map(|(ns, _)| ns))
+                .field("swapin_kb", paged.map(|(_, swapin)| swapin / 1024))
+                .emit();
+        } else if let Some((ns, swapin)) = paged {
+            println!(
+                "{:>6} {:10.2} {:14.2} {:10}",
+                format_size(size),
+                warm,
+                ns,
+                swapin / 1024 / 1024
+            );
+        } else {
+            println!("{:>6} {:10.2}", format_size(size), warm);
+        }
+
+        size *= 2;
+    }
+
+    Ok(())
+}
@@ -1,16 +1,888 @@
 // Copyright 2025 Google LLC
 // SPDX-License-Identifier: MIT
 
-use std::{env, io};
+use std::{
+    env, fmt, fs, io, iter, ops,
+    os::fd::AsRawFd,
+    path,
+    sync::{Mutex, atomic},
+    thread, time,
+};
+
+// files larger than this are split between threads
+const STRIPE_SIZE: usize = 64 * 1024 * 1024;
+
+// io_uring queue depth and read size
+const URING_DEPTH: u32 = 16;
+const URING_BUF_SIZE: usize = 128 * 1024;
+
+// the step size when throttled
+const THROTTLE_SIZE: usize = 1024 * 1024;
+
+// preadv iovec count and size
+const PREADV_IOVCNT: usize = 16;
+const PREADV_BUF_SIZE: usize = 128 * 1024;
+
+// how file pages are brought into the page cache
+#[derive(Clone, Copy)]
+enum Backend {
+    // touching the mapped pages
+    Mmap,
+    // reading into io_uring registered buffers
+    Uring,
+    // large vectored reads
+    Preadv,
+}
+
+impl Backend {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "mmap" => Some(Backend::Mmap),
+            "uring" => Some(Backend::Uring),
+            "preadv" => Some(Backend::Preadv),
+            _ => None,
+        }
+    }
+}
+
+// returns the number of major faults of the calling thread
+fn majflt() -> u64 {
+    // comm may contain spaces; the fields after it are space-separated
+    fs::read_to_string("/proc/thread-self/stat")
+        .ok()
+        .and_then(|stat| {
+            let (_, fields) = stat.rsplit_once(')')?;
+            fields.split_ascii_whitespace().nth(9)?.parse().ok()
+        })
+        .unwrap_or_default()
+}
+
+// returns the number of major faults system-wide
+fn pgmajfault() -> u64 {
+    fs::read_to_string("/proc/vmstat")
+        .ok()
+        .and_then(|vmstat| {
+            vmstat
+                .lines()
+                .find_map(|line| line.strip_prefix("pgmajfault "))
+                .and_then(|val| val.parse().ok())
+        })
+        .unwrap_or_default()
+}
+
+// major fault counters sampled before and after populating
+//
+// The system-wide count is None for the stripes and files, which the threads populate at the
+// same time, and is only sampled around a whole target.
+#[derive(Clone, Copy, Default)]
+struct Faults {
+    majflt: u64,
+    pgmajfault: Option<u64>,
+}
+
+impl Faults {
+    // samples the counter of the calling thread
+    fn collect() -> Self {
+        Faults {
+            majflt: majflt(),
+            pgmajfault: None,
+        }
+    }
+
+    fn delta(&self, prev: &Self) -> Self {
+        Faults {
+            majflt: self.majflt.saturating_sub(prev.majflt),
+            pgmajfault: self
+                .pgmajfault
+                .zip(prev.pgmajfault)
+                .map(|(cur, prev)| cur.saturating_sub(prev)),
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.majflt += other.majflt;
+        if let Some(pgmajfault) = other.pgmajfault {
+            *self.pgmajfault.get_or_insert(0) += pgmajfault;
+        }
+    }
+}
+
+impl fmt::Display for Faults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{} major faults", self.majflt)?;
+        if let Some(pgmajfault) = self.pgmajfault {
+            write!(f, " ({} system-wide)", pgmajfault)?;
+        }
+
+        Ok(())
+    }
+}
+
+// the result of populating one or more files
+#[derive(Clone, Copy, Default)]
+struct Sample {
+    bytes: u64,
+    elapsed: time::Duration,
+    faults: Faults,
+}
+
+impl Sample {
+    fn add(&mut self, other: &Self) {
+        self.bytes += other.bytes;
+        self.elapsed += other.elapsed;
+        self.faults.add(&other.faults);
+    }
+}
+
+impl Sample {
+    fn push_json(&self, line: &mut rustest::JsonLine) {
+        let secs = self.elapsed.as_secs_f64();
+        let rate = if secs > 0.0 {
+            self.bytes as f64 / 1024.0 / 1024.0 / secs
+        } else {
+            0.0
+        };
+
+        line.push("bytes", self.bytes);
+        line.push("elapsed_ns", self.elapsed);
+        line.push("mb_per_sec", rate);
+        line.push("majflt", self.faults.majflt);
+        line.push("pgmajfault", self.faults.pgmajfault);
+    }
+
+    // adds the fields of the --json PATH report
+    fn push_report_json(&self, line: &mut rustest::JsonLine) {
+        let secs = self.elapsed.as_secs_f64();
+        let rate = if secs > 0.0 {
+            self.bytes as f64 / 1024.0 / 1024.0 / secs
+        } else {
+            0.0
+        };
+
+        line.push("bytes", self.bytes);
+        line.push("seconds", secs);
+        line.push("mb_per_sec", rate);
+        line.push("majflt", self.faults.majflt);
+        line.push("pgmajfault", self.faults.pgmajfault);
+    }
+}
+
+// returns the latency object of the --json PATH report, or None when no page was timed
+fn latency_report_json(hist: &rustest::Histogram) -> Option<rustest::JsonLine> {
+    (hist.count() > 0).then(|| {
+        rustest::JsonLine::nested()
+            .field("pages", hist.count())
+            .field("p50_ns", hist.percentile(50))
+            .field("p95_ns", hist.percentile(95))
+            .field("p99_ns", hist.percentile(99))
+    })
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let mb = self.bytes as f64 / 1024.0 / 1024.0;
+        let secs = self.elapsed.as_secs_f64();
+        let rate = if secs > 0.0 { mb / secs } else { 0.0 };
+
+        write!(
+            f,
+            "{:.1} MB in {:.3}s ({:.1} MB/s), {}",
+            mb, secs, rate, self.faults
+        )
+    }
+}
+
+// limits the populate rate across threads
+struct Throttle {
+    // bytes per second
+    rate: f64,
+    // when the next step may start
+    next: Mutex<time::Instant>,
+}
+
+impl Throttle {
+    fn new(bytes_per_sec: u64) -> Self {
+        Throttle {
+            rate: bytes_per_sec as f64,
+            next: Mutex::new(time::Instant::now()),
+        }
+    }
+
+    // sleeps for the time slot of the bytes; idle time is not made up for with a burst
+    fn wait(&self, bytes: usize) {
+        let deadline = {
+            let mut next = self.next.lock().unwrap();
+            let now = time::Instant::now();
+            *next = (*next).max(now) + time::Duration::from_secs_f64(bytes as f64 / self.rate);
+            *next
+        };
+
+        thread::sleep(deadline.saturating_duration_since(time::Instant::now()));
+    }
+}
+
+struct File {
+    path: String,
+    fp: fs::File,
+    mmap: rustest::Mmap,
+}
+
+impl File {
+    fn populate(
+        &self,
+        range: ops::Range<usize>,
+        args: &Args,
+        seed: u64,
+        latency: &mut rustest::Histogram,
+        throttle: Option<&Throttle>,
+        mut uring: Option<&mut rustest::Uring>,
+    ) -> Result<Sample, io::Error> {
+        // throttling works in small steps
+        let step = if throttle.is_some() {
+            THROTTLE_SIZE
+        } else {
+            range.len().max(1)
+        };
+        let mut bytes = 0;
+
+        let before = Faults::collect();
+        let start = time::Instant::now();
+        for offset in range.clone().step_by(step) {
+            let chunk = offset..(offset + step).min(range.end);
+            let len = chunk.len();
+            let seed = seed ^ offset as u64;
+
+            bytes += match args.backend {
+                Backend::Mmap if args.latency => {
+                    self.populate_timed(chunk, args.random, seed, latency);
+                    len as u64
+                }
+                Backend::Mmap if args.random => {
+                    self.mmap.populate_random(chunk, seed);
+                    len as u64
+                }
+                Backend::Mmap => {
+                    self.mmap.populate_range(chunk);
+                    len as u64
+                }
+                Backend::Uring => {
+                    let uring = uring.as_deref_mut().ok_or(io::ErrorKind::InvalidInput)?;
+                    uring.read(&self.fp, chunk.start as u64..chunk.end as u64)?
+                }
+                Backend::Preadv => self.preadv(chunk)?,
+            };
+
+            if let Some(throttle) = throttle {
+                throttle.wait(len);
+            }
+        }
+
+        Ok(Sample {
+            bytes,
+            elapsed: start.elapsed(),
+            faults: Faults::collect().delta(&before),
+        })
+    }
+
+    // returns the number of pages in the page cache
+    fn cached(&self) -> Result<u64, io::Error> {
+        rustest::cachestat(&self.fp)
+            .map(|cstat| cstat.cache)
+            .or_else(|_| self.mmap.resident().map(|pages| pages as u64))
+    }
+
+    // touches the pages one by one and records the latency of each
+    fn populate_timed(
+        &self,
+        range: ops::Range<usize>,
+        random: bool,
+        seed: u64,
+        latency: &mut rustest::Histogram,
+    ) {
+        let page_size = rustest::page_size();
+        let end = range.end;
+
+        let mut pages: Vec<usize> = range.step_by(page_size).collect();
+        if random {
+            rustest::shuffle(&mut pages, seed);
+        }
+
+        for page in pages {
+            let start = time::Instant::now();
+            self.mmap.populate_range(page..(page + page_size).min(end));
+            latency.record(start.elapsed());
+        }
+    }
+
+    // reads the byte range with preadv and returns the number of bytes read
+    fn preadv(&self, range: ops::Range<usize>) -> Result<u64, io::Error> {
+        let mut buf = vec![0u8; PREADV_IOVCNT * PREADV_BUF_SIZE];
+
+        let mut offset = range.start;
+        while offset < range.end {
+            let len = (range.end - offset).min(buf.len());
+            let iovecs: Vec<_> = buf[..len]
+                .chunks_mut(PREADV_BUF_SIZE)
+                .map(|chunk| libc::iovec {
+                    iov_base: chunk.as_mut_ptr() as _,
+                    iov_len: chunk.len(),
+                })
+                .collect();
+
+            // SAFETY: iovecs point into buf, which outlives the call
+            let ret = unsafe {
+                libc::preadv(
+                    self.fp.as_raw_fd(),
+                    iovecs.as_ptr(),
+                    iovecs.len() as _,
+                    offset as _,
+                )
+            };
+            if ret < 0 {
+                return Err(io::Error::last_os_error());
+            } else if ret == 0 {
+                break;
+            }
+
+            offset += ret as usize;
+        }
+
+        Ok((offset - range.start) as u64)
+    }
+
+    // drops the pages of the file from the page cache
+    fn evict(&mut self, locked: bool) -> Result<(), io::Error> {
+        if locked {
+            self.mmap.munlock();
+        }
+
+        // the page cache keeps pages that are still mapped
+        self.mmap.madvise(libc::MADV_DONTNEED)?;
+
+        // dirty pages are not dropped
+        self.fp.sync_data()?;
+
+        rustest::fadvise(&self.fp, libc::POSIX_FADV_DONTNEED)
+    }
+}
+
+// a byte range of a file that is populated by a single thread
+struct Stripe {
+    file: usize,
+    range: ops::Range<usize>,
+}
+
+// the result of populating a target
+struct Report {
+    files: Vec<Sample>,
+    total: Sample,
+    latency: rustest::Histogram,
+}
+
+impl Report {
+    // returns the object of the target in the --json PATH report
+    fn json(&self, target: &Target) -> rustest::JsonLine {
+        let files: Vec<_> = iter::zip(&target.files, &self.files)
+            .map(|(file, sample)| {
+                let mut line = rustest::JsonLine::nested().field("path", &file.path);
+                sample.push_report_json(&mut line);
+                line
+            })
+            .collect();
+        let mut total = rustest::JsonLine::nested();
+        self.total.push_report_json(&mut total);
+
+        rustest::JsonLine::nested()
+            .field("name", &target.name)
+            .field("files", files)
+            .field("total", total)
+            .field("latency", latency_report_json(&self.latency))
+    }
+}
+
+// a command-line argument, which is a file or a directory
+struct Target {
+    name: String,
+    files: Vec<File>,
+}
+
+impl Target {
+    fn new(name: &str, json: bool) -> Result<Self, io::Error> {
+        let mut target = Target {
+            name: name.to_string(),
+            files: Vec::new(),
+        };
+
+        if fs::metadata(name)?.is_dir() {
+            target.map_dir(path::Path::new(name), json)?;
+        } else {
+            target.map_file(name, json)?;
+        }
+
+        Ok(target)
+    }
+
+    fn map_file(&mut self, path: &str, json: bool) -> Result<(), io::Error> {
+        if !json {
+            println!("mmapping {}...", path);
+        }
+        let fp = fs::File::open(path)?;
+        let mmap = rustest::Mmap::new(path)?;
+        self.files.push(File {
+            path: path.to_string(),
+            fp,
+            mmap,
+        });
+
+        Ok(())
+    }
+
+    fn map_dir(&mut self, dir: &path::Path, json: bool) -> Result<(), io::Error> {
+        let mut entries = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+
+        for entry in entries {
+            let file_type = fs::symlink_metadata(&entry)?.file_type();
+            if file_type.is_dir() {
+                self.map_dir(&entry, json)?;
+            } else if file_type.is_file() {
+                // empty files cannot be mapped
+                if fs::metadata(&entry)?.len() == 0 {
+                    continue;
+                }
+
+                let path = entry.to_string_lossy();
+                if let Err(err) = self.map_file(&path, json) {
+                    if json {
+                        rustest::JsonLine::new("skip")
+                            .field("path", &*path)
+                            .field("error", err.to_string())
+                            .emit();
+                    } else {
+                        println!("skipping {}: {}", path, err);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn advise_random(&mut self) -> Result<(), io::Error> {
+        for file in &mut self.files {
+            file.mmap.madvise(libc::MADV_RANDOM)?;
+        }
+
+        Ok(())
+    }
+
+    fn stripes(&self, jobs: usize) -> Vec<Stripe> {
+        let stripe_size = if jobs > 1 { STRIPE_SIZE } else { usize::MAX };
+
+        let mut stripes = Vec::new();
+        for (idx, file) in self.files.iter().enumerate() {
+            let len = file.mmap.as_bytes().len();
+            let mut offset = 0;
+            while offset < len {
+                let end = offset.saturating_add(stripe_size).min(len);
+                stripes.push(Stripe {
+                    file: idx,
+                    range: offset..end,
+                });
+                offset = end;
+            }
+        }
+
+        stripes
+    }
+
+    // prints the sample of each file and returns the report
+    fn populate(
+        &mut self,
+        pass: usize,
+        args: &Args,
+        throttle: Option<&Throttle>,
+    ) -> Result<Report, io::Error> {
+        if args.evict {
+            for file in &mut self.files {
+                file.evict(args.lock)?;
+            }
+        }
+
+        let cached: Vec<_> = self.files.iter().map(|file| file.cached()).collect();
+
+        let stripes = self.stripes(args.jobs);
+        let next = atomic::AtomicUsize::new(0);
+        // the stripes of a file overlap, so each file is timed from the start of its first
+        // stripe to the end of its last
+        let samples = Mutex::new(vec![
+            (Sample::default(), None::<ops::Range<time::Instant>>);
+            self.files.len()
+        ]);
+        let latency = Mutex::new(rustest::Histogram::new());
+
+        let before = pgmajfault();
+        let start = time::Instant::now();
+        thread::scope(|scope| {
+            let threads: Vec<_> = (0..args.jobs)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut hist = rustest::Histogram::new();
+                        // a ring per thread, set up before any stripe is timed
+                        let mut uring = match args.backend {
+                            Backend::Uring => {
+                                Some(rustest::Uring::new(URING_DEPTH, URING_BUF_SIZE)?)
+                            }
+                            _ => None,
+                        };
+                        while let Some(stripe) =
+                            stripes.get(next.fetch_add(1, atomic::Ordering::Relaxed))
+                        {
+                            let seed = ((stripe.file as u64) << 32) ^ stripe.range.start as u64;
+                            let file = &self.files[stripe.file];
+                            let begin = time::Instant::now();
+                            let sample = file.populate(
+                                stripe.range.clone(),
+                                args,
+                                seed,
+                                &mut hist,
+                                throttle,
+                                uring.as_mut(),
+                            )?;
+                            let end = time::Instant::now();
+
+                            let mut samples = samples.lock().unwrap();
+                            let (total, span) = &mut samples[stripe.file];
+                            total.add(&sample);
+                            *span = Some(match span.take() {
+                                Some(span) => begin.min(span.start)..end.max(span.end),
+                                None => begin..end,
+                            });
+                        }
+                        latency.lock().unwrap().add(&hist);
+
+                        Ok::<_, io::Error>(())
+                    })
+                })
+                .collect();
+
+            threads
+                .into_iter()
+                .try_for_each(|thread| thread.join().unwrap())
+        })?;
+        let elapsed = start.elapsed();
+        let system = pgmajfault().saturating_sub(before);
+
+        let mut total = Sample::default();
+        let samples: Vec<_> = samples
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|(mut sample, span)| {
+                if let Some(span) = span {
+                    sample.elapsed = span.end - span.start;
+                }
+                sample
+            })
+            .collect();
+        for ((file, sample), before) in iter::zip(iter::zip(&self.files, &samples), cached) {
+            let pages = file.mmap.as_bytes().len().div_ceil(rustest::page_size()) as u64;
+            let after = file.cached();
+            if args.json {
+                let mut line = rustest::JsonLine::new("file")
+                    .field("pass", pass)
+                    .field("target", &self.name)
+                    .field("path", &file.path);
+                sample.push_json(&mut line);
+                line.field(
+                    "cached_before_pct",
+                    before
+                        .as_ref()
+                        .ok()
+                        .map(|&v| v as f64 * 100.0 / pages as f64),
+                )
+                .field(
+                    "cached_after_pct",
+                    after
+                        .as_ref()
+                        .ok()
+                        .map(|&v| v as f64 * 100.0 / pages as f64),
+                )
+                .emit();
+            }
+
+            let residency = match (before, after) {
+                (Ok(before), Ok(after)) => format!(
+                    "{:.1}% -> {:.1}% cached",
+                    before as f64 * 100.0 / pages as f64,
+                    after as f64 * 100.0 / pages as f64
+                ),
+                (Err(err), _) | (_, Err(err)) => format!("unknown residency: {}", err),
+            };
+
+            if !args.json {
+                println!("  {}: {}, {}", file.path, sample, residency);
+            }
+
+            // the pages are resident now; locking only pins them
+            if args.lock {
+                file.mmap.mlock()?;
+            }
+
+            total.add(sample);
+        }
+
+        // threads overlap, so the total is measured as a whole
+        total.elapsed = elapsed;
+        total.faults.pgmajfault = Some(system);
+
+        Ok(Report {
+            files: samples,
+            total,
+            latency: latency.into_inner().unwrap(),
+        })
+    }
+}
+
+struct Args {
+    paths: Vec<String>,
+    loops: usize,
+    interval: time::Duration,
+    random: bool,
+    lock: bool,
+    evict: bool,
+    jobs: usize,
+    backend: Backend,
+    latency: bool,
+    kernel_latency: bool,
+    // JSON lines on stdout, with --json -
+    json: bool,
+    // the path of the report written by --json PATH
+    report: Option<String>,
+    // bytes per second
+    rate: Option<u64>,
+}
+
+// per-fault latency measured in the kernel, which needs the bpf feature
+struct KernelLatency {
+    #[cfg(feature = "bpf")]
+    faults: rustest::FaultLatency,
+}
+
+impl KernelLatency {
+    fn open() -> Result<Self, io::Error> {
+        #[cfg(feature = "bpf")]
+        return Ok(KernelLatency {
+            faults: rustest::FaultLatency::open(Some(std::process::id()))?,
+        });
+
+        #[cfg(not(feature = "bpf"))]
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "--kernel-latency needs the bpf feature",
+        ))
+    }
+
+    // returns the latencies since the last take
+    fn take(&mut self) -> Result<rustest::Histogram, io::Error> {
+        #[cfg(feature = "bpf")]
+        return self.faults.take();
+
+        #[cfg(not(feature = "bpf"))]
+        Ok(rustest::Histogram::new())
+    }
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        paths: Vec::new(),
+        loops: 1,
+        interval: time::Duration::ZERO,
+        random: false,
+        lock: false,
+        evict: false,
+        jobs: 1,
+        backend: Backend::Mmap,
+        latency: false,
+        kernel_latency: false,
+        json: false,
+        report: None,
+        rate: None,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--loop" => args.loops = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--interval" => {
+                args.interval = rustest::parse_duration(&iter.next().unwrap_or_default())?;
+            }
+            "--random" => args.random = true,
+            "--lock" => args.lock = true,
+            "--evict" => args.evict = true,
+            "--latency" => args.latency = true,
+            "--kernel-latency" => args.kernel_latency = true,
+            "--json" => match iter.next().unwrap_or_default().as_str() {
+                "" => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "missing json path",
+                    ));
+                }
+                "-" => args.json = true,
+                path => args.report = Some(path.to_string()),
+            },
+            "--rate" => {
+                let rate = iter.next().unwrap_or_default();
+                args.rate = Some(
+                    rustest::parse_bytes(&rate, 1024 * 1024)
+                        .ok()
+                        .filter(|&rate| rate > 0)
+                        .ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!("invalid rate {}", rate),
+                            )
+                        })?,
+                );
+            }
+            // --threads matches the option of the other tools
+            "--jobs" | "--threads" => {
+                args.jobs = rustest::parse_num::<usize>(&iter.next().unwrap_or_default())?.max(1);
+            }
+            "--io" => {
+                let backend = iter.next().unwrap_or_default();
+                args.backend = Backend::parse(&backend).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid io backend {}", backend),
+                    )
+                })?;
+            }
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.paths.push(arg),
+        }
+    }
+
+    Ok(args)
+}
 
 fn main() -> Result<(), io::Error> {
-    let args = env::args().skip(1);
+    let args = parse_args()?;
+
+    let mut targets = args
+        .paths
+        .iter()
+        .map(|path| Target::new(path, args.json))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if args.lock {
+        let len: usize = targets
+            .iter()
+            .flat_map(|target| &target.files)
+            .map(|file| file.mmap.as_bytes().len())
+            .sum();
+        rustest::Rlimit::Memlock.reserve(Some(len as u64))?;
+    }
+
+    if args.random {
+        for target in &mut targets {
+            target.advise_random()?;
+        }
+    }
+
+    let throttle = args.rate.map(Throttle::new);
+    let mut kernel_latency = args.kernel_latency.then(KernelLatency::open).transpose()?;
+
+    let mut passes = Vec::new();
+    for pass in 1..=args.loops {
+        if pass > 1 {
+            thread::sleep(args.interval);
+        }
+
+        let mut total = Sample::default();
+        let mut total_latency = rustest::Histogram::new();
+        let mut reports = Vec::new();
+        for target in &mut targets {
+            let report = target.populate(pass, &args, throttle.as_ref())?;
+
+            if args.json {
+                let mut line = rustest::JsonLine::new("result")
+                    .field("pass", pass)
+                    .field("target", &target.name)
+                    .field("files", target.files.len());
+                report.total.push_json(&mut line);
+                line.field("latency", args.latency.then_some(&report.latency))
+                    .emit();
+            } else {
+                println!(
+                    "pass {}: {}: {} files, {}",
+                    pass,
+                    target.name,
+                    target.files.len(),
+                    report.total
+                );
+
+                if args.latency {
+                    println!("pass {}: {}: {}", pass, target.name, report.latency);
+                }
+            }
+
+            total.add(&report.total);
+            total_latency.add(&report.latency);
+            reports.push(report.json(target));
+        }
+
+        let mut total_json = rustest::JsonLine::nested();
+        total.push_report_json(&mut total_json);
+        passes.push(
+            rustest::JsonLine::nested()
+                .field("pass", pass)
+                .field("targets", reports)
+                .field("total", total_json)
+                .field("latency", latency_report_json(&total_latency)),
+        );
+
+        let kernel = kernel_latency
+            .as_mut()
+            .map(KernelLatency::take)
+            .transpose()?;
+
+        if args.json {
+            let mut line = rustest::JsonLine::new("summary").field("pass", pass);
+            total.push_json(&mut line);
+            line.field("latency", args.latency.then_some(&total_latency))
+                .field("kernel_latency", &kernel)
+                .emit();
+            continue;
+        }
+
+        println!("pass {}: total: {}", pass, total);
+        if args.latency {
+            println!("pass {}: total: {}", pass, total_latency);
+            total_latency.print();
+        }
+        if let Some(kernel) = &kernel {
+            println!("pass {}: kernel: {}", pass, kernel);
+            kernel.print();
+        }
+    }
+
+    if let Some(path) = &args.report {
+        let report = rustest::JsonLine::nested().field("passes", passes);
+        fs::write(path, format!("{}\n", report))?;
+    }
 
-    for arg in args {
-        println!("mmapping {}...", &arg);
-        let mmap = rustest::Mmap::new(&arg)?;
-        println!("paging in {}...", &arg);
-        mmap.populate()?;
+    // the locks go away with the process
+    if args.lock {
+        if !args.json {
+            println!("holding the files locked; press Ctrl-C to exit");
+        }
+        loop {
+            thread::park();
+        }
     }
 
     Ok(())
@@ -0,0 +1,291 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crossterm::event;
+use std::{env, fmt, io, time};
+
+#[derive(Clone, Copy)]
+enum Filter {
+    All,
+    Anon,
+    File,
+    Shmem,
+}
+
+impl Filter {
+    fn parse(s: &str) -> Option<Self> {
+        let filter = match s {
+            "all" => Filter::All,
+            "anon" => Filter::Anon,
+            "file" => Filter::File,
+            "shmem" => Filter::Shmem,
+            _ => return None,
+        };
+
+        Some(filter)
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Filter::All => Filter::Anon,
+            Filter::Anon => Filter::File,
+            Filter::File => Filter::Shmem,
+            Filter::Shmem => Filter::All,
+        }
+    }
+
+    fn matches(self, vma: &rustest::Vma) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Anon => vma.is_anon(),
+            Filter::File => vma.is_file(),
+            Filter::Shmem => vma.is_shmem(),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let name = match self {
+            Filter::All => "all",
+            Filter::Anon => "anon",
+            Filter::File => "file",
+            Filter::Shmem => "shmem",
+        };
+        f.write_str(name)
+    }
+}
+
+// formats a vma, with sizes in KB
+fn format_vma(vma: &rustest::Vma) -> String {
+    format!(
+        "{:>12x} {:4} {:>9} {:>9} {:>9} {:>9}  {:24}  {}",
+        vma.start,
+        vma.perms,
+        vma.usage.size,
+        vma.usage.rss,
+        vma.usage.swap,
+        vma.usage.locked,
+        vma.flags.join(" "),
+        vma.name
+    )
+}
+
+fn emit_vma(vma: &rustest::Vma) {
+    rustest::JsonLine::new("vma")
+        .field("start", vma.start)
+        .field("end", vma.end)
+        .field("perms", &vma.perms)
+        .field("size_kb", vma.usage.size)
+        .field("rss_kb", vma.usage.rss)
+        .field("swap_kb", vma.usage.swap)
+        .field("locked_kb", vma.usage.locked)
+        .field("flags", &vma.flags)
+        .field("name", &vma.name)
+        .emit();
+}
+
+enum Action {
+    Redraw,
+    Quit,
+    Up(usize),
+    Down(usize),
+    Filter,
+}
+
+fn term_wait_action(term: &mut rustest::Term, page: usize) -> Action {
+    let key = match term.poll(1000) {
+        Ok(Some(key)) => key,
+        Ok(None) => return Action::Redraw,
+        Err(_) => return Action::Quit,
+    };
+
+    match key.modifiers {
+        event::KeyModifiers::CONTROL => match key.code {
+            event::KeyCode::Char('c') | event::KeyCode::Char('d') => Action::Quit,
+            _ => Action::Redraw,
+        },
+        event::KeyModifiers::SHIFT | event::KeyModifiers::NONE => match key.code {
+            event::KeyCode::Up | event::KeyCode::Char('k') => Action::Up(1),
+            event::KeyCode::Down | event::KeyCode::Char('j') => Action::Down(1),
+            event::KeyCode::PageUp => Action::Up(page),
+            event::KeyCode::PageDown | event::KeyCode::Char(' ') => Action::Down(page),
+            event::KeyCode::Home | event::KeyCode::Char('g') => Action::Up(usize::MAX),
+            event::KeyCode::End | event::KeyCode::Char('G') => Action::Down(usize::MAX),
+            event::KeyCode::Char('f') | event::KeyCode::Char('F') => Action::Filter,
+            event::KeyCode::Char('q') | event::KeyCode::Esc => Action::Quit,
+            _ => Action::Redraw,
+        },
+        _ => Action::Redraw,
+    }
+}
+
+fn print_help() {
+    println!("usage: pmap [options] PID");
+    println!("  --rows N: number of vmas shown at a time (default: 20)");
+    println!("  --filter KIND: all, anon, file, or shmem (default: all)");
+    println!("  --plain: print a line per sample rather than redrawing");
+    println!("  --interval SECS: time between plain samples (default: 1)");
+    println!("  --json: print the vmas as JSON lines every interval");
+    println!("  --log FILE: append a CSV row per sample to FILE");
+    println!("  up/down, j/k: scroll by a vma");
+    println!("  pgup/pgdn, space: scroll by a page");
+    println!("  home/end, g/G: scroll to the first/last vma");
+    println!("  f: cycle the filter");
+    println!("  q: quit");
+    println!("sizes are in KB");
+}
+
+struct Args {
+    pid: Option<String>,
+    rows: usize,
+    filter: Filter,
+    plain: bool,
+    interval: time::Duration,
+    json: bool,
+    log: Option<String>,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        pid: None,
+        rows: 20,
+        filter: Filter::All,
+        plain: false,
+        interval: time::Duration::from_secs(1),
+        json: false,
+        log: None,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--rows" => args.rows = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--filter" => {
+                let kind = iter.next().unwrap_or_default();
+                args.filter = Filter::parse(&kind).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid filter {}", kind),
+                    )
+                })?;
+            }
+            "--plain" => args.plain = true,
+            "--interval" => {
+                args.interval = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--json" => args.json = true,
+            "--log" => args.log = iter.next(),
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.pid = Some(arg),
+        }
+    }
+
+    args.rows = args.rows.max(1);
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let mut args = parse_args()?;
+    let Some(pid) = args.pid.take() else {
+        print_help();
+        return Ok(());
+    };
+    rustest::smaps(&pid)?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let mut log = args.log.as_deref().map(rustest::CsvLog::open).transpose()?;
+    let mut term = if args.json {
+        rustest::Term::new_json(args.interval)
+    } else if args.plain {
+        rustest::Term::new_plain(args.interval)
+    } else {
+        rustest::Term::new()?
+    };
+    let mut top = 0;
+
+    loop {
+        let Ok(vmas) = rustest::smaps(&pid) else {
+            term.reset();
+            if args.json {
+                rustest::JsonLine::new("exit").field("pid", &pid).emit();
+            } else {
+                println!();
+                println!("pid {} is gone", pid);
+            }
+            return Ok(());
+        };
+        let vmas: Vec<&rustest::Vma> = vmas.iter().filter(|vma| args.filter.matches(vma)).collect();
+
+        if args.json {
+            for vma in &vmas {
+                emit_vma(vma);
+            }
+        }
+        if args.json || log.is_some() {
+            let sum = |val: fn(&rustest::SmapsUsage) -> u64| -> u64 {
+                vmas.iter().map(|vma| val(&vma.usage)).sum()
+            };
+            let line = rustest::JsonLine::new("sample")
+                .field("pid", &pid)
+                .field("filter", args.filter.to_string())
+                .field("vmas", vmas.len())
+                .field("size_kb", sum(|usage| usage.size))
+                .field("rss_kb", sum(|usage| usage.rss))
+                .field("swap_kb", sum(|usage| usage.swap))
+                .field("locked_kb", sum(|usage| usage.locked));
+
+            if let Some(log) = &mut log {
+                log.append(&line)?;
+            }
+            if args.json {
+                line.emit();
+            }
+        }
+
+        top = top.min(vmas.len().saturating_sub(args.rows));
+        let shown = &vmas[top..vmas.len().min(top + args.rows)];
+
+        term.cmd_fmt(format_args!(
+            "pid {}: {} {} vmas, showing {}-{}\r\n",
+            pid,
+            vmas.len(),
+            args.filter,
+            top + 1,
+            top + shown.len()
+        ));
+        term.cmd_fmt(format_args!(
+            "{:>12} {:4} {:>9} {:>9} {:>9} {:>9}  {:24}  name\r\n",
+            "start", "perm", "size", "rss", "swap", "locked", "flags"
+        ));
+        for vma in shown {
+            term.cmd_fmt(format_args!("{}\r\n", format_vma(vma)));
+        }
+        term.cmd_flush();
+
+        match term_wait_action(&mut term, args.rows) {
+            Action::Redraw => (),
+            Action::Quit => break,
+            Action::Up(count) => top = top.saturating_sub(count),
+            Action::Down(count) => top = top.saturating_add(count),
+            Action::Filter => {
+                args.filter = args.filter.next();
+                top = 0;
+            }
+        }
+
+        term.cmd_clear(2 + shown.len() as u32);
+    }
+
+    term.reset();
+    if !args.json {
+        println!();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,299 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crossterm::event;
+use std::{cmp, env, fmt, fs, io, time};
+
+// smaps_rollup fields shown, in KB
+const ROLLUP_FIELDS: [&str; 9] = [
+    "Rss",
+    "Pss",
+    "Pss_Anon",
+    "Pss_File",
+    "Pss_Shmem",
+    "Swap",
+    "SwapPss",
+    "Locked",
+    "AnonHugePages",
+];
+
+#[derive(Clone, Copy)]
+enum SortKey {
+    Rss,
+    Swap,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Rss => SortKey::Swap,
+            SortKey::Swap => SortKey::Rss,
+        }
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let name = match self {
+            SortKey::Rss => "rss",
+            SortKey::Swap => "swap",
+        };
+        f.write_str(name)
+    }
+}
+
+// formats a vma, with sizes in KB
+fn format_vma(vma: &rustest::Vma) -> String {
+    format!(
+        "{:33} {:4} {:>9} {:>9} {:>9} {:>9} {:>9}  {}",
+        format!("{:x}-{:x}", vma.start, vma.end),
+        vma.perms,
+        vma.usage.size,
+        vma.usage.rss,
+        vma.usage.pss,
+        vma.usage.anonymous,
+        vma.usage.swap,
+        vma.name
+    )
+}
+
+struct Proc {
+    pid: String,
+    comm: String,
+    oom_score: i32,
+    oom_score_adj: i32,
+    rollup: [u64; ROLLUP_FIELDS.len()],
+    vmas: Vec<rustest::Vma>,
+}
+
+impl Proc {
+    fn collect(pid: &str) -> Result<Self, io::Error> {
+        let mut proc = Proc {
+            pid: pid.to_string(),
+            comm: fs::read_to_string(format!("/proc/{}/comm", pid))?
+                .trim()
+                .to_string(),
+            oom_score: rustest::read_oom(pid, "oom_score").unwrap_or_default(),
+            oom_score_adj: rustest::read_oom(pid, "oom_score_adj").unwrap_or_default(),
+            rollup: [0; ROLLUP_FIELDS.len()],
+            vmas: rustest::smaps(pid)?,
+        };
+
+        proc.collect_rollup()?;
+
+        Ok(proc)
+    }
+
+    fn collect_rollup(&mut self) -> Result<(), io::Error> {
+        let rollup = fs::read_to_string(format!("/proc/{}/smaps_rollup", self.pid))?;
+
+        self.rollup = ROLLUP_FIELDS.map(|key| {
+            rollup
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .and_then(|val| val.split_ascii_whitespace().next())
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default()
+        });
+
+        Ok(())
+    }
+
+    fn sample(&self) -> rustest::JsonLine {
+        let mut line = rustest::JsonLine::new("sample")
+            .field("pid", &self.pid)
+            .field("comm", &self.comm)
+            .field("vmas", self.vmas.len())
+            .field("oom_score", self.oom_score)
+            .field("oom_score_adj", self.oom_score_adj);
+        for (key, kb) in ROLLUP_FIELDS.iter().zip(self.rollup) {
+            line.push(&format!("{}_kb", key.to_ascii_lowercase()), kb);
+        }
+
+        line
+    }
+
+    fn sort_vmas(&mut self, key: SortKey) {
+        match key {
+            SortKey::Rss => self.vmas.sort_by_key(|vma| cmp::Reverse(vma.usage.rss)),
+            SortKey::Swap => self.vmas.sort_by_key(|vma| cmp::Reverse(vma.usage.swap)),
+        }
+    }
+}
+
+impl fmt::Display for Proc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "pid {} ({}): {} vmas, oom_score {}, oom_score_adj {}",
+            self.pid,
+            self.comm,
+            self.vmas.len(),
+            self.oom_score,
+            self.oom_score_adj
+        )
+    }
+}
+
+enum Action {
+    Redraw,
+    Quit,
+    More,
+    Fewer,
+    Sort,
+}
+
+fn term_wait_action(term: &mut rustest::Term) -> Action {
+    let key = match term.poll(1000) {
+        Ok(Some(key)) => key,
+        Ok(None) => return Action::Redraw,
+        Err(_) => return Action::Quit,
+    };
+
+    match key.modifiers {
+        event::KeyModifiers::CONTROL => match key.code {
+            event::KeyCode::Char('c') | event::KeyCode::Char('d') => Action::Quit,
+            _ => Action::Redraw,
+        },
+        event::KeyModifiers::SHIFT | event::KeyModifiers::NONE => match key.code {
+            event::KeyCode::Char('+') | event::KeyCode::Char('=') => Action::More,
+            event::KeyCode::Char('-') | event::KeyCode::Char('_') => Action::Fewer,
+            event::KeyCode::Char('s') | event::KeyCode::Char('S') => Action::Sort,
+            event::KeyCode::Char('q') | event::KeyCode::Esc => Action::Quit,
+            _ => Action::Redraw,
+        },
+        _ => Action::Redraw,
+    }
+}
+
+fn print_help() {
+    println!("usage: procmem [--vmas N] PID");
+    println!("  +/-: show more/fewer vmas (default: 10)");
+    println!("  s: sort vmas by rss or by swap");
+    println!("  q: quit");
+    println!("  --plain: print a line per sample rather than redrawing");
+    println!("  --interval SECS: time between plain samples (default: 1)");
+    println!("  --json: print samples as JSON lines");
+    println!("  --log FILE: append a CSV row per sample to FILE");
+    println!("sizes are in KB");
+}
+
+struct Args {
+    pid: Option<String>,
+    vmas: usize,
+    plain: bool,
+    interval: time::Duration,
+    json: bool,
+    log: Option<String>,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        pid: None,
+        vmas: 10,
+        plain: false,
+        interval: time::Duration::from_secs(1),
+        json: false,
+        log: None,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--vmas" => args.vmas = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--plain" => args.plain = true,
+            "--interval" => {
+                args.interval = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--json" => args.json = true,
+            "--log" => args.log = iter.next(),
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.pid = Some(arg),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let mut args = parse_args()?;
+    let Some(pid) = args.pid.take() else {
+        print_help();
+        return Ok(());
+    };
+    Proc::collect(&pid)?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let mut log = args.log.as_deref().map(rustest::CsvLog::open).transpose()?;
+    let mut term = if args.json {
+        rustest::Term::new_json(args.interval)
+    } else if args.plain {
+        rustest::Term::new_plain(args.interval)
+    } else {
+        rustest::Term::new()?
+    };
+    let mut sort = SortKey::Rss;
+
+    loop {
+        let Ok(mut proc) = Proc::collect(&pid) else {
+            term.reset();
+            if args.json {
+                rustest::JsonLine::new("exit").field("pid", &pid).emit();
+            } else {
+                println!();
+                println!("pid {} is gone", pid);
+            }
+            return Ok(());
+        };
+        proc.sort_vmas(sort);
+
+        if args.json || log.is_some() {
+            let line = proc.sample();
+            if let Some(log) = &mut log {
+                log.append(&line)?;
+            }
+            if args.json {
+                line.emit();
+            }
+        }
+
+        let rollup: Vec<String> = ROLLUP_FIELDS
+            .iter()
+            .zip(proc.rollup)
+            .map(|(key, kb)| format!("{} {}", key, kb))
+            .collect();
+
+        term.cmd_fmt(format_args!("{}\r\n", &proc));
+        term.cmd_fmt(format_args!("rollup: {}\r\n", rollup.join(", ")));
+        term.cmd_fmt(format_args!(
+            "{:33} {:4} {:>9} {:>9} {:>9} {:>9} {:>9}  name, by {}\r\n",
+            "range", "perm", "size", "rss", "pss", "anon", "swap", sort
+        ));
+        let vmas = proc.vmas.len().min(args.vmas);
+        for vma in &proc.vmas[..vmas] {
+            term.cmd_fmt(format_args!("{}\r\n", format_vma(vma)));
+        }
+        term.cmd_flush();
+
+        match term_wait_action(&mut term) {
+            Action::Redraw => (),
+            Action::Quit => break,
+            Action::More => args.vmas += 1,
+            Action::Fewer => args.vmas = args.vmas.saturating_sub(1),
+            Action::Sort => sort = sort.next(),
+        }
+
+        term.cmd_clear(3 + vmas as u32);
+    }
+
+    term.reset();
+    if !args.json {
+        println!();
+    }
+
+    Ok(())
+}
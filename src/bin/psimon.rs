@@ -0,0 +1,225 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, os::fd::AsRawFd, time};
+
+// how often the duration is checked when there are no wakeups
+const POLL_TIMEOUT_MS: i32 = 1000;
+
+// meminfo fields logged on each wakeup
+const FIELDS: [&str; 5] = ["MemFree", "MemAvailable", "Cached", "SwapFree", "Dirty"];
+
+// returns the accumulated some and full stall times in us
+fn pressure_totals(path: &str) -> [u64; 2] {
+    let pressure = fs::read_to_string(path).unwrap_or_default();
+    ["some ", "full "].map(|kind| {
+        pressure
+            .lines()
+            .find_map(|line| line.strip_prefix(kind))
+            .and_then(|line| {
+                line.split_ascii_whitespace()
+                    .find_map(|field| field.strip_prefix("total="))
+            })
+            .and_then(|val| val.parse().ok())
+            .unwrap_or_default()
+    })
+}
+
+// returns FIELDS from meminfo, in KB
+fn meminfo() -> [u64; FIELDS.len()] {
+    let meminfo = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    FIELDS.map(|key| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|val| val.split_ascii_whitespace().next())
+            .and_then(|val| val.parse().ok())
+            .unwrap_or_default()
+    })
+}
+
+fn print_help() {
+    println!("usage: psimon [options]");
+    println!("  --trigger some|full:STALL_MS:WINDOW_MS: a trigger to register, repeatable");
+    println!("      (default: some:150:2000)");
+    println!("  --file PATH: pressure file, such as a cgroup memory.pressure");
+    println!("      (default: /proc/pressure/memory)");
+    println!("  --duration SECS: stop after this long (default: forever)");
+    println!("  --json: print the wakeups and the summary as JSON lines");
+    println!("under systemd, READY, WATCHDOG, and STATUS with the stall are sent to NOTIFY_SOCKET");
+    println!("note: unprivileged triggers need a window that is a multiple of 2 seconds");
+}
+
+struct Args {
+    triggers: Vec<String>,
+    file: String,
+    duration: Option<time::Duration>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        triggers: Vec::new(),
+        file: String::from("/proc/pressure/memory"),
+        duration: None,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--trigger" => args.triggers.extend(iter.next()),
+            "--file" => args.file = iter.next().unwrap_or(args.file),
+            "--duration" => {
+                args.duration = Some(rustest::parse_duration(&iter.next().unwrap_or_default())?)
+            }
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    if args.triggers.is_empty() {
+        args.triggers.push(String::from("some:150:2000"));
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let triggers = args
+        .triggers
+        .iter()
+        .map(|spec| rustest::PsiTrigger::register(&args.file, spec))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let specs: Vec<&str> = triggers
+        .iter()
+        .map(|trigger| trigger.spec.as_str())
+        .collect();
+    if args.json {
+        rustest::JsonLine::new("config")
+            .field("file", &args.file)
+            .field("triggers", &specs)
+            .emit();
+    } else {
+        println!("watching {} for {}", args.file, specs.join(", "));
+    }
+
+    let mut pollfds: Vec<libc::pollfd> = triggers
+        .iter()
+        .map(|trigger| libc::pollfd {
+            fd: trigger.as_raw_fd(),
+            events: libc::POLLPRI,
+            revents: 0,
+        })
+        .collect();
+
+    let mut notify = rustest::SdNotify::from_env();
+    if let Some(notify) = &notify {
+        notify.ready(&format!("watching {}", args.file));
+    }
+
+    let mut prev = pressure_totals(&args.file);
+    let start = time::Instant::now();
+    let mut wakeups = 0;
+    while args
+        .duration
+        .is_none_or(|duration| start.elapsed() < duration)
+    {
+        if let Some(notify) = &mut notify {
+            notify.watchdog();
+        }
+
+        // SAFETY: pollfds is valid for its len entries
+        let ret = unsafe {
+            libc::poll(
+                pollfds.as_mut_ptr(),
+                pollfds.len() as libc::nfds_t,
+                POLL_TIMEOUT_MS,
+            )
+        };
+        if ret < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+
+        for (pollfd, trigger) in pollfds.iter().zip(&triggers) {
+            if pollfd.revents & libc::POLLERR != 0 {
+                // the cgroup of the pressure file was removed
+                return Err(io::Error::other(format!("{} is gone", args.file)));
+            } else if pollfd.revents & libc::POLLPRI == 0 {
+                continue;
+            }
+
+            let now = time::SystemTime::now()
+                .duration_since(time::UNIX_EPOCH)
+                .unwrap_or_default();
+            let totals = pressure_totals(&args.file);
+            let [some, full] = [totals[0] - prev[0], totals[1] - prev[1]];
+            prev = totals;
+            wakeups += 1;
+
+            if let Some(notify) = &notify {
+                notify.status(&format!(
+                    "{} wakeups, last {} stall some +{} ms, full +{} ms",
+                    wakeups,
+                    trigger.spec,
+                    some / 1000,
+                    full / 1000
+                ));
+            }
+
+            if args.json {
+                let mut line = rustest::JsonLine::new("event")
+                    .field("elapsed_ns", start.elapsed())
+                    .field("trigger", &trigger.spec)
+                    .field("some_us", some)
+                    .field("full_us", full);
+                for (key, kb) in FIELDS.iter().zip(meminfo()) {
+                    line.push(&format!("{}_kb", key), kb);
+                }
+                line.emit();
+                continue;
+            }
+
+            let mem: Vec<String> = FIELDS
+                .iter()
+                .zip(meminfo())
+                .map(|(key, kb)| format!("{} {} MB", key, kb / 1024))
+                .collect();
+            println!(
+                "[{:.3}] {:8.3}s: {} fired, stall some +{} ms, full +{} ms, {}",
+                now.as_secs_f64(),
+                start.elapsed().as_secs_f64(),
+                trigger.spec,
+                some / 1000,
+                full / 1000,
+                mem.join(", ")
+            );
+        }
+    }
+
+    if let Some(notify) = &notify {
+        notify.stopping();
+    }
+
+    if args.json {
+        rustest::JsonLine::new("summary")
+            .field("wakeups", wakeups)
+            .emit();
+    } else {
+        println!("{} wakeups", wakeups);
+    }
+
+    Ok(())
+}
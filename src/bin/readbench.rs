@@ -0,0 +1,300 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, fs,
+    io::{self, Read},
+    mem,
+    os::unix::fs::{FileExt, OpenOptionsExt},
+    path, time,
+};
+
+const BUF_SIZE: usize = 1024 * 1024;
+
+enum Method {
+    Mmap,
+    Read,
+    Direct,
+}
+
+impl Method {
+    const ALL: [Method; 3] = [Method::Mmap, Method::Read, Method::Direct];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Method::Mmap => "mmap",
+            Method::Read => "read",
+            Method::Direct => "o_direct",
+        }
+    }
+}
+
+// sums the bytes so that the data is consumed
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |sum, &b| sum.wrapping_add(b as u64))
+}
+
+struct Usage {
+    minflt: i64,
+    majflt: i64,
+    nvcsw: i64,
+}
+
+impl Usage {
+    fn collect() -> Self {
+        // SAFETY: rusage is plain data
+        let mut usage: libc::rusage = unsafe { mem::zeroed() };
+        // SAFETY: usage is valid
+        unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+
+        Usage {
+            minflt: usage.ru_minflt,
+            majflt: usage.ru_majflt,
+            nvcsw: usage.ru_nvcsw,
+        }
+    }
+}
+
+struct Sample {
+    bytes: u64,
+    elapsed: time::Duration,
+    syscalls: u64,
+    usage: Usage,
+    checksum: u64,
+}
+
+// consumes the file with the method and returns the checksum, the byte count, and the
+// syscall count
+fn consume(
+    method: &Method,
+    path: &path::Path,
+    buf: &mut [u8],
+) -> Result<(u64, u64, u64), io::Error> {
+    let mut sum = 0u64;
+    let mut bytes = 0;
+    let mut syscalls = 0;
+
+    match method {
+        Method::Mmap => {
+            // empty files cannot be mapped
+            if fs::metadata(path)?.len() > 0 {
+                let mmap = rustest::Mmap::new(&path.to_string_lossy())?;
+                sum = checksum(mmap.as_bytes());
+                bytes = mmap.as_bytes().len() as u64;
+                // mmap and munmap
+                syscalls = 2;
+            }
+        }
+        Method::Read => {
+            let mut fp = fs::File::open(path)?;
+            loop {
+                let len = fp.read(buf)?;
+                syscalls += 1;
+                if len == 0 {
+                    break;
+                }
+                sum = sum.wrapping_add(checksum(&buf[..len]));
+                bytes += len as u64;
+            }
+        }
+        Method::Direct => {
+            let fp = fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_DIRECT)
+                .open(path)?;
+            loop {
+                let len = fp.read_at(buf, bytes)?;
+                syscalls += 1;
+                sum = sum.wrapping_add(checksum(&buf[..len]));
+                bytes += len as u64;
+
+                // the next offset would be unaligned
+                if len < buf.len() {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok((sum, bytes, syscalls))
+}
+
+fn run(method: &Method, paths: &[path::PathBuf], cold: bool) -> Result<Sample, io::Error> {
+    if cold {
+        for path in paths {
+            rustest::fadvise(&fs::File::open(path)?, libc::POSIX_FADV_DONTNEED)?;
+        }
+    }
+
+    // O_DIRECT needs an aligned buffer
+    let mut buf = rustest::Mmap::anonymous(BUF_SIZE)?;
+    // fault in the buffer so that only the file faults are counted
+    buf.fill(0);
+    let buf = buf.as_bytes_mut();
+
+    let mut sample = Sample {
+        bytes: 0,
+        elapsed: time::Duration::ZERO,
+        syscalls: 0,
+        usage: Usage::collect(),
+        checksum: 0,
+    };
+
+    let start = time::Instant::now();
+    for path in paths {
+        let (sum, bytes, syscalls) = consume(method, path, buf)?;
+        sample.checksum = sample.checksum.wrapping_add(sum);
+        sample.bytes += bytes;
+        sample.syscalls += syscalls;
+    }
+    sample.elapsed = start.elapsed();
+
+    let usage = Usage::collect();
+    sample.usage = Usage {
+        minflt: usage.minflt - sample.usage.minflt,
+        majflt: usage.majflt - sample.usage.majflt,
+        nvcsw: usage.nvcsw - sample.usage.nvcsw,
+    };
+
+    Ok(sample)
+}
+
+fn collect_files(dir: &path::Path, paths: &mut Vec<path::PathBuf>) -> Result<(), io::Error> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        let file_type = fs::symlink_metadata(&entry)?.file_type();
+        if file_type.is_dir() {
+            collect_files(&entry, paths)?;
+        } else if file_type.is_file() {
+            paths.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_help() {
+    println!("usage: readbench [options] PATH...");
+    println!("  PATH: files, or directories to walk recursively");
+    println!("  --warm: do not evict the files from the page cache before each method");
+    println!("  --loops N: number of times each method runs (default: 1)");
+    println!("  --json: print the results as JSON lines");
+}
+
+struct Args {
+    paths: Vec<String>,
+    warm: bool,
+    loops: usize,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        paths: Vec::new(),
+        warm: false,
+        loops: 1,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--warm" => args.warm = true,
+            "--loops" => args.loops = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.paths.push(arg),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let mut paths = Vec::new();
+    for path in &args.paths {
+        let path = path::PathBuf::from(path);
+        if fs::metadata(&path)?.is_dir() {
+            collect_files(&path, &mut paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    if !args.json {
+        println!(
+            "{:8} {:>10} {:>8} {:>10} {:>10} {:>8} {:>10} {:>8}",
+            "method", "MB", "secs", "MB/s", "minflt", "majflt", "syscalls", "nvcsw"
+        );
+    }
+
+    let mut expected = None;
+    for _ in 0..args.loops {
+        for method in &Method::ALL {
+            let sample = match run(method, &paths, !args.warm) {
+                Ok(sample) => sample,
+                Err(err) => {
+                    if args.json {
+                        rustest::JsonLine::new("result")
+                            .field("method", method.name())
+                            .field("error", err.to_string())
+                            .emit();
+                    } else {
+                        println!("{:8} failed: {}", method.name(), err);
+                    }
+                    continue;
+                }
+            };
+
+            let secs = sample.elapsed.as_secs_f64();
+            let mb = sample.bytes as f64 / 1024.0 / 1024.0;
+            let same = *expected.get_or_insert(sample.checksum) == sample.checksum;
+            if args.json {
+                rustest::JsonLine::new("result")
+                    .field("method", method.name())
+                    .field("bytes", sample.bytes)
+                    .field("elapsed_ns", sample.elapsed)
+                    .field("mb_per_sec", mb / secs)
+                    .field("minflt", sample.usage.minflt)
+                    .field("majflt", sample.usage.majflt)
+                    .field("syscalls", sample.syscalls)
+                    .field("nvcsw", sample.usage.nvcsw)
+                    .field("same_data", same)
+                    .emit();
+                continue;
+            }
+
+            println!(
+                "{:8} {:10.1} {:8.3} {:10.1} {:10} {:8} {:10} {:8}",
+                method.name(),
+                mb,
+                secs,
+                mb / secs,
+                sample.usage.minflt,
+                sample.usage.majflt,
+                sample.syscalls,
+                sample.usage.nvcsw
+            );
+
+            if !same {
+                println!("{:8} read different data", method.name());
+            }
+        }
+    }
+
+    Ok(())
+}
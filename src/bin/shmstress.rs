@@ -0,0 +1,372 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, fs, io, path, process, ptr,
+    sync::atomic::{AtomicU32, Ordering},
+    thread, time,
+};
+
+const REPORT_INTERVAL: time::Duration = time::Duration::from_secs(1);
+// the exit status of a worker that fails to set its priority; others exit with the number of
+// bad pages, capped below it
+const PRIORITY_FAILED: i32 = 255;
+
+// a sense-reversing barrier in shared memory, where each pass bumps the generation
+struct Barrier {
+    mmap: rustest::Mmap,
+    workers: u32,
+}
+
+impl Barrier {
+    fn new(workers: u32) -> Result<Self, io::Error> {
+        let mmap = rustest::Mmap::memfd("shmstress-barrier", rustest::page_size())?;
+        Ok(Barrier { mmap, workers })
+    }
+
+    // returns the arrival count and the generation
+    fn atomics(&self) -> (&AtomicU32, &AtomicU32) {
+        // SAFETY: the mapping is page-aligned and AtomicU32 has the layout of u32
+        let (_, atomics, _) = unsafe { self.mmap.as_bytes().align_to::<AtomicU32>() };
+        (&atomics[0], &atomics[1])
+    }
+
+    fn generation(&self) -> u32 {
+        self.atomics().1.load(Ordering::Acquire)
+    }
+
+    fn wait(&self) {
+        let (count, generation) = self.atomics();
+
+        let cur = generation.load(Ordering::Acquire);
+        if count.fetch_add(1, Ordering::AcqRel) + 1 == self.workers {
+            count.store(0, Ordering::Relaxed);
+            generation.fetch_add(1, Ordering::Release);
+        } else {
+            while generation.load(Ordering::Acquire) == cur {
+                thread::yield_now();
+            }
+        }
+    }
+}
+
+struct Proc {
+    shmem: u64,
+    dirty: u64,
+    writeback: u64,
+    swap_free: u64,
+}
+
+impl Proc {
+    fn collect() -> Self {
+        let meminfo = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+        let extract_val = |key: &str| -> u64 {
+            meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .and_then(|val| val.split_ascii_whitespace().next())
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default()
+        };
+
+        Proc {
+            shmem: extract_val("Shmem"),
+            dirty: extract_val("Dirty"),
+            writeback: extract_val("Writeback"),
+            swap_free: extract_val("SwapFree"),
+        }
+    }
+}
+
+// a forked worker that dirties its share of the pages and then verifies all of them, in
+// lockstep with the other workers
+struct Worker {
+    pid: libc::pid_t,
+    status: Option<i32>,
+}
+
+impl Worker {
+    fn spawn(
+        args: &Args,
+        idx: usize,
+        data: &mut rustest::Mmap,
+        barrier: &Barrier,
+    ) -> Result<Self, io::Error> {
+        // SAFETY: we are single-threaded and the child never returns
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            return Err(io::Error::last_os_error());
+        } else if pid == 0 {
+            // only the workers run at the priority, not the reporting parent
+            let status = match rustest::set_priority(args.nice, args.ioprio) {
+                Ok(()) => {
+                    Self::serve(args, idx, data, barrier).min(PRIORITY_FAILED as usize - 1) as i32
+                }
+                Err(err) => {
+                    eprintln!("worker{}: {}", idx, err);
+                    PRIORITY_FAILED
+                }
+            };
+            // SAFETY: valid _exit call
+            unsafe { libc::_exit(status) };
+        }
+
+        Ok(Worker { pid, status: None })
+    }
+
+    // returns the number of bad pages seen
+    fn serve(args: &Args, idx: usize, data: &mut rustest::Mmap, barrier: &Barrier) -> usize {
+        let page_size = rustest::page_size();
+        let page_count = data.as_bytes().len() / page_size;
+
+        let mut bad = 0;
+        for round in 0..args.rounds {
+            let bytes = data.as_bytes_mut();
+            for page in (idx..page_count).step_by(args.workers) {
+                bytes[page * page_size] = (round + page) as u8;
+            }
+            if args.msync && idx == 0 {
+                let _ = data.msync(libc::MS_ASYNC);
+            }
+            barrier.wait();
+
+            let bytes = data.as_bytes();
+            bad += (0..page_count)
+                .filter(|page| bytes[page * page_size] != (round + page) as u8)
+                .count();
+            barrier.wait();
+        }
+
+        bad
+    }
+
+    // returns true when the worker has just exited
+    fn try_wait(&mut self, block: bool) -> bool {
+        if self.status.is_some() {
+            return false;
+        }
+
+        let mut status = 0;
+        let flags = if block { 0 } else { libc::WNOHANG };
+        // SAFETY: valid waitpid call
+        let ret = unsafe { libc::waitpid(self.pid, &mut status, flags) };
+        if ret == self.pid {
+            self.status = Some(status);
+            true
+        } else {
+            false
+        }
+    }
+
+    // returns why the worker failed
+    fn failure(&self) -> Option<String> {
+        match self.status {
+            None => Some("is stuck at the barrier".to_string()),
+            Some(status) if libc::WIFSIGNALED(status) => {
+                Some(format!("was killed by signal {}", libc::WTERMSIG(status)))
+            }
+            Some(status) if libc::WEXITSTATUS(status) == PRIORITY_FAILED => {
+                Some("failed to set its priority".to_string())
+            }
+            Some(status) if libc::WEXITSTATUS(status) != 0 => {
+                Some(format!("saw {} bad pages", libc::WEXITSTATUS(status)))
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        if self.status.is_some() {
+            return;
+        }
+
+        // SAFETY: valid kill call
+        unsafe { libc::kill(self.pid, libc::SIGKILL) };
+        // SAFETY: valid waitpid call
+        unsafe { libc::waitpid(self.pid, ptr::null_mut(), 0) };
+    }
+}
+
+fn print_help() {
+    println!("usage: shmstress [options] [SIZE_MB]");
+    println!("  SIZE_MB: size of the shared region (default: 512)");
+    println!("  --workers N: number of worker processes (default: 4)");
+    println!("  --rounds N: number of write and verify rounds (default: 10)");
+    println!("  --dir DIR: back the region by a file in DIR rather than a memfd");
+    println!("  --msync: msync after each write phase");
+    println!("  --nice N: nice value, -20 to 19 (default: unchanged)");
+    println!("  --ionice CLASS[:LEVEL]: io priority, idle, be:0-7, or rt:0-7 (default: unchanged)");
+    println!("  --json: print samples and the summary as JSON lines");
+}
+
+struct Args {
+    size_mb: usize,
+    workers: usize,
+    rounds: usize,
+    dir: Option<String>,
+    msync: bool,
+    nice: Option<i32>,
+    ioprio: Option<rustest::IoPrio>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        size_mb: 512,
+        workers: 4,
+        rounds: 10,
+        dir: None,
+        msync: false,
+        nice: None,
+        ioprio: None,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--workers" => args.workers = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--rounds" => args.rounds = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--dir" => args.dir = iter.next(),
+            "--msync" => args.msync = true,
+            "--nice" => args.nice = Some(rustest::parse_nice(&iter.next().unwrap_or_default())?),
+            "--ionice" => {
+                args.ioprio = Some(rustest::IoPrio::parse(&iter.next().unwrap_or_default())?)
+            }
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.size_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    args.workers = args.workers.max(1);
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let len = args.size_mb * 1024 * 1024;
+    let mut data = match &args.dir {
+        Some(dir) => {
+            let path = path::Path::new(dir).join(format!("shmstress-{}", process::id()));
+            let path = path.to_string_lossy();
+            let mmap = rustest::Mmap::create(&path, len);
+            // the mapping keeps the file alive
+            let _ = fs::remove_file(path.as_ref());
+            mmap?
+        }
+        None => rustest::Mmap::memfd("shmstress", len)?,
+    };
+    let barrier = Barrier::new(args.workers as u32)?;
+
+    if args.json {
+        rustest::JsonLine::new("config")
+            .field("workers", args.workers)
+            .field("rounds", args.rounds)
+            .field("size_mb", args.size_mb)
+            .field("dir", &args.dir)
+            .field("msync", args.msync)
+            .emit();
+    } else {
+        println!(
+            "{} workers, {} rounds over {} MB of {}",
+            args.workers,
+            args.rounds,
+            args.size_mb,
+            args.dir.as_deref().unwrap_or("memfd")
+        );
+    }
+
+    let mut workers = Vec::new();
+    for idx in 0..args.workers {
+        workers.push(Worker::spawn(&args, idx, &mut data, &barrier)?);
+    }
+
+    // each round passes the barrier twice
+    let total = args.rounds as u32 * 2;
+    let start = time::Instant::now();
+    loop {
+        thread::sleep(REPORT_INTERVAL);
+
+        let passed = barrier.generation();
+        let proc = Proc::collect();
+        if args.json {
+            rustest::JsonLine::new("sample")
+                .field("elapsed_ns", start.elapsed())
+                .field("round", passed / 2)
+                .field("shmem_kb", proc.shmem)
+                .field("dirty_kb", proc.dirty)
+                .field("writeback_kb", proc.writeback)
+                .field("swap_free_kb", proc.swap_free)
+                .emit();
+        } else {
+            println!(
+                "{:5.1}s: round {:3}/{}, Shmem {:5} MB, Dirty {:5} MB, Writeback {:5} MB, SwapFree {:5} MB",
+                start.elapsed().as_secs_f64(),
+                passed / 2,
+                args.rounds,
+                proc.shmem / 1024,
+                proc.dirty / 1024,
+                proc.writeback / 1024,
+                proc.swap_free / 1024
+            );
+        }
+
+        if passed >= total {
+            for worker in &mut workers {
+                worker.try_wait(true);
+            }
+            break;
+        }
+
+        // the others never pass the barrier again
+        if workers.iter_mut().any(|worker| worker.try_wait(false)) && barrier.generation() < total {
+            if !args.json {
+                println!("a worker exited early");
+            }
+            break;
+        }
+    }
+
+    let mut failed = 0;
+    for (idx, worker) in workers.iter().enumerate() {
+        if let Some(failure) = worker.failure() {
+            if args.json {
+                rustest::JsonLine::new("failure")
+                    .field("worker", idx)
+                    .field("failure", failure)
+                    .emit();
+            } else {
+                println!("worker{} {}", idx, failure);
+            }
+            failed += 1;
+        }
+    }
+
+    if args.json {
+        rustest::JsonLine::new("summary")
+            .field("rounds", barrier.generation() / 2)
+            .field("elapsed_ns", start.elapsed())
+            .field("failed", failed)
+            .emit();
+        return Ok(());
+    }
+
+    println!(
+        "{} rounds in {:?}, {} workers failed",
+        args.rounds,
+        start.elapsed(),
+        failed
+    );
+
+    Ok(())
+}
@@ -0,0 +1,354 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crossterm::event;
+use std::{cmp, env, fmt, fs, io, time};
+
+// long command lines are truncated so that rows do not wrap
+const CMD_WIDTH: usize = 64;
+
+#[derive(Clone, Copy)]
+enum SortKey {
+    Uss,
+    Pss,
+    Rss,
+    Swap,
+}
+
+impl SortKey {
+    fn parse(s: &str) -> Option<Self> {
+        let key = match s {
+            "uss" => SortKey::Uss,
+            "pss" => SortKey::Pss,
+            "rss" => SortKey::Rss,
+            "swap" => SortKey::Swap,
+            _ => return None,
+        };
+
+        Some(key)
+    }
+
+    fn next(self) -> Self {
+        match self {
+            SortKey::Uss => SortKey::Pss,
+            SortKey::Pss => SortKey::Rss,
+            SortKey::Rss => SortKey::Swap,
+            SortKey::Swap => SortKey::Uss,
+        }
+    }
+
+    fn value(self, usage: &rustest::SmapsUsage) -> u64 {
+        match self {
+            SortKey::Uss => usage.uss(),
+            SortKey::Pss => usage.pss,
+            SortKey::Rss => usage.rss,
+            SortKey::Swap => usage.swap,
+        }
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let name = match self {
+            SortKey::Uss => "uss",
+            SortKey::Pss => "pss",
+            SortKey::Rss => "rss",
+            SortKey::Swap => "swap",
+        };
+        f.write_str(name)
+    }
+}
+
+struct Proc {
+    pid: u32,
+    cmd: String,
+    usage: rustest::SmapsUsage,
+}
+
+impl Proc {
+    fn collect(pid: u32) -> Option<Self> {
+        let usage = rustest::smaps_rollup(&pid.to_string()).ok()?;
+        // kernel threads have no memory of their own
+        if usage.rss == 0 && usage.swap == 0 {
+            return None;
+        }
+
+        // cmdline separates arguments by NULs
+        let cmdline = fs::read_to_string(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+        let cmd = cmdline.trim_end_matches('\0').replace('\0', " ");
+        let cmd = if cmd.is_empty() {
+            fs::read_to_string(format!("/proc/{}/comm", pid))
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        } else {
+            cmd
+        };
+
+        Some(Proc {
+            pid,
+            cmd: cmd.chars().take(CMD_WIDTH).collect(),
+            usage,
+        })
+    }
+}
+
+// returns the processes whose smaps_rollup is readable
+fn collect_procs() -> Vec<Proc> {
+    fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .filter_map(Proc::collect)
+        .collect()
+}
+
+fn usage_line(kind: &str, usage: &rustest::SmapsUsage) -> rustest::JsonLine {
+    rustest::JsonLine::new(kind)
+        .field("uss_kb", usage.uss())
+        .field("pss_kb", usage.pss)
+        .field("rss_kb", usage.rss)
+        .field("swap_kb", usage.swap)
+        .field("swap_pss_kb", usage.swap_pss)
+}
+
+// the sorted processes and their total usage
+struct Table {
+    sort: SortKey,
+    procs: Vec<Proc>,
+    total: rustest::SmapsUsage,
+}
+
+impl Table {
+    fn collect(sort: SortKey) -> Self {
+        let mut procs = collect_procs();
+        procs.sort_by_key(|proc| cmp::Reverse(sort.value(&proc.usage)));
+
+        let mut total = rustest::SmapsUsage::default();
+        for proc in &procs {
+            total.private_clean += proc.usage.private_clean;
+            total.private_dirty += proc.usage.private_dirty;
+            total.pss += proc.usage.pss;
+            total.rss += proc.usage.rss;
+            total.swap += proc.usage.swap;
+            total.swap_pss += proc.usage.swap_pss;
+        }
+
+        Table { sort, procs, total }
+    }
+
+    fn total_line(&self, kind: &str) -> rustest::JsonLine {
+        usage_line(kind, &self.total)
+            .field("sort", self.sort.to_string())
+            .field("processes", self.procs.len())
+    }
+
+    // emits a "proc" line for each of the top processes and the total line
+    fn emit(&self, top: usize, total: &rustest::JsonLine) {
+        for proc in self.procs.iter().take(top) {
+            usage_line("proc", &proc.usage)
+                .field("pid", proc.pid)
+                .field("cmd", &proc.cmd)
+                .emit();
+        }
+        total.emit();
+    }
+
+    // returns the rows of the table, with sizes in KB
+    fn format(&self, top: usize) -> Vec<String> {
+        let format_row = |pid: &str, usage: &rustest::SmapsUsage, cmd: &str| {
+            format!(
+                "{:>8} {:>10} {:>10} {:>10} {:>10} {:>10}  {}",
+                pid,
+                usage.uss(),
+                usage.pss,
+                usage.rss,
+                usage.swap,
+                usage.swap_pss,
+                cmd
+            )
+        };
+
+        let mut rows = vec![format!(
+            "{:>8} {:>10} {:>10} {:>10} {:>10} {:>10}  command, by {}",
+            "pid", "uss", "pss", "rss", "swap", "swappss", self.sort
+        )];
+        rows.extend(
+            self.procs
+                .iter()
+                .take(top)
+                .map(|proc| format_row(&proc.pid.to_string(), &proc.usage, &proc.cmd)),
+        );
+        rows.push(format_row(
+            "total",
+            &self.total,
+            &format!(
+                "{} processes, rss double counts shared pages",
+                self.procs.len()
+            ),
+        ));
+
+        rows
+    }
+}
+
+enum Action {
+    Redraw,
+    Quit,
+    More,
+    Fewer,
+    Sort,
+}
+
+fn term_wait_action(term: &mut rustest::Term) -> Action {
+    let key = match term.poll(1000) {
+        Ok(Some(key)) => key,
+        Ok(None) => return Action::Redraw,
+        Err(_) => return Action::Quit,
+    };
+
+    match key.modifiers {
+        event::KeyModifiers::CONTROL => match key.code {
+            event::KeyCode::Char('c') | event::KeyCode::Char('d') => Action::Quit,
+            _ => Action::Redraw,
+        },
+        event::KeyModifiers::SHIFT | event::KeyModifiers::NONE => match key.code {
+            event::KeyCode::Char('+') | event::KeyCode::Char('=') => Action::More,
+            event::KeyCode::Char('-') | event::KeyCode::Char('_') => Action::Fewer,
+            event::KeyCode::Char('s') | event::KeyCode::Char('S') => Action::Sort,
+            event::KeyCode::Char('q') | event::KeyCode::Esc => Action::Quit,
+            _ => Action::Redraw,
+        },
+        _ => Action::Redraw,
+    }
+}
+
+fn print_help() {
+    println!("usage: smem [options]");
+    println!("  --sort KEY: uss, pss, rss, or swap (default: pss)");
+    println!("  --top N: number of processes shown (default: 20)");
+    println!("  --plain: watch, printing a line per sample rather than redrawing");
+    println!("  --interval SECS: time between plain samples (default: 1)");
+    println!("  --watch: refresh the table every second");
+    println!("  --json: print the table as JSON lines, once or every interval");
+    println!("  --log FILE: append a CSV row per sample to FILE");
+    println!("    +/-: show more/fewer processes");
+    println!("    s: cycle the sort key");
+    println!("    q: quit");
+    println!("sizes are in KB; processes whose smaps_rollup is unreadable are skipped");
+}
+
+struct Args {
+    sort: SortKey,
+    top: usize,
+    watch: bool,
+    plain: bool,
+    interval: time::Duration,
+    json: bool,
+    log: Option<String>,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        sort: SortKey::Pss,
+        top: 20,
+        watch: false,
+        plain: false,
+        interval: time::Duration::from_secs(1),
+        json: false,
+        log: None,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--sort" => {
+                let key = iter.next().unwrap_or_default();
+                args.sort = SortKey::parse(&key).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid sort key {}", key),
+                    )
+                })?;
+            }
+            "--top" => args.top = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--watch" => args.watch = true,
+            "--plain" => args.plain = true,
+            "--interval" => {
+                args.interval = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--json" => args.json = true,
+            "--log" => args.log = iter.next(),
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let mut args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    if !args.watch && !args.plain {
+        let table = Table::collect(args.sort);
+        if args.json {
+            table.emit(args.top, &table.total_line("summary"));
+            return Ok(());
+        }
+
+        for row in table.format(args.top) {
+            println!("{}", row);
+        }
+        return Ok(());
+    }
+
+    let mut log = args.log.as_deref().map(rustest::CsvLog::open).transpose()?;
+    let mut term = if args.json {
+        rustest::Term::new_json(args.interval)
+    } else if args.plain {
+        rustest::Term::new_plain(args.interval)
+    } else {
+        rustest::Term::new()?
+    };
+    loop {
+        let table = Table::collect(args.sort);
+        let total = table.total_line("sample");
+        if let Some(log) = &mut log {
+            log.append(&total)?;
+        }
+
+        let rows = if args.json {
+            table.emit(args.top, &total);
+            Vec::new()
+        } else {
+            table.format(args.top)
+        };
+        for row in &rows {
+            term.cmd_fmt(format_args!("{}\r\n", row));
+        }
+        term.cmd_flush();
+
+        match term_wait_action(&mut term) {
+            Action::Redraw => (),
+            Action::Quit => break,
+            Action::More => args.top += 1,
+            Action::Fewer => args.top = args.top.saturating_sub(1),
+            Action::Sort => args.sort = args.sort.next(),
+        }
+
+        term.cmd_clear(rows.len() as u32);
+    }
+
+    term.reset();
+    if !args.json {
+        println!();
+    }
+
+    Ok(())
+}
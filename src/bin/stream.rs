@@ -0,0 +1,348 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io, mem, sync::Barrier, thread, time};
+
+const SCALAR: f64 = 3.0;
+
+enum Kernel {
+    Copy,
+    Scale,
+    Add,
+    Triad,
+}
+
+impl Kernel {
+    const ALL: [Kernel; 4] = [Kernel::Copy, Kernel::Scale, Kernel::Add, Kernel::Triad];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Kernel::Copy => "copy",
+            Kernel::Scale => "scale",
+            Kernel::Add => "add",
+            Kernel::Triad => "triad",
+        }
+    }
+
+    // the number of arrays each kernel reads or writes
+    fn array_count(&self) -> usize {
+        match self {
+            Kernel::Copy | Kernel::Scale => 2,
+            Kernel::Add | Kernel::Triad => 3,
+        }
+    }
+}
+
+// best, average, and worst times of a kernel
+struct Timing {
+    min: time::Duration,
+    max: time::Duration,
+    sum: time::Duration,
+    count: u32,
+}
+
+impl Timing {
+    fn new() -> Self {
+        Timing {
+            min: time::Duration::MAX,
+            max: time::Duration::ZERO,
+            sum: time::Duration::ZERO,
+            count: 0,
+        }
+    }
+
+    fn record(&mut self, dur: time::Duration) {
+        self.min = self.min.min(dur);
+        self.max = self.max.max(dur);
+        self.sum += dur;
+        self.count += 1;
+    }
+}
+
+// an array of f64 backed by an anonymous mapping
+struct Array {
+    mmap: rustest::Mmap,
+}
+
+impl Array {
+    fn new(len: usize) -> Result<Self, io::Error> {
+        let mmap = rustest::Mmap::anonymous(len * mem::size_of::<f64>())?;
+        Ok(Array { mmap })
+    }
+
+    fn as_slice(&self) -> &[f64] {
+        // SAFETY: the mapping is page-aligned and any bit pattern is a valid f64
+        let (_, vals, _) = unsafe { self.mmap.as_bytes().align_to::<f64>() };
+        vals
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [f64] {
+        // SAFETY: the mapping is page-aligned and any bit pattern is a valid f64
+        let (_, vals, _) = unsafe { self.mmap.as_bytes_mut().align_to_mut::<f64>() };
+        vals
+    }
+}
+
+// returns the number of numa nodes
+fn numa_nodes() -> usize {
+    fs::read_dir("/sys/devices/system/node")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    name.strip_prefix("node")
+                        .is_some_and(|id| id.parse::<u32>().is_ok())
+                })
+                .count()
+        })
+        .unwrap_or(1)
+}
+
+// the part of each array that a worker owns; every kernel reads and writes the same indices of
+// the arrays, so the parts of different workers never overlap
+struct Part<'a> {
+    a: &'a mut [f64],
+    b: &'a mut [f64],
+    c: &'a mut [f64],
+}
+
+impl Part<'_> {
+    fn run(&mut self, kernel: &Kernel) {
+        match kernel {
+            Kernel::Copy => self.c.copy_from_slice(self.a),
+            Kernel::Scale => {
+                for (d, s) in self.b.iter_mut().zip(&*self.c) {
+                    *d = SCALAR * s;
+                }
+            }
+            Kernel::Add => {
+                for ((d, s1), s2) in self.c.iter_mut().zip(&*self.a).zip(&*self.b) {
+                    *d = s1 + s2;
+                }
+            }
+            Kernel::Triad => {
+                for ((d, s1), s2) in self.a.iter_mut().zip(&*self.b).zip(&*self.c) {
+                    *d = s1 + SCALAR * s2;
+                }
+            }
+        }
+    }
+}
+
+// runs every kernel args.loops times on workers that are spawned once, and returns the timing
+// of each kernel
+//
+// Each worker initializes its part before using it, so first-touch numa placement puts the
+// pages on the node of the worker.  Unless workers are pinned, the scheduler may still migrate
+// them away afterward.
+fn run(args: &Args, a: &mut Array, b: &mut Array, c: &mut Array) -> Vec<Timing> {
+    let len = a.as_slice().len();
+    let chunk_len = len.div_ceil(args.threads);
+    let parts: Vec<_> = a
+        .as_mut_slice()
+        .chunks_mut(chunk_len)
+        .zip(b.as_mut_slice().chunks_mut(chunk_len))
+        .zip(c.as_mut_slice().chunks_mut(chunk_len))
+        .map(|((a, b), c)| Part { a, b, c })
+        .collect();
+
+    // a kernel starts when all workers and the timer wait on go, and ends when they all wait
+    // on done
+    let go = Barrier::new(parts.len() + 1);
+    let done = Barrier::new(parts.len() + 1);
+
+    let mut timings: Vec<_> = Kernel::ALL.iter().map(|_| Timing::new()).collect();
+    thread::scope(|scope| {
+        for (idx, mut part) in parts.into_iter().enumerate() {
+            let (go, done) = (&go, &done);
+            scope.spawn(move || {
+                if !args.cpus.is_empty() {
+                    let _ = rustest::pin_to_cpu(args.cpus[idx % args.cpus.len()]);
+                }
+
+                part.a.fill(1.0);
+                part.b.fill(2.0);
+                part.c.fill(0.0);
+
+                for _ in 0..args.loops {
+                    for kernel in &Kernel::ALL {
+                        go.wait();
+                        part.run(kernel);
+                        done.wait();
+                    }
+                }
+            });
+        }
+
+        for iter in 0..args.loops {
+            for timing in &mut timings {
+                go.wait();
+                let start = time::Instant::now();
+                done.wait();
+
+                // the first loop warms up and is excluded when there are more
+                if iter > 0 || args.loops == 1 {
+                    timing.record(start.elapsed());
+                }
+            }
+        }
+    });
+
+    timings
+}
+
+fn print_help() {
+    println!("usage: stream [options] [ARRAY_MB]");
+    println!("  ARRAY_MB: size of each of the three arrays (default: 256)");
+    println!("  --threads N: number of threads (default: all cpus)");
+    println!("  --loops N: number of times each kernel runs (default: 10)");
+    println!("  --pin: pin thread i to cpu i");
+    println!("  --cpus LIST: pin thread i to the i-th cpu of LIST, such as 0-3,8, round-robin");
+    println!("  --json: print the results as JSON lines");
+}
+
+struct Args {
+    array_mb: usize,
+    threads: usize,
+    loops: usize,
+    // the cpus that threads are pinned to, round-robin
+    cpus: Vec<usize>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        array_mb: 256,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        loops: 10,
+        cpus: Vec::new(),
+        json: false,
+    };
+
+    let mut pin = false;
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--threads" => args.threads = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--loops" => args.loops = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--pin" => pin = true,
+            "--cpus" => {
+                args.cpus = rustest::parse_cpu_list(&iter.next().unwrap_or_default())?;
+                rustest::check_cpus(&args.cpus)?;
+            }
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.array_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    args.threads = args.threads.max(1);
+    args.loops = args.loops.max(1);
+    if pin && args.cpus.is_empty() {
+        args.cpus = (0..args.threads).collect();
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let len = args.array_mb * 1024 * 1024 / mem::size_of::<f64>();
+    if len == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid size"));
+    }
+
+    if args.json {
+        rustest::JsonLine::new("config")
+            .field("array_mb", args.array_mb)
+            .field("threads", args.threads)
+            .field("numa_nodes", numa_nodes())
+            .field("cpus", &args.cpus)
+            .emit();
+    } else {
+        println!(
+            "{} MB per array, {} threads, {} numa nodes",
+            args.array_mb,
+            args.threads,
+            numa_nodes()
+        );
+    }
+
+    let mut a = Array::new(len)?;
+    let mut b = Array::new(len)?;
+    let mut c = Array::new(len)?;
+    let timings = run(&args, &mut a, &mut b, &mut c);
+
+    if !args.json {
+        println!(
+            "{:8} {:>12} {:>10} {:>10} {:>10}",
+            "kernel", "best MB/s", "avg s", "min s", "max s"
+        );
+    }
+    for (kernel, timing) in Kernel::ALL.iter().zip(&timings) {
+        let bytes = (kernel.array_count() * len * mem::size_of::<f64>()) as f64;
+        if args.json {
+            rustest::JsonLine::new("result")
+                .field("kernel", kernel.name())
+                .field(
+                    "best_mb_per_sec",
+                    bytes / 1024.0 / 1024.0 / timing.min.as_secs_f64(),
+                )
+                .field("avg_ns", timing.sum / timing.count)
+                .field("min_ns", timing.min)
+                .field("max_ns", timing.max)
+                .emit();
+            continue;
+        }
+
+        let avg = timing.sum.as_secs_f64() / timing.count as f64;
+        println!(
+            "{:8} {:12.1} {:10.6} {:10.6} {:10.6}",
+            kernel.name(),
+            bytes / 1024.0 / 1024.0 / timing.min.as_secs_f64(),
+            avg,
+            timing.min.as_secs_f64(),
+            timing.max.as_secs_f64()
+        );
+    }
+
+    // replay the kernels on scalars to validate the arrays
+    let (mut aj, mut bj, mut cj) = (1.0f64, 2.0f64, 0.0f64);
+    for _ in 0..args.loops {
+        cj = aj;
+        bj = SCALAR * cj;
+        cj = aj + bj;
+        aj = bj + SCALAR * cj;
+    }
+    let mut bad_total = 0;
+    for (name, array, expected) in [("a", &a, aj), ("b", &b, bj), ("c", &c, cj)] {
+        let bad = array
+            .as_slice()
+            .iter()
+            .filter(|&&val| ((val - expected) / expected).abs() > 1e-13)
+            .count();
+        bad_total += bad;
+        if bad > 0 && !args.json {
+            println!(
+                "validation failed: {} of {} in {} are wrong",
+                bad, len, name
+            );
+        }
+    }
+
+    if args.json {
+        rustest::JsonLine::new("summary")
+            .field("bad", bad_total)
+            .emit();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, hint, io, time};
+
+// returns pswpin and pswpout from /proc/vmstat
+fn swap_counters() -> (u64, u64) {
+    let vmstat = fs::read_to_string("/proc/vmstat").unwrap_or_default();
+    let [pswpin, pswpout] = ["pswpin", "pswpout"].map(|key| {
+        vmstat
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+            .and_then(|val| val.parse().ok())
+            .unwrap_or_default()
+    });
+
+    (pswpin, pswpout)
+}
+
+enum Order {
+    Sequential,
+    Random,
+}
+
+impl Order {
+    const ALL: [Order; 2] = [Order::Sequential, Order::Random];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Order::Sequential => "seq",
+            Order::Random => "random",
+        }
+    }
+}
+
+struct Sample {
+    swapped: usize,
+    pswpout: u64,
+    pswpin: u64,
+    faults: rustest::Histogram,
+    elapsed: time::Duration,
+    bad: usize,
+}
+
+// pages the mapping out and times faulting it back in page by page
+fn run(mmap: &mut rustest::Mmap, order: &Order, fill: rustest::Fill) -> Result<Sample, io::Error> {
+    let page_size = rustest::page_size();
+    let page_count = mmap.as_bytes().len() / page_size;
+
+    let (_, pswpout) = swap_counters();
+    mmap.madvise(libc::MADV_PAGEOUT)?;
+    let swapped = page_count - mmap.resident()?;
+    let (pswpin, cur_pswpout) = swap_counters();
+
+    let mut pages: Vec<usize> = (0..page_count).collect();
+    if let Order::Random = order {
+        rustest::shuffle(&mut pages, 0);
+    }
+
+    let bytes = mmap.as_bytes();
+    let mut faults = rustest::Histogram::new();
+    let start = time::Instant::now();
+    for page in pages {
+        let begin = time::Instant::now();
+        hint::black_box(bytes[page * page_size]);
+        faults.record(begin.elapsed());
+    }
+    let elapsed = start.elapsed();
+
+    let (cur_pswpin, _) = swap_counters();
+
+    Ok(Sample {
+        swapped,
+        pswpout: cur_pswpout - pswpout,
+        pswpin: cur_pswpin - pswpin,
+        faults,
+        elapsed,
+        bad: mmap.verify_with(fill, 1),
+    })
+}
+
+fn print_help() {
+    println!("usage: swapin [options] [SIZE_MB]");
+    println!("  SIZE_MB: memory to page out and back in (default: 256)");
+    println!("  --fill MODE: zero, byte, text, random[:SEED], mixed:PCT (default: random)");
+    println!("  --loops N: number of times each order runs (default: 1)");
+    println!("  --json: print the results as JSON lines");
+    println!("note: pages only leave memory when there is swap");
+}
+
+struct Args {
+    size_mb: usize,
+    fill: rustest::Fill,
+    loops: usize,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        size_mb: 256,
+        fill: rustest::Fill::Random(0),
+        loops: 1,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--fill" => {
+                let fill = iter.next().unwrap_or_default();
+                args.fill = rustest::Fill::parse(&fill).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid fill {}", fill),
+                    )
+                })?;
+            }
+            "--loops" => args.loops = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.size_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let len = args.size_mb * 1024 * 1024;
+    if len == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid size"));
+    }
+
+    if !args.json {
+        println!("allocating and filling {} MB...", args.size_mb);
+    }
+    let mut mmap = rustest::Mmap::anonymous(len)?;
+    mmap.fill_with(args.fill, 1);
+
+    let page_kb = rustest::page_size() as u64 / 1024;
+    if !args.json {
+        println!(
+            "{:6} {:>10} {:>10} {:>10} {:>8} {:>10} {:>10} {:>10}",
+            "order", "swapped", "pswpout", "pswpin", "secs", "MB/s", "p50", "p99"
+        );
+    }
+    for _ in 0..args.loops {
+        for order in &Order::ALL {
+            let sample = run(&mut mmap, order, args.fill)?;
+
+            let secs = sample.elapsed.as_secs_f64();
+            if args.json {
+                rustest::JsonLine::new("result")
+                    .field("order", order.name())
+                    .field("swapped", sample.swapped)
+                    .field("pswpout", sample.pswpout)
+                    .field("pswpin", sample.pswpin)
+                    .field("elapsed_ns", sample.elapsed)
+                    .field(
+                        "mb_per_sec",
+                        (sample.pswpin * page_kb) as f64 / 1024.0 / secs,
+                    )
+                    .field("faults", &sample.faults)
+                    .field("bad", sample.bad)
+                    .emit();
+                continue;
+            }
+
+            println!(
+                "{:6} {:10} {:10} {:10} {:8.3} {:10.1} {:>10} {:>10}",
+                order.name(),
+                sample.swapped,
+                sample.pswpout,
+                sample.pswpin,
+                secs,
+                (sample.pswpin * page_kb) as f64 / 1024.0 / secs,
+                format!(
+                    "< {}",
+                    rustest::Histogram::format_ns(sample.faults.percentile(50))
+                ),
+                format!(
+                    "< {}",
+                    rustest::Histogram::format_ns(sample.faults.percentile(99))
+                ),
+            );
+
+            if sample.bad > 0 {
+                println!("{:6} read back {} bad pages", order.name(), sample.bad);
+            }
+            if sample.swapped == 0 {
+                println!("{:6} paged out nothing; is swap enabled?", order.name());
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,232 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, fs, hint,
+    io::{self, BufRead},
+    time,
+};
+
+// how often progress is reported
+const REPORT_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+struct Proc {
+    mem_total: u64,
+    // accumulated pages swapped in/out to block devices
+    pswpin: u64,
+    pswpout: u64,
+}
+
+impl Proc {
+    fn collect() -> Self {
+        let mut proc = Proc {
+            mem_total: 0,
+            pswpin: 0,
+            pswpout: 0,
+        };
+
+        let _ = proc.collect_meminfo();
+        let _ = proc.collect_vmstat();
+
+        proc
+    }
+
+    fn collect_meminfo(&mut self) -> Result<(), io::Error> {
+        let fp = fs::File::open("/proc/meminfo")?;
+        let reader = io::BufReader::new(fp);
+
+        for line in reader.lines() {
+            let line = line?;
+
+            if let Some(val) = line.strip_prefix("MemTotal:") {
+                self.mem_total = val
+                    .split_ascii_whitespace()
+                    .next()
+                    .and_then(|val| val.parse().ok())
+                    .unwrap_or_default();
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn collect_vmstat(&mut self) -> Result<(), io::Error> {
+        let fp = fs::File::open("/proc/vmstat")?;
+        let reader = io::BufReader::new(fp);
+
+        for line in reader.lines() {
+            let line = line?;
+
+            if let Some(val) = line.strip_prefix("pswpin ") {
+                self.pswpin = val.parse().unwrap_or_default();
+            } else if let Some(val) = line.strip_prefix("pswpout ") {
+                self.pswpout = val.parse().unwrap_or_default();
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn print_help() {
+    println!("usage: swapthrash [options] [SIZE_MB]");
+    println!("  SIZE_MB: memory to allocate (default: 125% of MemTotal)");
+    println!("  --duration SECS: how long to touch pages (default: 10)");
+    println!("  --fill MODE: zero, byte, text, random[:SEED], mixed:PCT (default: random)");
+    println!("  --write: dirty the touched pages rather than reading them");
+    println!("  --nice N: nice value, -20 to 19 (default: unchanged)");
+    println!("  --ionice CLASS[:LEVEL]: io priority, idle, be:0-7, or rt:0-7 (default: unchanged)");
+    println!("  --json: print samples and the summary as JSON lines");
+    println!("note: sizes beyond RAM need enough swap, or the OOM killer steps in");
+}
+
+struct Args {
+    size_mb: usize,
+    duration: time::Duration,
+    fill: rustest::Fill,
+    write: bool,
+    nice: Option<i32>,
+    ioprio: Option<rustest::IoPrio>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        size_mb: (Proc::collect().mem_total / 1024 * 5 / 4) as usize,
+        duration: time::Duration::from_secs(10),
+        fill: rustest::Fill::Random(0),
+        write: false,
+        nice: None,
+        ioprio: None,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--duration" => {
+                args.duration = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--fill" => {
+                let fill = iter.next().unwrap_or_default();
+                args.fill = rustest::Fill::parse(&fill).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid fill {}", fill),
+                    )
+                })?;
+            }
+            "--write" => args.write = true,
+            "--nice" => args.nice = Some(rustest::parse_nice(&iter.next().unwrap_or_default())?),
+            "--ionice" => {
+                args.ioprio = Some(rustest::IoPrio::parse(&iter.next().unwrap_or_default())?)
+            }
+            "--json" => args.json = true,
+            _ if arg.starts_with('-') => return Err(rustest::unknown_option(&arg)),
+            _ => args.size_mb = rustest::parse_mb(&arg)?,
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    rustest::set_priority(args.nice, args.ioprio)?;
+
+    let page_size = rustest::page_size();
+    let len = args.size_mb * 1024 * 1024;
+    let page_count = len / page_size;
+    if page_count == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid size"));
+    }
+
+    for rlimit in [rustest::Rlimit::As, rustest::Rlimit::Data] {
+        rlimit.reserve(Some(len as u64))?;
+    }
+
+    if !args.json {
+        println!("allocating and filling {} MB...", args.size_mb);
+    }
+    let mut mmap = rustest::Mmap::anonymous_with_flags(len, libc::MAP_NORESERVE)?;
+    mmap.fill_with(args.fill, 1);
+
+    if !args.json {
+        println!(
+            "{} {} pages randomly for {:?}...",
+            if args.write { "writing" } else { "reading" },
+            page_count,
+            args.duration
+        );
+    }
+
+    let bytes = mmap.as_bytes_mut();
+    let mut total = rustest::Histogram::new();
+    let mut interval = rustest::Histogram::new();
+    let mut state = 1;
+    let mut prev = Proc::collect();
+    let start = time::Instant::now();
+    let mut report = start;
+    while start.elapsed() < args.duration {
+        let page = (rustest::xorshift(&mut state) % page_count as u64) as usize;
+        let offset = page * page_size;
+
+        let begin = time::Instant::now();
+        if args.write {
+            bytes[offset] = bytes[offset].wrapping_add(1);
+        } else {
+            hint::black_box(bytes[offset]);
+        }
+        interval.record(begin.elapsed());
+
+        if report.elapsed() >= REPORT_INTERVAL {
+            let cur = Proc::collect();
+            let [swap_in, swap_out] = [cur.pswpin - prev.pswpin, cur.pswpout - prev.pswpout]
+                .map(|page_count| page_count as usize * page_size / 1024 / 1024);
+
+            if args.json {
+                rustest::JsonLine::new("sample")
+                    .field("elapsed_ns", start.elapsed())
+                    .field("touches", &interval)
+                    .field("swap_in_mb", swap_in)
+                    .field("swap_out_mb", swap_out)
+                    .emit();
+            } else {
+                println!(
+                    "{:5.1}s: {}, swap i/o +{}/+{} MB",
+                    start.elapsed().as_secs_f64(),
+                    interval,
+                    swap_in,
+                    swap_out
+                );
+            }
+
+            total.add(&interval);
+            interval = rustest::Histogram::new();
+            prev = cur;
+            report = time::Instant::now();
+        }
+    }
+    total.add(&interval);
+
+    if args.json {
+        rustest::JsonLine::new("summary")
+            .field("size_mb", args.size_mb)
+            .field("write", args.write)
+            .field("touches", &total)
+            .emit();
+    } else {
+        println!("total: {}", total);
+        total.print();
+    }
+
+    Ok(())
+}
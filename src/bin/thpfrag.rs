@@ -0,0 +1,224 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{env, fs, io};
+
+// the order of a pmd-sized page on x86-64 and arm64 with 4K pages
+const PMD_ORDER: usize = 9;
+
+// vmstat counters that show how the kernel copes with fragmentation
+const COUNTERS: [&str; 9] = [
+    "compact_stall",
+    "compact_success",
+    "compact_fail",
+    "compact_migrate_scanned",
+    "compact_free_scanned",
+    "thp_fault_alloc",
+    "thp_fault_fallback",
+    "thp_collapse_alloc",
+    "thp_collapse_alloc_failed",
+];
+
+struct Sample {
+    counters: [u64; COUNTERS.len()],
+    // AnonHugePages of this process
+    anon_huge_kb: u64,
+    // free blocks of PMD_ORDER or higher, in units of PMD_ORDER
+    free_blocks: u64,
+}
+
+impl Sample {
+    fn collect() -> Self {
+        let vmstat = fs::read_to_string("/proc/vmstat").unwrap_or_default();
+        let counters = COUNTERS.map(|key| {
+            vmstat
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default()
+        });
+
+        let anon_huge_kb = fs::read_to_string("/proc/self/smaps_rollup")
+            .ok()
+            .and_then(|rollup| {
+                rollup
+                    .lines()
+                    .find_map(|line| line.strip_prefix("AnonHugePages:"))
+                    .and_then(|val| val.split_ascii_whitespace().next())
+                    .and_then(|val| val.parse().ok())
+            })
+            .unwrap_or_default();
+
+        // lines are in the form of "Node 0, zone Normal <order 0 count> <order 1 count> ..."
+        let buddyinfo = fs::read_to_string("/proc/buddyinfo").unwrap_or_default();
+        let free_blocks = buddyinfo
+            .lines()
+            .flat_map(|line| {
+                line.split_ascii_whitespace()
+                    .skip(4)
+                    .enumerate()
+                    .skip(PMD_ORDER)
+                    .map(|(order, count)| {
+                        count.parse::<u64>().unwrap_or_default() << (order - PMD_ORDER)
+                    })
+            })
+            .sum();
+
+        Sample {
+            counters,
+            anon_huge_kb,
+            free_blocks,
+        }
+    }
+
+    fn print_delta(&self, prev: &Sample, phase: &str, json: bool) {
+        if json {
+            let mut line = rustest::JsonLine::new("phase")
+                .field("phase", phase)
+                .field("free_blocks", self.free_blocks)
+                .field("anon_huge_kb", self.anon_huge_kb);
+            for ((key, cur), prev) in COUNTERS.iter().zip(self.counters).zip(prev.counters) {
+                line.push(key, cur - prev);
+            }
+            line.emit();
+            return;
+        }
+
+        println!(
+            "  free order-{} blocks {} -> {}, AnonHugePages {} -> {} MB",
+            PMD_ORDER,
+            prev.free_blocks,
+            self.free_blocks,
+            prev.anon_huge_kb / 1024,
+            self.anon_huge_kb / 1024
+        );
+        for ((key, cur), prev) in COUNTERS.iter().zip(self.counters).zip(prev.counters) {
+            if cur > prev {
+                println!("  {} +{}", key, cur - prev);
+            }
+        }
+    }
+}
+
+// faults in a mapping with small pages and frees all but one in every keep pages, leaving
+// the rest scattered over the physical memory
+fn fragment(size_mb: usize, keep: usize) -> Result<rustest::Mmap, io::Error> {
+    let page_size = rustest::page_size();
+    let mut mmap = rustest::Mmap::anonymous(size_mb * 1024 * 1024)?;
+    mmap.madvise(libc::MADV_NOHUGEPAGE)?;
+    mmap.fill(1);
+
+    let page_count = size_mb * 1024 * 1024 / page_size;
+    for page in (0..page_count).filter(|page| page % keep != 0) {
+        let offset = page * page_size;
+        mmap.madvise_range(offset..offset + page_size, libc::MADV_DONTNEED)?;
+    }
+
+    Ok(mmap)
+}
+
+// faults in a mapping with small pages and collapses them into huge pages
+fn collapse(size_mb: usize) -> Result<rustest::Mmap, io::Error> {
+    let mut mmap = rustest::Mmap::anonymous(size_mb * 1024 * 1024)?;
+    mmap.madvise(libc::MADV_NOHUGEPAGE)?;
+    mmap.fill(1);
+
+    // MADV_COLLAPSE fails on VM_NOHUGEPAGE
+    mmap.madvise(libc::MADV_HUGEPAGE)?;
+    if let Err(err) = mmap.madvise(libc::MADV_COLLAPSE) {
+        println!("  failed: {}", err);
+    }
+
+    Ok(mmap)
+}
+
+fn print_help() {
+    println!("usage: thpfrag [options]");
+    println!("  --frag MB: memory to fragment (default: 1024)");
+    println!("  --keep N: keep one in every N pages of the fragmented memory (default: 2)");
+    println!("  --thp MB: memory to fault in with MADV_HUGEPAGE and to collapse (default: 256)");
+    println!("  --json: print the phases as JSON lines");
+}
+
+struct Args {
+    frag_mb: usize,
+    keep: usize,
+    thp_mb: usize,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let mut args = Args {
+        frag_mb: 1024,
+        keep: 2,
+        thp_mb: 256,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--frag" => args.frag_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--keep" => args.keep = rustest::parse_num(&iter.next().unwrap_or_default())?,
+            "--thp" => args.thp_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    args.keep = args.keep.max(1);
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let mut prev = Sample::collect();
+
+    if !args.json {
+        println!(
+            "fragmenting {} MB, keeping one in every {} pages...",
+            args.frag_mb, args.keep
+        );
+    }
+    let frag = fragment(args.frag_mb, args.keep)?;
+    let cur = Sample::collect();
+    cur.print_delta(&prev, "fragment", args.json);
+    prev = cur;
+
+    if !args.json {
+        println!("faulting in {} MB with MADV_HUGEPAGE...", args.thp_mb);
+    }
+    let mut thp = rustest::Mmap::anonymous(args.thp_mb * 1024 * 1024)?;
+    thp.madvise(libc::MADV_HUGEPAGE)?;
+    thp.fill(1);
+    let cur = Sample::collect();
+    cur.print_delta(&prev, "fault", args.json);
+    prev = cur;
+
+    if !args.json {
+        println!("MADV_COLLAPSE while fragmented...");
+    }
+    let collapsed = collapse(args.thp_mb)?;
+    let cur = Sample::collect();
+    cur.print_delta(&prev, "collapse fragmented", args.json);
+    prev = cur;
+
+    drop(thp);
+    drop(collapsed);
+    drop(frag);
+    if !args.json {
+        println!("MADV_COLLAPSE after freeing the fragmented memory...");
+    }
+    let _collapsed = collapse(args.thp_mb)?;
+    let cur = Sample::collect();
+    cur.print_delta(&prev, "collapse", args.json);
+
+    Ok(())
+}
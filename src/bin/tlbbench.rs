@@ -0,0 +1,225 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, fs, hint, io,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread, time,
+};
+
+// returns the total count of an /proc/interrupts row, such as "TLB" for x86 shootdowns
+fn interrupts(name: &str) -> u64 {
+    let interrupts = fs::read_to_string("/proc/interrupts").unwrap_or_default();
+    interrupts
+        .lines()
+        .find_map(|line| line.trim_start().strip_prefix(name)?.strip_prefix(':'))
+        .map(|counts| {
+            counts
+                .split_ascii_whitespace()
+                .map_while(|count| count.parse::<u64>().ok())
+                .sum()
+        })
+        .unwrap_or_default()
+}
+
+struct Sample {
+    flips: rustest::Histogram,
+    touches: u64,
+    shootdowns: u64,
+    elapsed: time::Duration,
+}
+
+// flips the protection of the mapping while readers touch its pages, so that every flip
+// must shoot down the TLBs of the cpus that run the readers
+//
+// Reader i is pinned to the i-th cpu of cpus, round-robin, unless cpus is empty.
+fn run(mmap: &rustest::Mmap, readers: usize, cpus: &[usize], duration: time::Duration) -> Sample {
+    let page_size = rustest::page_size();
+    let stop = AtomicBool::new(false);
+    let touches = AtomicU64::new(0);
+
+    let mut flips = rustest::Histogram::new();
+    let shootdowns = interrupts("TLB");
+    let start = time::Instant::now();
+    thread::scope(|scope| {
+        for idx in 0..readers {
+            let (stop, touches) = (&stop, &touches);
+            scope.spawn(move || {
+                if !cpus.is_empty() {
+                    let _ = rustest::pin_to_cpu(cpus[idx % cpus.len()]);
+                }
+
+                let mut count = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    for page in mmap.as_bytes().chunks(page_size) {
+                        hint::black_box(page[0]);
+                    }
+                    count += mmap.as_bytes().len() as u64 / page_size as u64;
+                }
+                touches.fetch_add(count, Ordering::Relaxed);
+            });
+        }
+
+        let mut write = false;
+        while start.elapsed() < duration {
+            let prot = if write {
+                libc::PROT_READ | libc::PROT_WRITE
+            } else {
+                libc::PROT_READ
+            };
+
+            let begin = time::Instant::now();
+            // SAFETY: every flip keeps PROT_READ and the mapping is only read until it is dropped
+            if unsafe { mmap.mprotect(prot) }.is_err() {
+                break;
+            }
+            flips.record(begin.elapsed());
+
+            write = !write;
+        }
+
+        stop.store(true, Ordering::Relaxed);
+    });
+
+    Sample {
+        flips,
+        touches: touches.into_inner(),
+        shootdowns: interrupts("TLB") - shootdowns,
+        elapsed: start.elapsed(),
+    }
+}
+
+fn print_help() {
+    println!("usage: tlbbench [options]");
+    println!("  --size MB: size of the shared mapping (default: 16)");
+    println!("  --threads N,...: reader thread counts to run (default: 0, 1, 2, 4, ... cpus)");
+    println!("  --duration SECS: how long each thread count runs (default: 3)");
+    println!("  --cpus LIST: pin reader i to the i-th cpu of LIST, such as 0-3,8, round-robin");
+    println!("  --perf: also count faults, context switches, and dTLB misses with perf events");
+    println!("  --json: print the results as JSON lines");
+}
+
+struct Args {
+    size_mb: usize,
+    threads: Vec<usize>,
+    duration: time::Duration,
+    cpus: Vec<usize>,
+    perf: bool,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, io::Error> {
+    let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut args = Args {
+        size_mb: 16,
+        threads: [0]
+            .into_iter()
+            .chain((0..).map(|shift| 1 << shift).take_while(|&n| n <= cpus))
+            .collect(),
+        duration: time::Duration::from_secs(3),
+        cpus: Vec::new(),
+        perf: false,
+        json: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--size" => args.size_mb = rustest::parse_mb(&iter.next().unwrap_or_default())?,
+            "--threads" => {
+                let list = iter.next().unwrap_or_default();
+                args.threads = list
+                    .split(',')
+                    .filter(|count| !count.is_empty())
+                    .map(|count| {
+                        count.parse().map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!("invalid thread count {}", count),
+                            )
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--duration" => {
+                args.duration = rustest::parse_duration(&iter.next().unwrap_or_default())?
+            }
+            "--cpus" => {
+                args.cpus = rustest::parse_cpu_list(&iter.next().unwrap_or_default())?;
+                rustest::check_cpus(&args.cpus)?;
+            }
+            "--perf" => args.perf = true,
+            "--json" => args.json = true,
+            _ => return Err(rustest::unknown_option(&arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> Result<(), io::Error> {
+    let args = parse_args()?;
+
+    if !args.json {
+        print_help();
+        println!();
+    }
+
+    let mut mmap = rustest::Mmap::anonymous(args.size_mb * 1024 * 1024)?;
+    mmap.fill(1);
+
+    // the readers are spawned after the counters are opened and are counted too
+    let perf = args
+        .perf
+        .then(|| rustest::PerfCounters::open(&rustest::PerfEvent::ALL))
+        .transpose()?;
+
+    if !args.json {
+        println!(
+            "{:>7} {:>10} {:>10} {:>12} {:>12} {:>12}",
+            "readers", "flips/s", "p50", "p99", "shootdowns", "touches/s"
+        );
+    }
+    for &readers in &args.threads {
+        let perf_prev = perf.as_ref().map(|perf| perf.read());
+        let sample = run(&mmap, readers, &args.cpus, args.duration);
+        let perf_delta = perf
+            .as_ref()
+            .zip(perf_prev)
+            .map(|(perf, prev)| perf.read().delta(&prev));
+        let secs = sample.elapsed.as_secs_f64();
+
+        if args.json {
+            rustest::JsonLine::new("result")
+                .field("readers", readers)
+                .field("flips_per_sec", sample.flips.count() as f64 / secs)
+                .field("flips", &sample.flips)
+                .field("shootdowns", sample.shootdowns)
+                .field("touches_per_sec", sample.touches as f64 / secs)
+                .field("perf", &perf_delta)
+                .emit();
+            continue;
+        }
+
+        println!(
+            "{:7} {:10.0} {:>10} {:>12} {:12} {:12.0}",
+            readers,
+            sample.flips.count() as f64 / secs,
+            format!(
+                "< {}",
+                rustest::Histogram::format_ns(sample.flips.percentile(50))
+            ),
+            format!(
+                "< {}",
+                rustest::Histogram::format_ns(sample.flips.percentile(99))
+            ),
+            sample.shootdowns,
+            sample.touches as f64 / secs
+        );
+        if let Some(perf_delta) = &perf_delta {
+            println!("{:7} perf: {}", "", perf_delta);
+        }
+    }
+
+    Ok(())
+}
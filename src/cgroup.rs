@@ -0,0 +1,232 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crate::PsiTrigger;
+use std::{
+    fs,
+    io::{self, Read, Seek},
+    os::fd::{AsRawFd, RawFd},
+    path, process,
+};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+// a cgroup v2 leaf that is removed on drop
+pub struct Cgroup {
+    path: path::PathBuf,
+    // the cgroup we were in before entering this one
+    orig: Option<path::PathBuf>,
+}
+
+impl Cgroup {
+    pub fn create(name: &str) -> Result<Self, io::Error> {
+        let path = path::Path::new(CGROUP_ROOT).join(name);
+        if let Some(parent) = path.parent() {
+            fs::write(parent.join("cgroup.subtree_control"), "+memory")?;
+        }
+        fs::create_dir_all(&path)?;
+
+        Ok(Cgroup { path, orig: None })
+    }
+
+    pub fn name(&self) -> &str {
+        self.path
+            .strip_prefix(CGROUP_ROOT)
+            .ok()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+
+    // moves this process into the cgroup
+    pub fn enter(&mut self) -> Result<(), io::Error> {
+        let cur = fs::read_to_string("/proc/self/cgroup")?;
+        let orig = cur
+            .lines()
+            .find_map(|line| line.strip_prefix("0::/"))
+            .map(|name| path::Path::new(CGROUP_ROOT).join(name));
+
+        self.add_pid(process::id())?;
+        self.orig = orig;
+
+        Ok(())
+    }
+
+    pub fn add_pid(&self, pid: u32) -> Result<(), io::Error> {
+        self.write("cgroup.procs", &pid.to_string())
+    }
+
+    pub fn read(&self, file: &str) -> Result<String, io::Error> {
+        fs::read_to_string(self.path.join(file))
+    }
+
+    pub fn write(&self, file: &str, val: &str) -> Result<(), io::Error> {
+        fs::write(self.path.join(file), val)
+    }
+
+    // reads a single-value file, where "max" is u64::MAX
+    pub fn read_value(&self, file: &str) -> Option<u64> {
+        let val = self.read(file).ok()?;
+        match val.trim() {
+            "max" => Some(u64::MAX),
+            val => val.parse().ok(),
+        }
+    }
+
+    // writes a single-value file, where u64::MAX is "max"
+    pub fn write_value(&self, file: &str, val: u64) -> Result<(), io::Error> {
+        if val == u64::MAX {
+            self.write(file, "max")
+        } else {
+            self.write(file, &val.to_string())
+        }
+    }
+
+    // watches memory.events and, with trigger, memory.pressure
+    pub fn watch(&self, trigger: Option<&str>) -> Result<CgroupWatcher, io::Error> {
+        CgroupWatcher::new(self, trigger)
+    }
+
+    // reads a flat-keyed file such as memory.events
+    pub fn read_keyed(&self, file: &str, key: &str) -> Option<u64> {
+        let val = self.read(file).ok()?;
+        val.lines().find_map(|line| {
+            line.strip_prefix(key)
+                .and_then(|val| val.strip_prefix(' '))
+                .and_then(|val| val.parse().ok())
+        })
+    }
+}
+
+// a change reported by a CgroupWatcher
+pub struct CgroupEvent {
+    // a memory.events key such as "high" or "oom_kill", or "pressure" for the trigger
+    pub key: String,
+    // the increase since the previous poll
+    pub delta: u64,
+    pub total: u64,
+}
+
+// watches memory.events and a memory.pressure trigger of a cgroup without blocking
+pub struct CgroupWatcher {
+    events: fs::File,
+    counts: Vec<(String, u64)>,
+    pressure: Option<PsiTrigger>,
+    pressure_count: u64,
+}
+
+impl CgroupWatcher {
+    // trigger is in the form of "some:STALL_MS:WINDOW_MS" or "full:STALL_MS:WINDOW_MS"
+    fn new(cgroup: &Cgroup, trigger: Option<&str>) -> Result<Self, io::Error> {
+        let events = fs::File::open(cgroup.path.join("memory.events"))?;
+
+        let pressure = trigger
+            .map(|spec| PsiTrigger::register(cgroup.path.join("memory.pressure"), spec))
+            .transpose()?;
+
+        let mut watcher = CgroupWatcher {
+            events,
+            counts: Vec::new(),
+            pressure,
+            pressure_count: 0,
+        };
+        watcher.read_events();
+
+        Ok(watcher)
+    }
+
+    // reads memory.events and returns the counters that increased
+    fn read_events(&mut self) -> Vec<CgroupEvent> {
+        let mut text = String::new();
+        if self.events.rewind().is_err() || self.events.read_to_string(&mut text).is_err() {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        for line in text.lines() {
+            let Some((key, total)) = line.split_once(' ') else {
+                continue;
+            };
+            let Ok(total) = total.parse::<u64>() else {
+                continue;
+            };
+
+            match self.counts.iter_mut().find(|(k, _)| k == key) {
+                Some((_, count)) => {
+                    if total > *count {
+                        events.push(CgroupEvent {
+                            key: key.to_string(),
+                            delta: total - *count,
+                            total,
+                        });
+                    }
+                    *count = total;
+                }
+                None => self.counts.push((key.to_string(), total)),
+            }
+        }
+
+        events
+    }
+
+    // returns the fds of memory.events and the trigger, which notify with POLLPRI
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    pub(crate) fn fds(&self) -> (RawFd, Option<RawFd>) {
+        (
+            self.events.as_raw_fd(),
+            self.pressure.as_ref().map(|trigger| trigger.as_raw_fd()),
+        )
+    }
+
+    // returns the changes since the previous poll
+    //
+    // memory.events is only read again after the kernel notifies that it changed.
+    pub fn poll(&mut self) -> Vec<CgroupEvent> {
+        let mut pollfds = vec![libc::pollfd {
+            fd: self.events.as_raw_fd(),
+            events: libc::POLLPRI,
+            revents: 0,
+        }];
+        if let Some(pressure) = &self.pressure {
+            pollfds.push(libc::pollfd {
+                fd: pressure.as_raw_fd(),
+                events: libc::POLLPRI,
+                revents: 0,
+            });
+        }
+
+        // SAFETY: pollfds is valid for its len entries
+        let ret = unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as libc::nfds_t, 0) };
+        if ret <= 0 {
+            return Vec::new();
+        }
+
+        // kernfs notifies with POLLPRI and POLLERR
+        let mut events = if pollfds[0].revents != 0 {
+            self.read_events()
+        } else {
+            Vec::new()
+        };
+        if pollfds
+            .get(1)
+            .is_some_and(|pollfd| pollfd.revents & libc::POLLPRI != 0)
+        {
+            self.pressure_count += 1;
+            events.push(CgroupEvent {
+                key: "pressure".to_string(),
+                delta: 1,
+                total: self.pressure_count,
+            });
+        }
+
+        events
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        if let Some(orig) = &self.orig {
+            let _ = fs::write(orig.join("cgroup.procs"), process::id().to_string());
+        }
+        let _ = fs::remove_dir(&self.path);
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{io, str, time};
+
+// parses a size such as "512", "512M", "2G", or "1.5G" and returns it in bytes
+//
+// A bare number is in units of unit bytes.  Suffixes are K, M, G, and T, in powers of 1024, and
+// may be followed by B or iB; a lowercase b, as in Mb, is rejected.  Sizes of 2^64 bytes or more
+// are rejected.
+pub fn parse_bytes(s: &str, unit: u64) -> Result<u64, io::Error> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("invalid size {}", s));
+
+    let num = s
+        .strip_suffix("iB")
+        .or_else(|| s.strip_suffix('B'))
+        .unwrap_or(s);
+    let (num, scale) = match num.char_indices().last() {
+        Some((idx, suffix)) if suffix.is_ascii_alphabetic() => {
+            let shift = match suffix.to_ascii_uppercase() {
+                'K' => 10,
+                'M' => 20,
+                'G' => 30,
+                'T' => 40,
+                _ => return Err(invalid()),
+            };
+            (&num[..idx], (1u64 << shift) as f64)
+        }
+        // "B" alone means bytes
+        _ if num.len() < s.len() => (num, 1.0),
+        _ => (num, unit as f64),
+    };
+
+    let val: f64 = num.trim().parse().map_err(|_| invalid())?;
+    let bytes = val * scale;
+    // u64::MAX rounds up to 2^64 as f64
+    if !(0.0..u64::MAX as f64).contains(&bytes) {
+        return Err(invalid());
+    }
+
+    Ok(bytes as u64)
+}
+
+// parses a size and returns it in units of unit bytes, which are named unit_name; sizes that are
+// not a whole number of units are rejected
+fn parse_size<T: TryFrom<u64>>(s: &str, unit: u64, unit_name: &str) -> Result<T, io::Error> {
+    let bytes = parse_bytes(s, unit)?;
+    if bytes % unit != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "invalid size {}, which is not a whole number of {}",
+                s, unit_name
+            ),
+        ));
+    }
+
+    T::try_from(bytes / unit)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid size {}", s)))
+}
+
+// parses a size for an option or argument documented in MB
+pub fn parse_mb<T: TryFrom<u64>>(s: &str) -> Result<T, io::Error> {
+    parse_size(s, 1024 * 1024, "MB")
+}
+
+// parses a size for an option or argument documented in KB
+pub fn parse_kb<T: TryFrom<u64>>(s: &str) -> Result<T, io::Error> {
+    parse_size(s, 1024, "KB")
+}
+
+// parses a nice value, which must be within -20 to 19
+pub fn parse_nice(s: &str) -> Result<i32, io::Error> {
+    s.parse()
+        .ok()
+        .filter(|nice| (-20..=19).contains(nice))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid nice {}", s)))
+}
+
+// parses a number, such as a count, for an option or argument
+pub fn parse_num<T: str::FromStr>(s: &str) -> Result<T, io::Error> {
+    s.parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid number {}", s)))
+}
+
+// returns the error for an option that a tool does not know
+pub fn unknown_option(arg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("unknown option {}", arg),
+    )
+}
+
+// parses a duration such as "1.5", "500ms", "30s", "10m", or "2h"
+//
+// A bare number is in seconds.  Suffixes are ms, s, m, h, and d.
+pub fn parse_duration(s: &str) -> Result<time::Duration, io::Error> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid duration {}", s),
+        )
+    };
+
+    let (num, scale) = if let Some(num) = s.strip_suffix("ms") {
+        (num, 0.001)
+    } else if let Some(num) = s.strip_suffix('s') {
+        (num, 1.0)
+    } else if let Some(num) = s.strip_suffix('m') {
+        (num, 60.0)
+    } else if let Some(num) = s.strip_suffix('h') {
+        (num, 3600.0)
+    } else if let Some(num) = s.strip_suffix('d') {
+        (num, 86400.0)
+    } else {
+        (s, 1.0)
+    };
+
+    let val: f64 = num.trim().parse().map_err(|_| invalid())?;
+    time::Duration::try_from_secs_f64(val * scale).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn bytes() {
+        assert_eq!(parse_bytes("512", MB).unwrap(), 512 * MB);
+        assert_eq!(parse_bytes("4K", MB).unwrap(), 4096);
+        assert_eq!(parse_bytes("4k", MB).unwrap(), 4096);
+        assert_eq!(parse_bytes("2G", MB).unwrap(), 2048 * MB);
+        assert_eq!(parse_bytes("2GB", MB).unwrap(), 2048 * MB);
+        assert_eq!(parse_bytes("2GiB", MB).unwrap(), 2048 * MB);
+        assert_eq!(parse_bytes("1T", 1).unwrap(), 1 << 40);
+        assert_eq!(parse_bytes("100B", MB).unwrap(), 100);
+        assert_eq!(parse_bytes("1.5K", 1).unwrap(), 1536);
+
+        for s in [
+            "", "B", "iB", "-1", "-1K", "nan", "inf", "512Mb", "512mb", "2GBB", "2P", "2 G B",
+        ] {
+            assert!(parse_bytes(s, MB).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn bytes_overflow() {
+        assert_eq!(parse_bytes("16777215T", 1).unwrap(), 16777215 << 40);
+        assert!(parse_bytes("16777216T", 1).is_err());
+        assert!(parse_bytes("99999999T", 1).is_err());
+        assert!(parse_bytes("18446744073709551616", 1).is_err());
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_mb::<u64>("512").unwrap(), 512);
+        assert_eq!(parse_mb::<u64>("1.5G").unwrap(), 1536);
+        assert_eq!(parse_mb::<u64>("1024K").unwrap(), 1);
+        assert_eq!(parse_kb::<u64>("4").unwrap(), 4);
+        assert_eq!(parse_kb::<u64>("2M").unwrap(), 2048);
+
+        // not whole units
+        assert!(parse_mb::<u64>("512K").is_err());
+        assert!(parse_mb::<u64>("1.5M").is_err());
+        assert!(parse_mb::<u64>("0.5").is_err());
+        assert!(parse_kb::<u64>("100B").is_err());
+
+        // does not fit in the target type
+        assert_eq!(parse_mb::<u32>("4095T").unwrap(), 4095 << 20);
+        assert!(parse_mb::<u32>("4096T").is_err());
+    }
+
+    #[test]
+    fn durations() {
+        let ms = time::Duration::from_millis;
+        assert_eq!(parse_duration("1.5").unwrap(), ms(1500));
+        assert_eq!(parse_duration("500ms").unwrap(), ms(500));
+        assert_eq!(parse_duration("30s").unwrap(), ms(30_000));
+        assert_eq!(parse_duration("10m").unwrap(), ms(600_000));
+        assert_eq!(parse_duration("2h").unwrap(), ms(7_200_000));
+        assert_eq!(parse_duration("1d").unwrap(), ms(86_400_000));
+
+        for s in ["", "s", "-1", "nan", "inf", "1e30", "10x", "10 min"] {
+            assert!(parse_duration(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(parse_nice("-20").unwrap(), -20);
+        assert_eq!(parse_nice("19").unwrap(), 19);
+        assert!(parse_nice("20").is_err());
+        assert!(parse_nice("-21").is_err());
+        assert_eq!(parse_num::<usize>("8").unwrap(), 8);
+        assert!(parse_num::<usize>("-8").is_err());
+    }
+}
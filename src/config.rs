@@ -0,0 +1,243 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{fs, io};
+
+enum Value {
+    Bool(bool),
+    Scalar(String),
+    Array(Vec<String>),
+}
+
+// a config file in a subset of TOML, for the --config option of the tools
+//
+// Tables are named after the tools and their keys are long options, so that a config file
+// stands in for flags that are inconvenient to retype.  Values are strings, numbers,
+// booleans, or single-line arrays of strings and numbers.  Keys before the first table
+// apply to all tools.
+pub struct Config {
+    // tables and their keys, in file order
+    entries: Vec<(String, String, Value)>,
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self, io::Error> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    fn parse(text: &str) -> Result<Self, io::Error> {
+        let mut entries = Vec::new();
+        let mut table = String::new();
+        for (idx, line) in text.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid config line {}: {}", idx + 1, line),
+                )
+            };
+
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                table = name
+                    .strip_suffix(']')
+                    .ok_or_else(invalid)?
+                    .trim()
+                    .to_string();
+                continue;
+            }
+
+            let (key, val) = line.split_once('=').ok_or_else(invalid)?;
+            let key = key.trim();
+            let val = val.trim();
+            if key.is_empty() {
+                return Err(invalid());
+            }
+
+            let val = if let Some(items) = val.strip_prefix('[') {
+                let items = items.strip_suffix(']').ok_or_else(invalid)?;
+                let items = split_array(items)
+                    .into_iter()
+                    .map(|item| parse_scalar(item).ok_or_else(invalid))
+                    .collect::<Result<_, _>>()?;
+                Value::Array(items)
+            } else if val == "true" || val == "false" {
+                Value::Bool(val == "true")
+            } else {
+                Value::Scalar(parse_scalar(val).ok_or_else(invalid)?)
+            };
+
+            entries.push((table.clone(), key.to_string(), val));
+        }
+
+        Ok(Config { entries })
+    }
+
+    // returns the keys of the root table and of table as command-line arguments
+    //
+    // true becomes a bare flag, false is skipped, and arrays repeat the option.
+    pub fn args(&self, table: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        for (_, key, val) in self
+            .entries
+            .iter()
+            .filter(|(name, _, _)| name.is_empty() || name == table)
+        {
+            let opt = format!("--{}", key);
+            match val {
+                Value::Bool(true) => args.push(opt),
+                Value::Bool(false) => (),
+                Value::Scalar(val) => args.extend([opt, val.clone()]),
+                Value::Array(vals) => {
+                    for val in vals {
+                        args.extend([opt.clone(), val.clone()]);
+                    }
+                }
+            }
+        }
+
+        args
+    }
+}
+
+// strips a trailing comment, ignoring # in strings
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#') => return &line[..idx],
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            _ => (),
+        }
+    }
+
+    line
+}
+
+// splits the items of an array on commas outside strings
+fn split_array(items: &str) -> Vec<&str> {
+    let mut split = Vec::new();
+    let mut quote = None;
+    let mut begin = 0;
+    for (idx, c) in items.char_indices() {
+        match (quote, c) {
+            (None, ',') => {
+                split.push(items[begin..idx].trim());
+                begin = idx + 1;
+            }
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            _ => (),
+        }
+    }
+
+    // a trailing comma is allowed
+    let last = items[begin..].trim();
+    if !last.is_empty() {
+        split.push(last);
+    }
+
+    split
+}
+
+// returns the value of a basic string, a literal string, or a bare number
+fn parse_scalar(val: &str) -> Option<String> {
+    if let Some(literal) = val.strip_prefix('\'') {
+        return literal.strip_suffix('\'').map(String::from);
+    }
+
+    let Some(escaped) = val.strip_prefix('"') else {
+        // numbers may use _ as a separator
+        return (!val.is_empty() && !val.contains(char::is_whitespace))
+            .then(|| val.replace('_', ""));
+    };
+
+    let escaped = escaped.strip_suffix('"')?;
+    let mut unescaped = String::new();
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            'n' => unescaped.push('\n'),
+            't' => unescaped.push('\t'),
+            c => unescaped.push(c),
+        }
+    }
+
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn args() {
+        let config = Config::parse(
+            r#"
+            # applies to all tools
+            verbose = true
+
+            [mlock]
+            heap = "512M"   # a string
+            chunks = [ 'locked:1', "normal:2", ]
+            max = 1_000
+            json = false
+
+            [pgmajfault]
+            file = "/tmp/a#b"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.args("mlock"),
+            [
+                "--verbose",
+                "--heap",
+                "512M",
+                "--chunks",
+                "locked:1",
+                "--chunks",
+                "normal:2",
+                "--max",
+                "1000",
+            ]
+        );
+        assert_eq!(
+            config.args("pgmajfault"),
+            ["--verbose", "--file", "/tmp/a#b"]
+        );
+        assert_eq!(config.args("other"), ["--verbose"]);
+    }
+
+    #[test]
+    fn escapes() {
+        let config = Config::parse(r#"name = "a\"b\tc\\""#).unwrap();
+        assert_eq!(config.args(""), ["--name", "a\"b\tc\\"]);
+    }
+
+    #[test]
+    fn invalid() {
+        for text in [
+            "[mlock",
+            "heap",
+            "= 1",
+            "heap = \"512M",
+            "heap = 'open",
+            "heap = [1, 2",
+            "heap = 1 2",
+            "heap =",
+        ] {
+            assert!(Config::parse(text).is_err(), "{}", text);
+        }
+    }
+}
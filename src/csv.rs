@@ -0,0 +1,73 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crate::{JsonLine, json_unquote};
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+};
+
+// returns the value of a JSON string or number as a CSV cell
+fn csv_cell(val: &str) -> String {
+    let val = match json_unquote(val) {
+        Some(val) => val,
+        // spreadsheets treat empty cells as missing
+        None if val == "null" => String::new(),
+        None => val.to_string(),
+    };
+
+    if val.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", val.replace('"', "\"\""))
+    } else {
+        val
+    }
+}
+
+// a CSV file that samples are appended to, for the --log option of the tools
+//
+// Each JsonLine becomes a row, without its "type".  A header row is written before the first
+// row, and again whenever the keys change.
+pub struct CsvLog {
+    fp: fs::File,
+    header: Vec<String>,
+}
+
+impl CsvLog {
+    // opens or creates path for appending, picking up the last header of an existing file
+    pub fn open(path: &str) -> Result<Self, io::Error> {
+        let fp = fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let mut header = Vec::new();
+        for line in io::BufReader::new(&fp).lines() {
+            let line = line?;
+            if line.starts_with("time,") {
+                header = line.split(',').map(String::from).collect();
+            }
+        }
+
+        Ok(CsvLog { fp, header })
+    }
+
+    pub fn append(&mut self, line: &JsonLine) -> Result<(), io::Error> {
+        let (keys, vals): (Vec<&str>, Vec<&str>) =
+            line.fields().filter(|(key, _)| *key != "type").unzip();
+
+        let mut buf = String::new();
+        if self.header != keys {
+            self.header = keys.iter().map(|key| key.to_string()).collect();
+            buf.push_str(&self.header.join(","));
+            buf.push('\n');
+        }
+
+        let cells: Vec<String> = vals.into_iter().map(csv_cell).collect();
+        buf.push_str(&cells.join(","));
+        buf.push('\n');
+
+        // one write per row, so that a tool killed mid-run leaves whole rows behind
+        self.fp.write_all(buf.as_bytes())
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crate::Mmap;
+use std::{
+    fs, io, mem,
+    os::fd::{AsRawFd, OwnedFd},
+};
+
+// _IOWR('d', nr, T)
+const fn drm_iowr<T>(nr: u32) -> libc::c_ulong {
+    ((3 << 30) | ((mem::size_of::<T>() as u32) << 16) | ((b'd' as u32) << 8) | nr) as _
+}
+
+const DRM_IOCTL_MODE_CREATE_DUMB: libc::c_ulong = drm_iowr::<CreateDumb>(0xb2);
+const DRM_IOCTL_MODE_MAP_DUMB: libc::c_ulong = drm_iowr::<MapDumb>(0xb3);
+const DRM_IOCTL_MODE_DESTROY_DUMB: libc::c_ulong = drm_iowr::<DestroyDumb>(0xb4);
+
+// the structs below mirror the kernel uapi
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct CreateDumb {
+    height: u32,
+    width: u32,
+    bpp: u32,
+    flags: u32,
+    handle: u32,
+    pitch: u32,
+    size: u64,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct MapDumb {
+    handle: u32,
+    pad: u32,
+    offset: u64,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct DestroyDumb {
+    handle: u32,
+}
+
+// a DRM device node, such as /dev/dri/card0
+pub struct Drm {
+    fd: OwnedFd,
+}
+
+impl Drm {
+    pub fn open(path: &str) -> Result<Self, io::Error> {
+        let fp = fs::OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Drm { fd: fp.into() })
+    }
+
+    pub fn fd(&self) -> i32 {
+        self.fd.as_raw_fd()
+    }
+
+    fn ioctl<T>(&self, req: libc::c_ulong, arg: &mut T) -> Result<(), io::Error> {
+        // SAFETY: arg is the struct that req expects
+        let ret = unsafe { libc::ioctl(self.fd.as_raw_fd(), req, arg as *mut T) };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    // allocates a dumb buffer, which is a GEM object that KMS drivers can scan out, and maps it
+    pub fn create_dumb(
+        &self,
+        width: u32,
+        height: u32,
+        bpp: u32,
+    ) -> Result<DumbBuffer<'_>, io::Error> {
+        let mut create = CreateDumb {
+            height,
+            width,
+            bpp,
+            ..Default::default()
+        };
+        self.ioctl(DRM_IOCTL_MODE_CREATE_DUMB, &mut create)?;
+
+        // dropping the buffer on errors destroys the GEM object
+        let mut buf = DumbBuffer {
+            drm: self,
+            handle: create.handle,
+            mmap: None,
+        };
+
+        let mut map = MapDumb {
+            handle: create.handle,
+            ..Default::default()
+        };
+        self.ioctl(DRM_IOCTL_MODE_MAP_DUMB, &mut map)?;
+
+        buf.mmap = Some(Mmap::mmap_raw_at(
+            create.size as _,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            self.fd.as_raw_fd(),
+            map.offset as _,
+        )?);
+
+        Ok(buf)
+    }
+}
+
+pub struct DumbBuffer<'a> {
+    drm: &'a Drm,
+    handle: u32,
+    mmap: Option<Mmap>,
+}
+
+impl DumbBuffer<'_> {
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    pub fn mmap(&mut self) -> &mut Mmap {
+        self.mmap
+            .as_mut()
+            .expect("dumb buffers are mapped on creation")
+    }
+}
+
+impl Drop for DumbBuffer<'_> {
+    fn drop(&mut self) {
+        // the mapping holds a reference to the GEM object
+        self.mmap = None;
+
+        let mut destroy = DestroyDumb {
+            handle: self.handle,
+        };
+        let _ = self.drm.ioctl(DRM_IOCTL_MODE_DESTROY_DUMB, &mut destroy);
+    }
+}
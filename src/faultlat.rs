@@ -0,0 +1,364 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crate::Histogram;
+use std::{
+    ffi, io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+// bpf commands, map types, and program types, not in libc yet
+const BPF_MAP_CREATE: libc::c_int = 0;
+const BPF_MAP_LOOKUP_ELEM: libc::c_int = 1;
+const BPF_PROG_LOAD: libc::c_int = 5;
+const BPF_MAP_TYPE_HASH: u32 = 1;
+const BPF_MAP_TYPE_ARRAY: u32 = 2;
+const BPF_PROG_TYPE_KPROBE: u32 = 2;
+
+// helper ids
+const BPF_FUNC_MAP_LOOKUP_ELEM: i32 = 1;
+const BPF_FUNC_MAP_UPDATE_ELEM: i32 = 2;
+const BPF_FUNC_MAP_DELETE_ELEM: i32 = 3;
+const BPF_FUNC_KTIME_GET_NS: i32 = 5;
+const BPF_FUNC_GET_CURRENT_PID_TGID: i32 = 14;
+
+// faults in flight, keyed by tid
+const START_ENTRIES: u32 = 16384;
+// a bucket per Histogram bucket
+const HIST_ENTRIES: u32 = u64::BITS;
+
+const LICENSE: &ffi::CStr = c"Dual MIT/GPL";
+
+// the function that every page fault goes through, on all architectures
+const FAULT_FUNC: &str = "handle_mm_fault";
+
+// struct bpf_insn
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Insn {
+    code: u8,
+    // dst in the low nibble and src in the high nibble
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+// the instructions that the programs use
+impl Insn {
+    const fn new(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> Self {
+        Insn {
+            code,
+            regs: dst | (src << 4),
+            off,
+            imm,
+        }
+    }
+
+    // dst = src
+    const fn mov(dst: u8, src: u8) -> Self {
+        Self::new(0xbf, dst, src, 0, 0)
+    }
+
+    // dst = imm
+    const fn mov_imm(dst: u8, imm: i32) -> Self {
+        Self::new(0xb7, dst, 0, 0, imm)
+    }
+
+    // dst += imm
+    const fn add_imm(dst: u8, imm: i32) -> Self {
+        Self::new(0x07, dst, 0, 0, imm)
+    }
+
+    // dst -= src
+    const fn sub(dst: u8, src: u8) -> Self {
+        Self::new(0x1f, dst, src, 0, 0)
+    }
+
+    // dst >>= imm
+    const fn rsh_imm(dst: u8, imm: i32) -> Self {
+        Self::new(0x77, dst, 0, 0, imm)
+    }
+
+    // dst = *(u64 *)(src + off)
+    const fn load64(dst: u8, src: u8, off: i16) -> Self {
+        Self::new(0x79, dst, src, off, 0)
+    }
+
+    // *(u32 *)(dst + off) = src
+    const fn store32(dst: u8, off: i16, src: u8) -> Self {
+        Self::new(0x63, dst, src, off, 0)
+    }
+
+    // *(u64 *)(dst + off) = src
+    const fn store64(dst: u8, off: i16, src: u8) -> Self {
+        Self::new(0x7b, dst, src, off, 0)
+    }
+
+    // lock *(u64 *)(dst + off) += src
+    const fn atomic_add64(dst: u8, off: i16, src: u8) -> Self {
+        Self::new(0xdb, dst, src, off, 0)
+    }
+
+    // if dst == imm, skip off instructions
+    const fn jeq_imm(dst: u8, imm: i32, off: i16) -> Self {
+        Self::new(0x15, dst, 0, off, imm)
+    }
+
+    // if dst != imm, skip off instructions
+    const fn jne_imm(dst: u8, imm: i32, off: i16) -> Self {
+        Self::new(0x55, dst, 0, off, imm)
+    }
+
+    const fn call(func: i32) -> Self {
+        Self::new(0x85, 0, 0, 0, func)
+    }
+
+    const fn exit() -> Self {
+        Self::new(0x95, 0, 0, 0, 0)
+    }
+
+    // dst = the map of fd, which takes two instructions
+    fn load_map(dst: u8, fd: &OwnedFd) -> [Self; 2] {
+        // BPF_PSEUDO_MAP_FD
+        [
+            Self::new(0x18, dst, 1, 0, fd.as_raw_fd()),
+            Self::new(0, 0, 0, 0, 0),
+        ]
+    }
+}
+
+// registers: r0 for return values, r1 to r5 for arguments and clobbered by calls, r6 to r9
+// preserved across calls, and r10 for the read-only frame pointer
+const R0: u8 = 0;
+const R1: u8 = 1;
+const R2: u8 = 2;
+const R3: u8 = 3;
+const R4: u8 = 4;
+const R6: u8 = 6;
+const R7: u8 = 7;
+const R8: u8 = 8;
+const FP: u8 = 10;
+
+fn bpf(cmd: libc::c_int, attr: &[u64; 16]) -> Result<OwnedFd, io::Error> {
+    // SAFETY: attr is a zero-padded union bpf_attr
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            cmd,
+            attr.as_ptr(),
+            size_of_val(attr) as libc::c_uint,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: the command returned an fd that we now own
+    Ok(unsafe { OwnedFd::from_raw_fd(ret as _) })
+}
+
+// packs two u32 fields of bpf_attr into a u64
+fn pack(lo: u32, hi: u32) -> u64 {
+    if cfg!(target_endian = "little") {
+        lo as u64 | ((hi as u64) << 32)
+    } else {
+        hi as u64 | ((lo as u64) << 32)
+    }
+}
+
+fn create_map(
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    entries: u32,
+) -> Result<OwnedFd, io::Error> {
+    let mut attr = [0u64; 16];
+    attr[0] = pack(map_type, key_size);
+    attr[1] = pack(value_size, entries);
+
+    bpf(BPF_MAP_CREATE, &attr)
+}
+
+fn load_prog(insns: &[Insn]) -> Result<OwnedFd, io::Error> {
+    let mut log = vec![0u8; 65536];
+    let mut attr = [0u64; 16];
+    attr[0] = pack(BPF_PROG_TYPE_KPROBE, insns.len() as u32);
+    attr[1] = insns.as_ptr() as u64;
+    attr[2] = LICENSE.as_ptr() as u64;
+    attr[3] = pack(1, log.len() as u32);
+    attr[4] = log.as_mut_ptr() as u64;
+
+    bpf(BPF_PROG_LOAD, &attr).map_err(|err| {
+        // the verifier explains why it rejected the program
+        let len = log.iter().position(|&b| b == 0).unwrap_or(log.len());
+        let log = String::from_utf8_lossy(&log[..len]);
+        io::Error::new(
+            err.kind(),
+            format!("failed to load bpf program: {}: {}", err, log.trim_end()),
+        )
+    })
+}
+
+// records the start time of each fault, by tid, for tgid or for all processes
+fn entry_prog(start: &OwnedFd, tgid: Option<u32>) -> Vec<Insn> {
+    let mut record = vec![
+        Insn::store32(FP, -4, R6),
+        Insn::call(BPF_FUNC_KTIME_GET_NS),
+        Insn::store64(FP, -16, R0),
+    ];
+    record.extend(Insn::load_map(R1, start));
+    record.extend([
+        Insn::mov(R2, FP),
+        Insn::add_imm(R2, -4),
+        Insn::mov(R3, FP),
+        Insn::add_imm(R3, -16),
+        // BPF_ANY
+        Insn::mov_imm(R4, 0),
+        Insn::call(BPF_FUNC_MAP_UPDATE_ELEM),
+    ]);
+
+    let mut insns = vec![Insn::call(BPF_FUNC_GET_CURRENT_PID_TGID), Insn::mov(R6, R0)];
+    if let Some(tgid) = tgid {
+        insns.extend([
+            Insn::mov(R1, R0),
+            Insn::rsh_imm(R1, 32),
+            Insn::jne_imm(R1, tgid as i32, record.len() as i16),
+        ]);
+    }
+    insns.extend(record);
+    insns.extend([Insn::mov_imm(R0, 0), Insn::exit()]);
+
+    insns
+}
+
+// adds the time since the start of the fault to its log2 bucket
+fn return_prog(start: &OwnedFd, hist: &OwnedFd) -> Vec<Insn> {
+    let mut record = vec![
+        Insn::load64(R6, R0, 0),
+        Insn::call(BPF_FUNC_KTIME_GET_NS),
+        Insn::sub(R0, R6),
+        Insn::mov(R7, R0),
+        Insn::mov_imm(R8, 0),
+    ];
+    // r8 = ilog2(r7), or 0 when r7 is 0
+    for shift in [32, 16, 8, 4, 2, 1] {
+        record.extend([
+            Insn::mov(R1, R7),
+            Insn::rsh_imm(R1, shift),
+            Insn::jeq_imm(R1, 0, 2),
+            Insn::mov(R7, R1),
+            Insn::add_imm(R8, shift),
+        ]);
+    }
+    record.push(Insn::store32(FP, -8, R8));
+    record.extend(Insn::load_map(R1, hist));
+    record.extend([
+        Insn::mov(R2, FP),
+        Insn::add_imm(R2, -8),
+        Insn::call(BPF_FUNC_MAP_LOOKUP_ELEM),
+        Insn::jeq_imm(R0, 0, 2),
+        Insn::mov_imm(R1, 1),
+        Insn::atomic_add64(R0, 0, R1),
+    ]);
+    record.extend(Insn::load_map(R1, start));
+    record.extend([
+        Insn::mov(R2, FP),
+        Insn::add_imm(R2, -4),
+        Insn::call(BPF_FUNC_MAP_DELETE_ELEM),
+    ]);
+
+    let mut insns = vec![
+        Insn::call(BPF_FUNC_GET_CURRENT_PID_TGID),
+        Insn::store32(FP, -4, R0),
+    ];
+    insns.extend(Insn::load_map(R1, start));
+    insns.extend([
+        Insn::mov(R2, FP),
+        Insn::add_imm(R2, -4),
+        Insn::call(BPF_FUNC_MAP_LOOKUP_ELEM),
+        // the fault started before the probes or is of another process
+        Insn::jeq_imm(R0, 0, record.len() as i16),
+    ]);
+    insns.extend(record);
+    insns.extend([Insn::mov_imm(R0, 0), Insn::exit()]);
+
+    insns
+}
+
+// per-fault handling latency, measured in the kernel by eBPF programs on the entry and the
+// return of handle_mm_fault
+//
+// This needs CAP_BPF and CAP_PERFMON, or root, and a kernel with kprobes.  A fault that is
+// retried, such as a major fault that dropped mmap_lock to wait for io, counts once per try.
+pub struct FaultLatency {
+    hist: OwnedFd,
+    // buckets at the last take
+    prev: Vec<u64>,
+    // the programs stay attached while the perf events are open
+    _probes: Vec<OwnedFd>,
+}
+
+impl FaultLatency {
+    // starts measuring the faults of process tgid, or of all processes
+    pub fn open(tgid: Option<u32>) -> Result<Self, io::Error> {
+        let start = create_map(BPF_MAP_TYPE_HASH, 4, 8, START_ENTRIES)?;
+        let hist = create_map(BPF_MAP_TYPE_ARRAY, 4, 8, HIST_ENTRIES)?;
+
+        let entry = load_prog(&entry_prog(&start, tgid))?;
+        let ret = load_prog(&return_prog(&start, &hist))?;
+
+        // the return probe goes first, so that no fault is recorded without its return
+        let mut probes = Vec::new();
+        for (prog, retprobe) in [(ret, true), (entry, false)] {
+            let probe = crate::perf::open_kprobe(FAULT_FUNC, retprobe)?;
+            crate::perf::attach_bpf(&probe, &prog)?;
+            probes.push(probe);
+        }
+
+        Ok(FaultLatency {
+            hist,
+            prev: vec![0; HIST_ENTRIES as usize],
+            _probes: probes,
+        })
+    }
+
+    fn buckets(&self) -> Result<Vec<u64>, io::Error> {
+        (0..HIST_ENTRIES)
+            .map(|idx| {
+                let mut val = 0u64;
+                let mut attr = [0u64; 16];
+                attr[0] = self.hist.as_raw_fd() as u64;
+                attr[1] = &idx as *const u32 as u64;
+                attr[2] = &mut val as *mut u64 as u64;
+
+                // SAFETY: attr points to a key and a value of the sizes of the map
+                let ret = unsafe {
+                    libc::syscall(
+                        libc::SYS_bpf,
+                        BPF_MAP_LOOKUP_ELEM,
+                        attr.as_ptr(),
+                        size_of_val(&attr) as libc::c_uint,
+                    )
+                };
+                if ret < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+
+                Ok(val)
+            })
+            .collect()
+    }
+
+    // returns the latencies of the faults that returned since the last take
+    pub fn take(&mut self) -> Result<Histogram, io::Error> {
+        let buckets = self.buckets()?;
+        let delta = buckets
+            .iter()
+            .zip(&self.prev)
+            .map(|(cur, prev)| cur.saturating_sub(*prev))
+            .collect();
+        self.prev = buckets;
+
+        Ok(Histogram::from_buckets(delta))
+    }
+}
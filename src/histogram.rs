@@ -0,0 +1,107 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{fmt, iter, time};
+
+// log2 histogram of latencies, where bucket i counts [2^i, 2^(i+1)) ns
+#[derive(Clone)]
+pub struct Histogram {
+    buckets: Vec<u64>,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Histogram {
+            buckets: vec![0; u64::BITS as usize],
+        }
+    }
+
+    // a histogram of buckets counted elsewhere, such as by a bpf program
+    #[cfg(feature = "bpf")]
+    pub(crate) fn from_buckets(mut buckets: Vec<u64>) -> Self {
+        buckets.resize(u64::BITS as usize, 0);
+        Histogram { buckets }
+    }
+
+    pub fn record(&mut self, dur: time::Duration) {
+        let ns = (dur.as_nanos() as u64).max(1);
+        self.buckets[ns.ilog2() as usize] += 1;
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        for (bucket, count) in iter::zip(&mut self.buckets, &other.buckets) {
+            *bucket += count;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    // returns the upper bound of the bucket holding the percentile, in ns
+    pub fn percentile(&self, pct: u64) -> u64 {
+        let target = self.count() * pct / 100;
+        let mut sum = 0;
+        for (idx, count) in self.buckets.iter().enumerate() {
+            sum += count;
+            if sum > target {
+                return Self::bound(idx);
+            }
+        }
+
+        u64::MAX
+    }
+
+    fn bound(idx: usize) -> u64 {
+        1u64.checked_shl(idx as u32 + 1).unwrap_or(u64::MAX)
+    }
+
+    pub fn format_ns(ns: u64) -> String {
+        if ns < 1000 {
+            format!("{}ns", ns)
+        } else if ns < 1000 * 1000 {
+            format!("{:.1}us", ns as f64 / 1000.0)
+        } else {
+            format!("{:.1}ms", ns as f64 / 1000.0 / 1000.0)
+        }
+    }
+
+    pub fn print(&self) {
+        let count = self.count();
+        let Some(first) = self.buckets.iter().position(|&c| c > 0) else {
+            return;
+        };
+        let last = self.buckets.iter().rposition(|&c| c > 0).unwrap_or(first);
+
+        for idx in first..=last {
+            let pct = self.buckets[idx] as f64 * 100.0 / count as f64;
+            let bar = "#".repeat((pct / 2.0).round() as usize);
+            println!(
+                "  < {:>8}: {:>8} {:5.1}% {}",
+                Self::format_ns(Self::bound(idx)),
+                self.buckets[idx],
+                pct,
+                bar
+            );
+        }
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "p50 < {}, p95 < {}, p99 < {} over {} samples",
+            Self::format_ns(self.percentile(50)),
+            Self::format_ns(self.percentile(95)),
+            Self::format_ns(self.percentile(99)),
+            self.count()
+        )
+    }
+}
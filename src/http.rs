@@ -0,0 +1,101 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    io::{self, Read, Write},
+    net, time,
+};
+
+// how long a client may take to send its whole request, as the main loop waits for it
+const REQUEST_TIMEOUT: time::Duration = time::Duration::from_millis(100);
+const MAX_REQUEST_SIZE: usize = 8192;
+
+// a minimal HTTP server that the main loop of a tool polls between samples
+//
+// Only GET is supported and each connection serves a single request.
+pub struct HttpServer {
+    listener: net::TcpListener,
+}
+
+impl HttpServer {
+    // addr is in the form of "HOST:PORT" or ":PORT" for localhost
+    //
+    // There is no authentication, so listening on other interfaces takes an explicit HOST such
+    // as 0.0.0.0.
+    pub fn bind(addr: &str) -> Result<Self, io::Error> {
+        let addr = match addr.strip_prefix(':') {
+            Some(port) => format!("127.0.0.1:{}", port),
+            None => addr.to_string(),
+        };
+
+        let listener = net::TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(HttpServer { listener })
+    }
+
+    pub fn local_addr(&self) -> Result<net::SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
+
+    // serves the pending connections without blocking
+    //
+    // handler maps a path to a content type and a body, or returns None for 404.
+    pub fn poll(&self, mut handler: impl FnMut(&str) -> Option<(&'static str, String)>) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            // a misbehaving client only loses its own response
+            let _ = Self::serve(stream, &mut handler);
+        }
+    }
+
+    fn serve(
+        mut stream: net::TcpStream,
+        handler: &mut impl FnMut(&str) -> Option<(&'static str, String)>,
+    ) -> Result<(), io::Error> {
+        stream.set_nonblocking(false)?;
+        stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+        // the deadline is for the whole request, so that a client that trickles bytes cannot
+        // stall the main loop
+        let deadline = time::Instant::now() + REQUEST_TIMEOUT;
+        let mut req = Vec::new();
+        let mut buf = [0; 1024];
+        while !req.windows(4).any(|w| w == b"\r\n\r\n") && req.len() < MAX_REQUEST_SIZE {
+            let timeout = deadline.saturating_duration_since(time::Instant::now());
+            if timeout.is_zero() {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            stream.set_read_timeout(Some(timeout))?;
+
+            let len = stream.read(&mut buf)?;
+            if len == 0 {
+                break;
+            }
+            req.extend_from_slice(&buf[..len]);
+        }
+
+        // the request line is in the form of "GET /path HTTP/1.1"
+        let req = String::from_utf8_lossy(&req);
+        let mut tokens = req.lines().next().unwrap_or_default().split(' ');
+        let method = tokens.next().unwrap_or_default();
+        let path = tokens.next().unwrap_or_default();
+
+        let (status, content_type, body) = if method != "GET" {
+            ("405 Method Not Allowed", "text/plain", String::new())
+        } else {
+            match handler(path) {
+                Some((content_type, body)) => ("200 OK", content_type, body),
+                None => ("404 Not Found", "text/plain", String::new()),
+            }
+        };
+
+        let resp = format!(
+            "HTTP/1.0 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        );
+        stream.write_all(resp.as_bytes())
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    io::{self, Write},
+    os::unix::net::UnixDatagram,
+    process,
+};
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+// the system log, for the events that device-lab log collection should capture
+//
+// Entries go to journald with structured fields when it is running, and to syslog otherwise,
+// where the fields are appended to the message as KEY=VALUE.
+pub struct Journal {
+    sock: UnixDatagram,
+    native: bool,
+    ident: String,
+}
+
+impl Journal {
+    pub fn open(ident: &str) -> Result<Self, io::Error> {
+        let sock = UnixDatagram::unbound()?;
+        let native = match sock.connect(JOURNAL_SOCKET) {
+            Ok(()) => true,
+            Err(_) => {
+                sock.connect(SYSLOG_SOCKET)?;
+                false
+            }
+        };
+
+        Ok(Journal {
+            sock,
+            native,
+            ident: ident.to_string(),
+        })
+    }
+
+    // priority is a syslog level such as libc::LOG_WARNING; keys are upper-case
+    pub fn send(&self, priority: i32, msg: &str, fields: &[(&str, &str)]) -> Result<(), io::Error> {
+        let buf = if self.native {
+            let mut buf = Vec::new();
+            let priority = priority.to_string();
+            let std_fields = [
+                ("MESSAGE", msg),
+                ("PRIORITY", &priority),
+                ("SYSLOG_IDENTIFIER", &self.ident),
+            ];
+            for (key, val) in std_fields.iter().chain(fields) {
+                // values with newlines use the binary form, which is prefixed by the length
+                if val.contains('\n') {
+                    buf.extend_from_slice(key.as_bytes());
+                    buf.push(b'\n');
+                    buf.extend_from_slice(&(val.len() as u64).to_le_bytes());
+                    buf.extend_from_slice(val.as_bytes());
+                    buf.push(b'\n');
+                } else {
+                    writeln!(buf, "{}={}", key, val)?;
+                }
+            }
+            buf
+        } else {
+            let mut buf = format!(
+                "<{}>{}[{}]: {}",
+                libc::LOG_USER | priority,
+                self.ident,
+                process::id(),
+                msg
+            );
+            for (key, val) in fields {
+                buf.push_str(&format!(" {}={}", key, val));
+            }
+            buf.into_bytes()
+        };
+
+        self.sock.send(&buf)?;
+        Ok(())
+    }
+}
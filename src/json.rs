@@ -0,0 +1,303 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crate::Histogram;
+use std::{
+    fmt::{self, Write},
+    time,
+};
+
+// a value that can be written as JSON
+pub trait JsonValue {
+    fn write_json(&self, buf: &mut String);
+}
+
+macro_rules! impl_json_value_int {
+    ($($ty:ty),*) => {
+        $(
+            impl JsonValue for $ty {
+                fn write_json(&self, buf: &mut String) {
+                    let _ = write!(buf, "{}", self);
+                }
+            }
+        )*
+    };
+}
+
+impl_json_value_int!(u8, u32, u64, usize, i32, i64);
+
+impl JsonValue for f64 {
+    fn write_json(&self, buf: &mut String) {
+        // JSON has no inf or nan
+        if self.is_finite() {
+            let _ = write!(buf, "{}", self);
+        } else {
+            buf.push_str("null");
+        }
+    }
+}
+
+impl JsonValue for bool {
+    fn write_json(&self, buf: &mut String) {
+        buf.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+impl JsonValue for str {
+    fn write_json(&self, buf: &mut String) {
+        buf.push('"');
+        for c in self.chars() {
+            match c {
+                '"' => buf.push_str("\\\""),
+                '\\' => buf.push_str("\\\\"),
+                '\n' => buf.push_str("\\n"),
+                '\r' => buf.push_str("\\r"),
+                '\t' => buf.push_str("\\t"),
+                c if c < ' ' => {
+                    let _ = write!(buf, "\\u{:04x}", c as u32);
+                }
+                c => buf.push(c),
+            }
+        }
+        buf.push('"');
+    }
+}
+
+impl JsonValue for String {
+    fn write_json(&self, buf: &mut String) {
+        self.as_str().write_json(buf);
+    }
+}
+
+impl<T: JsonValue + ?Sized> JsonValue for &T {
+    fn write_json(&self, buf: &mut String) {
+        (*self).write_json(buf);
+    }
+}
+
+impl<T: JsonValue> JsonValue for Option<T> {
+    fn write_json(&self, buf: &mut String) {
+        match self {
+            Some(val) => val.write_json(buf),
+            None => buf.push_str("null"),
+        }
+    }
+}
+
+impl<T: JsonValue> JsonValue for [T] {
+    fn write_json(&self, buf: &mut String) {
+        buf.push('[');
+        for (idx, val) in self.iter().enumerate() {
+            if idx > 0 {
+                buf.push(',');
+            }
+            val.write_json(buf);
+        }
+        buf.push(']');
+    }
+}
+
+impl<T: JsonValue> JsonValue for Vec<T> {
+    fn write_json(&self, buf: &mut String) {
+        self.as_slice().write_json(buf);
+    }
+}
+
+// durations are in ns
+impl JsonValue for time::Duration {
+    fn write_json(&self, buf: &mut String) {
+        (self.as_nanos() as u64).write_json(buf);
+    }
+}
+
+// histograms are summarized by their count and the percentiles of their Display, in ns; the
+// percentiles are null when the histogram is empty
+impl JsonValue for Histogram {
+    fn write_json(&self, buf: &mut String) {
+        let count = self.count();
+        let mut line = JsonLine::nested().field("count", count);
+        for pct in [50, 95, 99] {
+            line.push(
+                &format!("p{}_ns", pct),
+                (count > 0).then(|| self.percentile(pct)),
+            );
+        }
+        line.write_json(buf);
+    }
+}
+
+// returns the value of a JSON string, or None if val is not a string
+pub fn json_unquote(val: &str) -> Option<String> {
+    let escaped = val.strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::new();
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                unescaped.extend(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32));
+            }
+            Some(c) => unescaped.push(c),
+            None => (),
+        }
+    }
+
+    Some(unescaped)
+}
+
+// splits the JSON value at the start of s from the rest of s
+fn split_value(s: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    let mut in_str = false;
+    let mut escaped = false;
+    for (idx, c) in s.char_indices() {
+        if in_str {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => {
+                    in_str = false;
+                    if depth == 0 {
+                        return Some(s.split_at(idx + 1));
+                    }
+                }
+                _ => (),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_str = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(s.split_at(idx + 1));
+                }
+            }
+            ',' | '}' | ']' | ' ' | '\t' | '\r' | '\n' if depth == 0 => {
+                return (idx > 0).then(|| s.split_at(idx));
+            }
+            _ => (),
+        }
+    }
+
+    (depth == 0 && !in_str && !s.is_empty()).then_some((s, ""))
+}
+
+// a JSON object that is printed as a single line, for the --json mode of the tools
+//
+// Each object starts with "type", such as "sample" or "summary", and "time", the unix time in
+// seconds.  The fields are kept so that the object can also be logged as a CSV row.
+#[derive(Clone)]
+pub struct JsonLine {
+    // keys and their values in JSON
+    fields: Vec<(String, String)>,
+}
+
+impl JsonLine {
+    pub fn new(kind: &str) -> Self {
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        JsonLine { fields: Vec::new() }
+            .field("type", kind)
+            .field("time", now.as_secs_f64())
+    }
+
+    // returns an object without "type" and "time", to be nested in another object
+    pub fn nested() -> Self {
+        JsonLine { fields: Vec::new() }
+    }
+
+    pub fn field(mut self, key: &str, val: impl JsonValue) -> Self {
+        self.push(key, val);
+        self
+    }
+
+    // adds a field in place, for objects built in loops
+    pub fn push(&mut self, key: &str, val: impl JsonValue) {
+        let mut buf = String::new();
+        val.write_json(&mut buf);
+        self.fields.push((key.to_string(), buf));
+    }
+
+    // returns the keys and their values in JSON
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields
+            .iter()
+            .map(|(key, val)| (key.as_str(), val.as_str()))
+    }
+
+    // returns the value of key in JSON
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields().find_map(|(k, val)| (k == key).then_some(val))
+    }
+
+    pub fn emit(&self) {
+        println!("{}", self);
+    }
+
+    // parses a JSON object, such as a line printed by emit
+    //
+    // The values are kept in JSON.  Nested objects can be parsed again.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut fields = Vec::new();
+        let mut rest = s.trim().strip_prefix('{')?.trim_start();
+        if let Some(after) = rest.strip_prefix('}') {
+            return after.trim().is_empty().then_some(JsonLine { fields });
+        }
+
+        loop {
+            let (key, after) = split_value(rest)?;
+            let key = json_unquote(key)?;
+            rest = after.trim_start().strip_prefix(':')?.trim_start();
+
+            let (val, after) = split_value(rest)?;
+            fields.push((key, val.to_string()));
+            rest = after.trim_start();
+
+            match rest.strip_prefix(',') {
+                Some(after) => rest = after.trim_start(),
+                None => break,
+            }
+        }
+
+        rest.strip_prefix('}')?
+            .trim()
+            .is_empty()
+            .then_some(JsonLine { fields })
+    }
+}
+
+impl fmt::Display for JsonLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let mut buf = String::new();
+        for (idx, (key, val)) in self.fields.iter().enumerate() {
+            if idx > 0 {
+                buf.push(',');
+            }
+            key.write_json(&mut buf);
+            buf.push(':');
+            buf.push_str(val);
+        }
+
+        write!(f, "{{{}}}", buf)
+    }
+}
+
+impl JsonValue for JsonLine {
+    fn write_json(&self, buf: &mut String) {
+        let _ = write!(buf, "{}", self);
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    fs,
+    io::{self, Read, Seek},
+    os::unix::fs::OpenOptionsExt,
+};
+
+// reads kernel log records that are logged after open
+pub struct Kmsg {
+    fp: fs::File,
+}
+
+impl Kmsg {
+    pub fn open() -> Result<Self, io::Error> {
+        let mut fp = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/kmsg")?;
+        fp.seek(io::SeekFrom::End(0))?;
+
+        Ok(Kmsg { fp })
+    }
+
+    // returns the messages of new records
+    pub fn read(&mut self) -> Vec<String> {
+        let mut msgs = Vec::new();
+        let mut buf = vec![0; 8192];
+        loop {
+            // each read returns a single "prefix;message" record
+            match self.fp.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => {
+                    let rec = String::from_utf8_lossy(&buf[..len]);
+                    if let Some((_, msg)) = rec.split_once(';') {
+                        msgs.push(msg.lines().next().unwrap_or_default().to_string());
+                    }
+                }
+                // records were overwritten before we read them
+                Err(err) if err.raw_os_error() == Some(libc::EPIPE) => continue,
+                Err(_) => break,
+            }
+        }
+
+        msgs
+    }
+}
+
+// whether a kernel message is the start or the verdict of an oom report
+pub fn is_oom_msg(msg: &str) -> bool {
+    msg.contains("invoked oom-killer")
+        || msg.contains("Out of memory")
+        || msg.contains("out of memory")
+        || is_oom_kill_msg(msg)
+}
+
+// whether a kernel message names the victim of an oom kill, such as "oom-kill:..." or
+// "Out of memory: Killed process ..."
+pub fn is_oom_kill_msg(msg: &str) -> bool {
+    msg.starts_with("oom-kill:") || msg.contains("Killed process")
+}
+
+// whether a kernel message is about memory, such as an oom kill or an allocation failure
+pub fn is_memory_msg(msg: &str) -> bool {
+    is_oom_msg(msg)
+        || msg.contains("page allocation failure")
+        || msg.starts_with("oom_reaper:")
+        || msg.contains("lowmemorykiller")
+}
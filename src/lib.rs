@@ -1,12 +1,70 @@
 // Copyright 2025 Google LLC
 // SPDX-License-Identifier: MIT
 
+#[cfg(feature = "tokio")]
+mod asyncio;
+mod cgroup;
+mod cli;
+mod config;
+mod csv;
+mod drm;
+#[cfg(feature = "bpf")]
+mod faultlat;
+mod histogram;
+mod http;
+mod journal;
+mod json;
+mod kmsg;
+mod meminfo;
+mod metrics;
+mod notify;
+mod perf;
+mod psi;
+mod rlimit;
+mod rusage;
+mod sched;
+mod smaps;
+mod tracefs;
+mod uring;
+
+#[cfg(feature = "tokio")]
+pub use asyncio::{AsyncCgroupWatcher, AsyncPsiTrigger};
+pub use cgroup::{Cgroup, CgroupEvent, CgroupWatcher};
+pub use cli::{
+    parse_bytes, parse_duration, parse_kb, parse_mb, parse_nice, parse_num, unknown_option,
+};
+pub use config::Config;
+pub use csv::CsvLog;
+pub use drm::{Drm, DumbBuffer};
+#[cfg(feature = "bpf")]
+pub use faultlat::FaultLatency;
+pub use histogram::Histogram;
+pub use http::HttpServer;
+pub use journal::Journal;
+pub use json::{JsonLine, JsonValue, json_unquote};
+pub use kmsg::{Kmsg, is_memory_msg, is_oom_kill_msg, is_oom_msg};
+pub use meminfo::{DmaBufInfo, Meminfo};
+pub use metrics::Metrics;
+pub use notify::SdNotify;
+pub use perf::{PerfCounters, PerfCounts, PerfEvent};
+pub use psi::PsiTrigger;
+pub use rlimit::Rlimit;
+pub use rusage::Rusage;
+pub use sched::{
+    IoPrio, affinity, check_cpus, parse_cpu_list, pin_to_cpu, set_affinity, set_ioprio, set_nice,
+    set_priority,
+};
+pub use smaps::{SmapsUsage, Vma, smaps, smaps_rollup};
+pub use tracefs::{MM_TRACEPOINTS, TraceEvent, Tracefs};
+pub use uring::Uring;
+
 use crossterm::{cursor, event, execute, queue, terminal};
 use std::{
-    ffi, fmt, fs,
+    ffi, fmt, fs, hint,
     io::{self, Seek, Write},
-    os::fd::{AsFd, AsRawFd, RawFd},
-    ptr, slice, time,
+    ops,
+    os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd, RawFd},
+    ptr, slice, thread, time,
 };
 
 pub fn page_size() -> usize {
@@ -15,9 +73,242 @@ pub fn page_size() -> usize {
     if page_size > 0 { page_size } else { 4096 }
 }
 
+// returns the read and write ends of a new pipe
+pub fn pipe() -> Result<(fs::File, fs::File), io::Error> {
+    let mut fds = [0; 2];
+
+    // SAFETY: fds is valid for two ints
+    let ret = unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: pipe2 returned two fds that we now own
+    let [rd, wr] = fds.map(|fd| unsafe { OwnedFd::from_raw_fd(fd) });
+
+    Ok((fs::File::from(rd), fs::File::from(wr)))
+}
+
+// reads oom_score or oom_score_adj of a pid or "self"
+pub fn read_oom(pid: &str, name: &str) -> Option<i32> {
+    fs::read_to_string(format!("/proc/{}/{}", pid, name))
+        .ok()
+        .and_then(|val| val.trim().parse().ok())
+}
+
+pub fn write_oom_score_adj(pid: &str, adj: i32) -> Result<(), io::Error> {
+    fs::write(format!("/proc/{}/oom_score_adj", pid), adj.to_string())
+}
+
+// not in libc yet
+const SYS_CACHESTAT: libc::c_long = 451;
+
+// not in libc yet
+const MPOL_MF_STRICT: libc::c_uint = 1 << 0;
+const MPOL_MF_MOVE: libc::c_uint = 1 << 1;
+
+// page cache state of a file, in pages
+#[derive(Clone, Copy, Default)]
+pub struct CacheStat {
+    pub cache: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    pub evicted: u64,
+    pub recently_evicted: u64,
+}
+
+// returns the page cache state of the whole file
+pub fn cachestat(fp: &fs::File) -> Result<CacheStat, io::Error> {
+    // struct cachestat_range covering the whole file
+    let range = [0u64, 0u64];
+    let mut cstat = [0u64; 5];
+
+    // SAFETY: range and cstat match the kernel structs
+    let ret = unsafe {
+        libc::syscall(
+            SYS_CACHESTAT,
+            fp.as_raw_fd(),
+            range.as_ptr(),
+            cstat.as_mut_ptr(),
+            0,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let [cache, dirty, writeback, evicted, recently_evicted] = cstat;
+    Ok(CacheStat {
+        cache,
+        dirty,
+        writeback,
+        evicted,
+        recently_evicted,
+    })
+}
+
+pub fn fadvise(fp: &fs::File, advice: i32) -> Result<(), io::Error> {
+    // SAFETY: fp is a valid fd
+    let ret = unsafe { libc::posix_fadvise(fp.as_raw_fd(), 0, 0, advice) };
+    if ret != 0 {
+        return Err(io::Error::from_raw_os_error(ret));
+    }
+
+    Ok(())
+}
+
+// advances a xorshift64 generator; state must be non-zero
+pub fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+// shuffles items in a reproducible order
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed | 1;
+    for i in (1..items.len()).rev() {
+        let j = (xorshift(&mut state) % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+// content written to each page by Mmap::fill_with
+#[derive(Clone, Copy)]
+pub enum Fill {
+    // zero pages tagged with the value in the first byte
+    Zero,
+    // pages filled with the value
+    Byte,
+    // repeating text, tagged with the value in the first byte
+    Text,
+    // pseudo-random bytes from a seeded xorshift generator
+    Random(u64),
+    // pseudo-random bytes for the percentage of each page, and repeating text for the rest
+    Mixed(u8),
+}
+
+impl Fill {
+    const TEXT: &[u8] = b"the quick brown fox jumps over the lazy dog 0123456789\n";
+
+    pub fn parse(s: &str) -> Option<Self> {
+        let fill = match s.split_once(':') {
+            Some(("random", seed)) => Fill::Random(seed.parse().ok()?),
+            Some(("mixed", pct)) => Fill::Mixed(pct.parse().ok().filter(|&pct| pct <= 100)?),
+            Some(_) => return None,
+            None => match s {
+                "zero" => Fill::Zero,
+                "byte" => Fill::Byte,
+                "text" => Fill::Text,
+                "random" => Fill::Random(0),
+                _ => return None,
+            },
+        };
+
+        Some(fill)
+    }
+
+    // writes the content of the page at index into page
+    fn generate(&self, val: u8, index: usize, page: &mut [u8]) {
+        match self {
+            Fill::Zero => {
+                page.fill(0);
+                page[0] = val;
+            }
+            Fill::Byte => page.fill(val),
+            Fill::Text => {
+                for line in page.chunks_mut(Self::TEXT.len()) {
+                    line.copy_from_slice(&Self::TEXT[..line.len()]);
+                }
+                page[0] = val;
+            }
+            Fill::Random(seed) => {
+                let mut state = (seed ^ ((val as u64) << 56) ^ (index as u64))
+                    .wrapping_mul(0x9e3779b97f4a7c15)
+                    | 1;
+                for word in page.chunks_mut(8) {
+                    let val = xorshift(&mut state);
+                    word.copy_from_slice(&val.to_ne_bytes()[..word.len()]);
+                }
+            }
+            Fill::Mixed(pct) => {
+                Fill::Text.generate(val, index, page);
+                let len = page.len() * *pct as usize / 100;
+                Fill::Random(0).generate(val, index, &mut page[..len]);
+                page[0] = val;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Fill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Fill::Zero => f.write_str("zero"),
+            Fill::Byte => f.write_str("byte"),
+            Fill::Text => f.write_str("text"),
+            Fill::Random(seed) => write!(f, "random:{}", seed),
+            Fill::Mixed(pct) => write!(f, "mixed:{}", pct),
+        }
+    }
+}
+
+// copies src to dst with non-temporal stores where supported
+fn copy_nontemporal(dst: &mut [u8], src: &[u8]) {
+    let len = dst.len().min(src.len());
+
+    #[cfg(target_arch = "x86_64")]
+    let done = {
+        use std::arch::x86_64::_mm_stream_si64;
+
+        for (dst, src) in dst[..len].chunks_exact_mut(8).zip(src.chunks_exact(8)) {
+            let val = i64::from_ne_bytes(src.try_into().unwrap());
+            // SAFETY: dst is valid for 8 bytes; movnti has no alignment requirement
+            unsafe { _mm_stream_si64(dst.as_mut_ptr() as *mut i64, val) };
+        }
+        len / 8 * 8
+    };
+
+    #[cfg(target_arch = "aarch64")]
+    let done = {
+        for (dst, src) in dst[..len].chunks_exact_mut(16).zip(src.chunks_exact(16)) {
+            let [lo, hi] =
+                [&src[..8], &src[8..]].map(|half| u64::from_ne_bytes(half.try_into().unwrap()));
+            // SAFETY: dst is valid for 16 bytes and is only written
+            unsafe {
+                std::arch::asm!(
+                    "stnp {lo}, {hi}, [{dst}]",
+                    lo = in(reg) lo,
+                    hi = in(reg) hi,
+                    dst = in(reg) dst.as_mut_ptr(),
+                    options(nostack, preserves_flags),
+                )
+            };
+        }
+        len / 16 * 16
+    };
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let done = 0;
+
+    dst[done..len].copy_from_slice(&src[done..len]);
+}
+
+// orders non-temporal stores before the stores that follow
+fn store_fence() {
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: sse is always available on x86_64
+    unsafe {
+        std::arch::x86_64::_mm_sfence()
+    };
+}
+
 pub struct Mmap {
     addr: *mut ffi::c_void,
     len: usize,
+    // whether the mapping was created with PROT_WRITE
+    writable: bool,
 }
 
 impl Mmap {
@@ -29,18 +320,75 @@ impl Mmap {
         Self::mmap_raw(len, libc::PROT_READ, libc::MAP_SHARED, fd.as_raw_fd())
     }
 
+    pub fn create(path: &str, len: usize) -> Result<Self, io::Error> {
+        let fp = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        fp.set_len(len as u64)?;
+        let fd = fp.as_fd();
+
+        Self::mmap_raw(
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            fd.as_raw_fd(),
+        )
+    }
+
+    // a shared mapping of a new memfd, which is shmem and is inherited by forked children
+    pub fn memfd(name: &str, len: usize) -> Result<Self, io::Error> {
+        let name = ffi::CString::new(name)?;
+
+        // SAFETY: name is a valid C string
+        let fd = unsafe { libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: memfd_create returned an fd that we now own
+        let fp = fs::File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        fp.set_len(len as u64)?;
+
+        Self::mmap_raw(
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            fp.as_raw_fd(),
+        )
+    }
+
     pub fn anonymous(len: usize) -> Result<Self, io::Error> {
+        Self::anonymous_with_flags(len, 0)
+    }
+
+    pub fn anonymous_hugetlb(len: usize) -> Result<Self, io::Error> {
+        Self::anonymous_with_flags(len, libc::MAP_HUGETLB)
+    }
+
+    // flags are in addition to MAP_PRIVATE and MAP_ANONYMOUS
+    pub fn anonymous_with_flags(len: usize, flags: i32) -> Result<Self, io::Error> {
         Self::mmap_raw(
             len,
             libc::PROT_READ | libc::PROT_WRITE,
-            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
             -1,
         )
     }
 
     fn mmap_raw(len: usize, prot: i32, flags: i32, fd: RawFd) -> Result<Self, io::Error> {
+        Self::mmap_raw_at(len, prot, flags, fd, 0)
+    }
+
+    fn mmap_raw_at(
+        len: usize,
+        prot: i32,
+        flags: i32,
+        fd: RawFd,
+        offset: libc::off_t,
+    ) -> Result<Self, io::Error> {
         let addr = ptr::null_mut();
-        let offset = 0;
 
         // SAFETY: all args are valid
         let addr = unsafe { libc::mmap(addr, len, prot, flags, fd, offset) };
@@ -48,7 +396,11 @@ impl Mmap {
             return Err(io::Error::last_os_error());
         }
 
-        Ok(Mmap { addr, len })
+        Ok(Mmap {
+            addr,
+            len,
+            writable: prot & libc::PROT_WRITE != 0,
+        })
     }
 
     pub fn mlock(&self) -> Result<(), io::Error> {
@@ -66,25 +418,299 @@ impl Mmap {
         unsafe { libc::munlock(self.addr, self.len) };
     }
 
-    pub fn populate(&self) -> Result<(), io::Error> {
-        self.mlock()?;
-        self.munlock();
+    // advice such as MADV_DONTNEED and MADV_FREE changes the contents, so the mapping must not
+    // be borrowed
+    pub fn madvise(&mut self, advice: i32) -> Result<(), io::Error> {
+        self.madvise_range(0..self.len, advice)
+    }
+
+    // advises the pages of the byte range, which must start at a page boundary
+    pub fn madvise_range(
+        &mut self,
+        range: ops::Range<usize>,
+        advice: i32,
+    ) -> Result<(), io::Error> {
+        let range = &self.as_bytes()[range];
+
+        // SAFETY: range is within the mapping
+        let ret = unsafe { libc::madvise(range.as_ptr() as *mut _, range.len(), advice) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// # Safety
+    ///
+    /// While prot lacks PROT_READ or PROT_WRITE, the mapping must not be read or written
+    /// respectively, through slices borrowed from it or through methods such as as_bytes,
+    /// populate, verify, and fill.
+    pub unsafe fn mprotect(&self, prot: i32) -> Result<(), io::Error> {
+        // SAFETY: we control self
+        let ret = unsafe { libc::mprotect(self.addr, self.len, prot) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    // writes back dirty pages of a shared file mapping
+    pub fn msync(&self, flags: i32) -> Result<(), io::Error> {
+        // SAFETY: we control self
+        let ret = unsafe { libc::msync(self.addr, self.len, flags) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
 
         Ok(())
     }
 
+    // binds the mapping to a numa node; with migrate, pages already faulted in elsewhere are
+    // moved to the node
+    pub fn mbind(&self, node: usize, migrate: bool) -> Result<(), io::Error> {
+        let bits = libc::c_ulong::BITS as usize;
+        let mut mask = vec![0 as libc::c_ulong; node / bits + 1];
+        mask[node / bits] |= 1 << (node % bits);
+        let flags = if migrate {
+            MPOL_MF_MOVE | MPOL_MF_STRICT
+        } else {
+            0
+        };
+
+        // SAFETY: mask is valid for maxnode bits; the kernel ignores the last bit of maxnode
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_mbind,
+                self.addr,
+                self.len,
+                libc::MPOL_BIND,
+                mask.as_ptr(),
+                mask.len() * bits + 1,
+                flags,
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    // returns the numa node of each page, or a negative errno for pages that are not present
+    pub fn page_nodes(&self) -> Result<Vec<i32>, io::Error> {
+        let page_size = page_size();
+        let pages: Vec<*const ffi::c_void> = (0..self.len.div_ceil(page_size))
+            .map(|page| self.addr.wrapping_byte_add(page * page_size) as *const _)
+            .collect();
+        let mut status = vec![0; pages.len()];
+
+        // SAFETY: pages and status have an entry for each page; a null nodes only queries
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_move_pages,
+                0,
+                pages.len(),
+                pages.as_ptr(),
+                ptr::null::<i32>(),
+                status.as_mut_ptr(),
+                0,
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(status)
+    }
+
+    // returns the number of pages that are resident, as reported by mincore
+    pub fn resident(&self) -> Result<usize, io::Error> {
+        Ok(self.resident_pages()?.len())
+    }
+
+    // returns the indices of the pages that are resident, as reported by mincore
+    pub fn resident_pages(&self) -> Result<Vec<usize>, io::Error> {
+        let mut vec = vec![0u8; self.len.div_ceil(page_size())];
+
+        // SAFETY: vec has an entry for each page of the mapping
+        let ret = unsafe { libc::mincore(self.addr, self.len, vec.as_mut_ptr()) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(vec
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| *v & 1 != 0)
+            .map(|(page, _)| page)
+            .collect())
+    }
+
+    // faults in every page by reading it, without needing RLIMIT_MEMLOCK
+    pub fn populate(&self) {
+        self.populate_range(0..self.len);
+    }
+
+    // populate on threads, each faulting in a contiguous part of the mapping
+    pub fn populate_parallel(&self, threads: usize) {
+        let page_size = page_size();
+        let part_len = Self::part_len(self.len, threads);
+        if part_len == 0 {
+            return;
+        }
+
+        thread::scope(|scope| {
+            for part in self.as_bytes().chunks(part_len) {
+                scope.spawn(move || {
+                    for page in part.chunks(page_size) {
+                        hint::black_box(page[0]);
+                    }
+                });
+            }
+        });
+    }
+
+    // returns the length of the parts of len bytes that threads work on, in whole pages
+    fn part_len(len: usize, threads: usize) -> usize {
+        let page_size = page_size();
+        len.div_ceil(page_size).div_ceil(threads.max(1)) * page_size
+    }
+
+    // faults in the pages of the byte range
+    pub fn populate_range(&self, range: ops::Range<usize>) {
+        let page_size = page_size();
+
+        for page in self.as_bytes()[range].chunks(page_size) {
+            hint::black_box(page[0]);
+        }
+    }
+
+    // faults in the pages of the byte range in a shuffled order, to defeat readahead
+    pub fn populate_random(&self, range: ops::Range<usize>, seed: u64) {
+        let page_size = page_size();
+        let bytes = &self.as_bytes()[range];
+
+        let mut pages: Vec<usize> = (0..bytes.len().div_ceil(page_size)).collect();
+        shuffle(&mut pages, seed);
+
+        for page in pages {
+            hint::black_box(bytes[page * page_size]);
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        // SAFETY: the mapping is readable and valid for len bytes
+        unsafe { slice::from_raw_parts(self.addr as _, self.len) }
+    }
+
+    // panics when the mapping is read-only, such as one from new
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        assert!(self.writable, "the mapping is not writable");
+
+        // SAFETY: the mapping was created writable and is valid for len bytes
+        unsafe { slice::from_raw_parts_mut(self.addr as _, self.len) }
+    }
+
     pub fn fill(&mut self, val: u8) {
         let page_size = page_size();
 
-        // SAFETY: we control self
-        let bytes = unsafe { slice::from_raw_parts_mut(self.addr as _, self.len) };
-        let page_count = (bytes.len() + page_size - 1) / page_size;
+        let bytes = self.as_bytes_mut();
+        let page_count = bytes.len().div_ceil(page_size);
         for page in 0..page_count {
             bytes[page * page_size] = val;
         }
     }
+
+    // returns the number of pages that do not hold what fill wrote
+    pub fn verify(&self, val: u8) -> usize {
+        let page_size = page_size();
+
+        self.as_bytes()
+            .chunks(page_size)
+            .filter(|page| page[0] != val || page[1..].iter().any(|&b| b != 0))
+            .count()
+    }
+
+    pub fn fill_with(&mut self, fill: Fill, val: u8) {
+        Self::fill_pages(self.as_bytes_mut(), 0, fill, val, false);
+    }
+
+    // fill_with on threads, each writing a contiguous part of the mapping
+    //
+    // With nontemporal, pages are generated in a buffer and copied with stores that bypass the
+    // CPU caches, so that filling a large mapping does not evict the working set of the rest of
+    // the system.
+    pub fn fill_parallel(&mut self, fill: Fill, val: u8, threads: usize, nontemporal: bool) {
+        let part_len = Self::part_len(self.len, threads);
+        if part_len == 0 {
+            return;
+        }
+
+        let part_pages = part_len / page_size();
+        thread::scope(|scope| {
+            for (idx, part) in self.as_bytes_mut().chunks_mut(part_len).enumerate() {
+                scope.spawn(move || {
+                    Self::fill_pages(part, idx * part_pages, fill, val, nontemporal)
+                });
+            }
+        });
+    }
+
+    // writes the pages of bytes, which start at page index first
+    fn fill_pages(bytes: &mut [u8], first: usize, fill: Fill, val: u8, nontemporal: bool) {
+        let page_size = page_size();
+        let mut buf = if nontemporal {
+            vec![0; page_size]
+        } else {
+            Vec::new()
+        };
+
+        for (index, page) in bytes.chunks_mut(page_size).enumerate() {
+            match fill {
+                // a fresh mapping is already zeroed
+                Fill::Zero => page[0] = val,
+                _ if nontemporal => {
+                    let buf = &mut buf[..page.len()];
+                    fill.generate(val, first + index, buf);
+                    copy_nontemporal(page, buf);
+                }
+                _ => fill.generate(val, first + index, page),
+            }
+        }
+
+        if nontemporal {
+            store_fence();
+        }
+    }
+
+    // returns the number of pages that do not hold what fill_with wrote
+    pub fn verify_with(&self, fill: Fill, val: u8) -> usize {
+        if let Fill::Zero = fill {
+            return self.verify(val);
+        }
+
+        let page_size = page_size();
+        let mut expected = vec![0; page_size];
+        self.as_bytes()
+            .chunks(page_size)
+            .enumerate()
+            .filter(|(index, page)| {
+                let expected = &mut expected[..page.len()];
+                fill.generate(val, *index, expected);
+                *page != expected
+            })
+            .count()
+    }
 }
 
+// SAFETY: the mapping is owned and has no thread affinity
+unsafe impl Send for Mmap {}
+// SAFETY: shared references only read the mapping; the methods that change its contents take
+// a mutable reference, and mprotect is unsafe
+unsafe impl Sync for Mmap {}
+
 impl Drop for Mmap {
     fn drop(&mut self) {
         // SAFETY: all args are valid
@@ -94,12 +720,43 @@ impl Drop for Mmap {
 
 pub struct Term {
     writer: io::Stdout,
+    // in plain mode, each frame is printed as a single line every interval rather than redrawn
+    plain: Option<time::Duration>,
+    // in json mode, frames are discarded and the tool emits JSON lines instead
+    json: bool,
+    frame: String,
+    // in plain mode, when the next frame is printed; frames drawn before then are discarded
+    next_frame: time::Instant,
 }
 
 impl Term {
     pub fn new() -> Result<Self, io::Error> {
         let writer = Self::init()?;
-        Ok(Term { writer })
+        Ok(Term {
+            writer,
+            plain: None,
+            json: false,
+            frame: String::new(),
+            next_frame: time::Instant::now(),
+        })
+    }
+
+    // returns a term that prints a line per frame, for logging over serial consoles
+    pub fn new_plain(interval: time::Duration) -> Self {
+        Term {
+            writer: io::stdout(),
+            plain: Some(interval),
+            json: false,
+            frame: String::new(),
+            next_frame: time::Instant::now() + interval,
+        }
+    }
+
+    // returns a plain term that draws nothing, for tools emitting JSON lines every interval
+    pub fn new_json(interval: time::Duration) -> Self {
+        let mut term = Self::new_plain(interval);
+        term.json = true;
+        term
     }
 
     fn init() -> Result<io::Stdout, io::Error> {
@@ -113,12 +770,82 @@ impl Term {
         Ok(writer)
     }
 
+    // prints the rows of the frame as "<unix time> <row> | <row> | ...", with runs of
+    // whitespace collapsed
+    fn print_frame(&mut self) {
+        if self.json {
+            self.frame.clear();
+        }
+        if self.frame.is_empty() {
+            return;
+        }
+
+        let frame = Self::strip_escapes(&self.frame);
+        let rows: Vec<String> = frame
+            .lines()
+            .map(|row| row.split_ascii_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|row| !row.is_empty())
+            .collect();
+        self.frame.clear();
+
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let _ = writeln!(
+            self.writer,
+            "{}.{:03} {}",
+            now.as_secs(),
+            now.subsec_millis(),
+            rows.join(" | ")
+        );
+        let _ = self.writer.flush();
+    }
+
+    // removes CSI sequences such as the reverse video that highlights changes
+    fn strip_escapes(s: &str) -> String {
+        let mut stripped = String::with_capacity(s.len());
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                // skip '[', parameters, and the final byte in '@'..='~'
+                chars.next();
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            } else {
+                stripped.push(c);
+            }
+        }
+
+        stripped
+    }
+
     pub fn reset(&mut self) {
+        if self.plain.is_some() {
+            self.print_frame();
+            return;
+        }
+
         let _ = execute!(self.writer, cursor::Show);
         let _ = terminal::disable_raw_mode();
     }
 
     pub fn cmd_clear(&mut self, rows: u32) {
+        // a plain frame ends when the next one is about to be drawn, and is printed only at the
+        // end of an interval
+        if let Some(interval) = self.plain {
+            let now = time::Instant::now();
+            if now >= self.next_frame {
+                self.print_frame();
+                self.next_frame = (self.next_frame + interval).max(now);
+            } else {
+                self.frame.clear();
+            }
+            return;
+        }
+
         let _ = queue!(
             self.writer,
             cursor::MoveToColumn(0),
@@ -128,10 +855,20 @@ impl Term {
     }
 
     pub fn cmd_fmt(&mut self, args: fmt::Arguments) {
+        if self.plain.is_some() {
+            let _ = fmt::Write::write_fmt(&mut self.frame, args);
+            return;
+        }
+
         let _ = self.writer.write_fmt(args);
     }
 
     pub fn cmd_str(&mut self, s: &str) {
+        if self.plain.is_some() {
+            self.frame.push_str(s);
+            return;
+        }
+
         let _ = self.writer.write_all(s.as_bytes());
     }
 
@@ -139,7 +876,34 @@ impl Term {
         let _ = self.writer.flush();
     }
 
+    // returns how long until the next plain frame is due
+    fn until_next_frame(&self) -> time::Duration {
+        self.next_frame
+            .saturating_duration_since(time::Instant::now())
+    }
+
+    // in plain mode, waits for the next frame and never returns keys
     pub fn poll(&mut self, timeout_ms: i32) -> Result<Option<event::KeyEvent>, io::Error> {
+        if self.plain.is_some() {
+            thread::sleep(self.until_next_frame());
+            return Ok(None);
+        }
+
+        self.poll_within(timeout_ms)
+    }
+
+    // like poll, but in plain mode waits no longer than timeout_ms either, for callers with
+    // work due before the next frame such as scripted actions
+    pub fn poll_within(&mut self, timeout_ms: i32) -> Result<Option<event::KeyEvent>, io::Error> {
+        if self.plain.is_some() {
+            let mut dur = self.until_next_frame();
+            if timeout_ms >= 0 {
+                dur = dur.min(time::Duration::from_millis(timeout_ms as _));
+            }
+            thread::sleep(dur);
+            return Ok(None);
+        }
+
         let dur = if timeout_ms >= 0 {
             time::Duration::from_millis(timeout_ms as _)
         } else {
@@ -0,0 +1,150 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{fs, io};
+
+// commonly used /proc/meminfo fields, in KB
+#[derive(Clone, Copy, Default)]
+pub struct Meminfo {
+    pub mem_total: u64,
+    pub mem_free: u64,
+    pub mem_available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    pub swap_cached: u64,
+    pub active: u64,
+    pub inactive: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    pub zswap: u64,
+    pub zswapped: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    pub anon_pages: u64,
+    pub mapped: u64,
+    pub shmem: u64,
+    pub slab: u64,
+    pub s_reclaimable: u64,
+    pub k_reclaimable: u64,
+    pub mlocked: u64,
+    pub committed_as: u64,
+    // ION totals that some Android vendor kernels add
+    pub ion_heap: u64,
+    pub ion_heap_pool: u64,
+}
+
+impl Meminfo {
+    pub fn collect() -> Result<Self, io::Error> {
+        Ok(Self::parse(&fs::read_to_string("/proc/meminfo")?))
+    }
+
+    // parses text in the meminfo format, including per-node meminfo where keys are prefixed by
+    // "Node N "
+    pub fn parse(text: &str) -> Self {
+        let mut info = Meminfo::default();
+        for line in text.lines() {
+            let Some((key, val)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.rsplit(' ').next().unwrap_or(key);
+            let val = val
+                .split_ascii_whitespace()
+                .next()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default();
+
+            let field = match key {
+                "MemTotal" => &mut info.mem_total,
+                "MemFree" => &mut info.mem_free,
+                "MemAvailable" => &mut info.mem_available,
+                "Buffers" => &mut info.buffers,
+                "Cached" => &mut info.cached,
+                "SwapCached" => &mut info.swap_cached,
+                "Active" => &mut info.active,
+                "Inactive" => &mut info.inactive,
+                "SwapTotal" => &mut info.swap_total,
+                "SwapFree" => &mut info.swap_free,
+                "Zswap" => &mut info.zswap,
+                "Zswapped" => &mut info.zswapped,
+                "Dirty" => &mut info.dirty,
+                "Writeback" => &mut info.writeback,
+                "AnonPages" => &mut info.anon_pages,
+                "Mapped" => &mut info.mapped,
+                "Shmem" => &mut info.shmem,
+                "Slab" => &mut info.slab,
+                "SReclaimable" => &mut info.s_reclaimable,
+                "KReclaimable" => &mut info.k_reclaimable,
+                "Mlocked" => &mut info.mlocked,
+                "Committed_AS" => &mut info.committed_as,
+                "ION_heap" => &mut info.ion_heap,
+                "ION_heap_pool" => &mut info.ion_heap_pool,
+                _ => continue,
+            };
+            *field = val;
+        }
+
+        info
+    }
+
+    // memory that free(1) reports as buff/cache
+    pub fn buff_cache(&self) -> u64 {
+        self.buffers + self.cached + self.s_reclaimable
+    }
+
+    // reclaimable kernel memory other than slab, such as the DMA-BUF heap pools on Android
+    pub fn k_reclaimable_other(&self) -> u64 {
+        self.k_reclaimable.saturating_sub(self.s_reclaimable)
+    }
+
+    // memory that free(1) reports as used, which is MemTotal minus MemAvailable since procps-ng 4
+    pub fn used(&self) -> u64 {
+        self.mem_total.saturating_sub(self.mem_available)
+    }
+}
+
+// DMA-BUF and ION totals from the sysfs files of Android kernels, in KB
+//
+// Each total is None when the kernel does not report it.
+#[derive(Clone, Copy, Default)]
+pub struct DmaBufInfo {
+    // buffers allocated from the heaps
+    pub heaps: Option<u64>,
+    // free pages cached by the heap pools, which are counted in KReclaimable
+    pub pools: Option<u64>,
+}
+
+impl DmaBufInfo {
+    // info provides the fallback for kernels that report ION in meminfo only
+    pub fn collect(info: &Meminfo) -> Self {
+        let read_kb =
+            |path: &str| -> Option<u64> { fs::read_to_string(path).ok()?.trim().parse().ok() };
+        let ion = (info.ion_heap > 0 || info.ion_heap_pool > 0).then_some(info);
+
+        // ION kernels report both totals; GKI kernels report the pools of DMA-BUF heaps and
+        // the buffers in the per-buffer stats
+        let heaps = read_kb("/sys/kernel/ion/total_heaps_kb")
+            .or_else(Self::sum_buffers)
+            .or(ion.map(|info| info.ion_heap));
+        let pools = read_kb("/sys/kernel/ion/total_pools_kb")
+            .or_else(|| read_kb("/sys/kernel/dma_heap/total_pools_kb"))
+            .or(ion.map(|info| info.ion_heap_pool));
+
+        DmaBufInfo { heaps, pools }
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.heaps.is_some() || self.pools.is_some()
+    }
+
+    // sums /sys/kernel/dmabuf/buffers/*/size, which are in bytes
+    fn sum_buffers() -> Option<u64> {
+        let entries = fs::read_dir("/sys/kernel/dmabuf/buffers").ok()?;
+        let bytes: u64 = entries
+            .flatten()
+            .filter_map(|entry| fs::read_to_string(entry.path().join("size")).ok())
+            .filter_map(|size| size.trim().parse::<u64>().ok())
+            .sum();
+
+        Some(bytes / 1024)
+    }
+}
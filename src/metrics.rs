@@ -0,0 +1,85 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crate::{JsonLine, JsonValue};
+use std::fmt::Write;
+
+// metrics in the Prometheus text format, such as for the /metrics page of mlock --http
+//
+// All metrics are gauges.  Samples of the same name are grouped under a single TYPE line.
+pub struct Metrics {
+    prefix: String,
+    // names and their samples, which are label sets and values
+    metrics: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Metrics {
+    pub fn new(prefix: &str) -> Self {
+        Metrics {
+            prefix: prefix.to_string(),
+            metrics: Vec::new(),
+        }
+    }
+
+    // adds a sample; values that are not numbers, such as null, are skipped
+    pub fn gauge(&mut self, name: &str, labels: &[(&str, &str)], val: impl JsonValue) {
+        let mut buf = String::new();
+        val.write_json(&mut buf);
+        if buf.parse::<f64>().is_err() {
+            return;
+        }
+
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, val)| {
+                let mut quoted = String::new();
+                val.write_json(&mut quoted);
+                format!("{}={}", key, quoted)
+            })
+            .collect();
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", labels.join(","))
+        };
+
+        let name = format!("{}_{}", self.prefix, name);
+        match self.metrics.iter_mut().find(|(key, _)| *key == name) {
+            Some((_, samples)) => samples.push((labels, buf)),
+            None => self.metrics.push((name, vec![(labels, buf)])),
+        }
+    }
+
+    // adds the numeric fields of line, converted to base units as Prometheus prefers
+    pub fn push_line(&mut self, line: &JsonLine) {
+        for (key, val) in line.fields() {
+            if key == "type" || key == "time" {
+                continue;
+            }
+
+            if let Some(name) = key.strip_suffix("_kb") {
+                if let Ok(kb) = val.parse::<u64>() {
+                    self.gauge(&format!("{}_bytes", name), &[], kb * 1024);
+                }
+            } else if let Some(name) = key.strip_suffix("_ns") {
+                if let Ok(ns) = val.parse::<u64>() {
+                    self.gauge(&format!("{}_seconds", name), &[], ns as f64 / 1e9);
+                }
+            } else if let Ok(val) = val.parse::<f64>() {
+                self.gauge(key, &[], val);
+            }
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut buf = String::new();
+        for (name, samples) in &self.metrics {
+            let _ = writeln!(buf, "# TYPE {} gauge", name);
+            for (labels, val) in samples {
+                let _ = writeln!(buf, "{}{} {}", name, labels, val);
+            }
+        }
+
+        buf
+    }
+}
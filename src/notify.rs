@@ -0,0 +1,83 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    env, io,
+    os::{
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram},
+    },
+    process, time,
+};
+
+// the service manager notification socket, for tools run as systemd services
+//
+// See sd_notify(3).  Notifications are best-effort and their errors are ignored.
+pub struct SdNotify {
+    sock: UnixDatagram,
+    addr: SocketAddr,
+    // half of WATCHDOG_USEC, as systemd recommends
+    watchdog: Option<time::Duration>,
+    watchdog_prev: Option<time::Instant>,
+}
+
+impl SdNotify {
+    // returns None when NOTIFY_SOCKET is unset, such as when not run by systemd
+    pub fn from_env() -> Option<Self> {
+        let path = env::var("NOTIFY_SOCKET").ok()?;
+        let addr = match path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name),
+            None => SocketAddr::from_pathname(&path),
+        }
+        .ok()?;
+        let sock = UnixDatagram::unbound().ok()?;
+
+        // WATCHDOG_PID is set when the watchdog is meant for another process
+        let watchdog = env::var("WATCHDOG_PID")
+            .ok()
+            .is_none_or(|pid| pid.parse() == Ok(process::id()))
+            .then(|| env::var("WATCHDOG_USEC").ok())
+            .flatten()
+            .and_then(|usec| usec.parse().ok())
+            .filter(|usec| *usec > 0)
+            .map(|usec| time::Duration::from_micros(usec) / 2);
+
+        Some(SdNotify {
+            sock,
+            addr,
+            watchdog,
+            watchdog_prev: None,
+        })
+    }
+
+    // sends newline-separated assignments such as "READY=1"
+    pub fn notify(&self, state: &str) -> Result<(), io::Error> {
+        self.sock.send_to_addr(state.as_bytes(), &self.addr)?;
+        Ok(())
+    }
+
+    pub fn ready(&self, status: &str) {
+        let _ = self.notify(&format!("READY=1\nSTATUS={}", status));
+    }
+
+    pub fn status(&self, status: &str) {
+        let _ = self.notify(&format!("STATUS={}", status));
+    }
+
+    pub fn stopping(&self) {
+        let _ = self.notify("STOPPING=1");
+    }
+
+    // pings the watchdog if it is enabled and due; call at least once per sample
+    pub fn watchdog(&mut self) {
+        let Some(interval) = self.watchdog else {
+            return;
+        };
+
+        let now = time::Instant::now();
+        if self.watchdog_prev.is_none_or(|prev| now - prev >= interval) {
+            let _ = self.notify("WATCHDOG=1");
+            self.watchdog_prev = Some(now);
+        }
+    }
+}
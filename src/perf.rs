@@ -0,0 +1,297 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crate::JsonValue;
+#[cfg(feature = "bpf")]
+use std::{ffi, os::fd::AsRawFd, path};
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    mem,
+    os::fd::{FromRawFd, OwnedFd},
+};
+
+// struct perf_event_attr up to PERF_ATTR_SIZE_VER0, not in libc yet
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    // disabled, inherit, pinned, exclusive, exclude_user, exclude_kernel, ...
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+const PERF_TYPE_SOFTWARE: u32 = 1;
+const PERF_TYPE_HW_CACHE: u32 = 3;
+const PERF_COUNT_SW_CONTEXT_SWITCHES: u64 = 3;
+const PERF_COUNT_SW_PAGE_FAULTS: u64 = 2;
+const PERF_COUNT_SW_PAGE_FAULTS_MAJ: u64 = 6;
+// PERF_COUNT_HW_CACHE_DTLB | PERF_COUNT_HW_CACHE_OP_READ << 8 |
+// PERF_COUNT_HW_CACHE_RESULT_MISS << 16
+const PERF_COUNT_HW_CACHE_DTLB_READ_MISS: u64 = 3 | (1 << 16);
+
+const PERF_FLAG_INHERIT: u64 = 1 << 1;
+const PERF_FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+const PERF_FLAG_EXCLUDE_HV: u64 = 1 << 6;
+
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+// PERF_EVENT_IOC_ENABLE and PERF_EVENT_IOC_SET_BPF
+#[cfg(feature = "bpf")]
+const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+#[cfg(feature = "bpf")]
+const PERF_EVENT_IOC_SET_BPF: libc::c_ulong = 0x40042408;
+
+fn perf_event_open(
+    attr: &PerfEventAttr,
+    pid: libc::pid_t,
+    cpu: i32,
+) -> Result<fs::File, io::Error> {
+    // SAFETY: attr is a valid perf_event_attr
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_perf_event_open,
+            attr as *const PerfEventAttr,
+            pid,
+            cpu,
+            -1,
+            PERF_FLAG_FD_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: perf_event_open returned an fd that we now own
+    Ok(fs::File::from(unsafe { OwnedFd::from_raw_fd(fd as _) }))
+}
+
+// opens a kprobe, or a kretprobe, on the kernel function through the kprobe pmu
+#[cfg(feature = "bpf")]
+pub(crate) fn open_kprobe(func: &str, retprobe: bool) -> Result<OwnedFd, io::Error> {
+    let pmu = path::Path::new("/sys/bus/event_source/devices/kprobe");
+    let kind = fs::read_to_string(pmu.join("type"))
+        .map_err(|err| io::Error::new(err.kind(), format!("no kprobe pmu: {}", err)))?;
+    let kind = kind
+        .trim()
+        .parse()
+        .map_err(|_| io::ErrorKind::InvalidData)?;
+    let mut config = 0;
+    if retprobe {
+        // "config:BIT"
+        let format = fs::read_to_string(pmu.join("format/retprobe"))?;
+        let bit: u32 = format
+            .trim()
+            .strip_prefix("config:")
+            .and_then(|bit| bit.parse().ok())
+            .ok_or(io::ErrorKind::InvalidData)?;
+        config = 1 << bit;
+    }
+    let func = ffi::CString::new(func)?;
+
+    let attr = PerfEventAttr {
+        kind,
+        size: mem::size_of::<PerfEventAttr>() as u32,
+        config,
+        // kprobe_func
+        config1: func.as_ptr() as u64,
+        ..Default::default()
+    };
+
+    // a bpf program on a kprobe runs for every task on every cpu
+    perf_event_open(&attr, -1, 0).map(OwnedFd::from)
+}
+
+// runs the bpf program whenever the probe fires
+#[cfg(feature = "bpf")]
+pub(crate) fn attach_bpf(probe: &OwnedFd, prog: &OwnedFd) -> Result<(), io::Error> {
+    for (req, arg) in [
+        (PERF_EVENT_IOC_SET_BPF, prog.as_raw_fd()),
+        (PERF_EVENT_IOC_ENABLE, 0),
+    ] {
+        // SAFETY: valid ioctl call on a perf event fd
+        let ret = unsafe { libc::ioctl(probe.as_raw_fd(), req as _, arg) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PerfEvent {
+    PageFaults,
+    MajorFaults,
+    ContextSwitches,
+    // a hardware event, which vms and some cpus lack
+    DtlbMisses,
+}
+
+impl PerfEvent {
+    pub const ALL: [PerfEvent; 4] = [
+        PerfEvent::PageFaults,
+        PerfEvent::MajorFaults,
+        PerfEvent::ContextSwitches,
+        PerfEvent::DtlbMisses,
+    ];
+
+    // the name used by perf stat
+    pub fn name(&self) -> &'static str {
+        match self {
+            PerfEvent::PageFaults => "page-faults",
+            PerfEvent::MajorFaults => "major-faults",
+            PerfEvent::ContextSwitches => "context-switches",
+            PerfEvent::DtlbMisses => "dTLB-load-misses",
+        }
+    }
+
+    // the name used as a JSON key
+    pub fn key(&self) -> &'static str {
+        match self {
+            PerfEvent::PageFaults => "page_faults",
+            PerfEvent::MajorFaults => "major_faults",
+            PerfEvent::ContextSwitches => "context_switches",
+            PerfEvent::DtlbMisses => "dtlb_load_misses",
+        }
+    }
+
+    fn is_hardware(&self) -> bool {
+        matches!(self, PerfEvent::DtlbMisses)
+    }
+
+    fn open(&self, exclude_kernel: bool) -> Result<fs::File, io::Error> {
+        let (kind, config) = match self {
+            PerfEvent::PageFaults => (PERF_TYPE_SOFTWARE, PERF_COUNT_SW_PAGE_FAULTS),
+            PerfEvent::MajorFaults => (PERF_TYPE_SOFTWARE, PERF_COUNT_SW_PAGE_FAULTS_MAJ),
+            PerfEvent::ContextSwitches => (PERF_TYPE_SOFTWARE, PERF_COUNT_SW_CONTEXT_SWITCHES),
+            PerfEvent::DtlbMisses => (PERF_TYPE_HW_CACHE, PERF_COUNT_HW_CACHE_DTLB_READ_MISS),
+        };
+        let mut flags = PERF_FLAG_INHERIT | PERF_FLAG_EXCLUDE_HV;
+        if exclude_kernel {
+            flags |= PERF_FLAG_EXCLUDE_KERNEL;
+        }
+        let attr = PerfEventAttr {
+            kind,
+            size: mem::size_of::<PerfEventAttr>() as u32,
+            config,
+            flags,
+            ..Default::default()
+        };
+
+        // the calling thread on any cpu
+        perf_event_open(&attr, 0, -1)
+    }
+}
+
+// perf event counters of the calling thread and of the threads and processes it creates
+// afterward
+//
+// Counting starts when the counters are opened.  Unlike /proc, the counts are exact and are
+// not limited to the process as a whole.
+pub struct PerfCounters {
+    counters: Vec<(PerfEvent, fs::File)>,
+}
+
+impl PerfCounters {
+    // opens the events, skipping hardware events that are not supported
+    //
+    // Events are opened for user space only when perf_event_paranoid forbids counting the
+    // kernel.  Faults are still counted then, but context switches are not.
+    pub fn open(events: &[PerfEvent]) -> Result<Self, io::Error> {
+        let mut counters = Vec::new();
+        for &event in events {
+            let fp = match event.open(false) {
+                Err(err) if err.kind() == io::ErrorKind::PermissionDenied => event.open(true),
+                res => res,
+            };
+            match fp {
+                Ok(fp) => counters.push((event, fp)),
+                Err(_) if event.is_hardware() => (),
+                Err(err) => {
+                    return Err(io::Error::new(
+                        err.kind(),
+                        format!("failed to open perf event {}: {}", event.name(), err),
+                    ));
+                }
+            }
+        }
+
+        Ok(PerfCounters { counters })
+    }
+
+    pub fn read(&self) -> PerfCounts {
+        let counts = self
+            .counters
+            .iter()
+            .map(|(event, fp)| {
+                let mut buf = [0; 8];
+                let count = match (&*fp).read_exact(&mut buf) {
+                    Ok(()) => u64::from_ne_bytes(buf),
+                    Err(_) => 0,
+                };
+                (*event, count)
+            })
+            .collect();
+
+        PerfCounts { counts }
+    }
+}
+
+// the counts of the opened events
+#[derive(Clone)]
+pub struct PerfCounts {
+    counts: Vec<(PerfEvent, u64)>,
+}
+
+impl PerfCounts {
+    pub fn get(&self, event: PerfEvent) -> Option<u64> {
+        self.counts
+            .iter()
+            .find_map(|(ev, count)| (*ev == event).then_some(*count))
+    }
+
+    // returns the counts accumulated since prev
+    pub fn delta(&self, prev: &Self) -> Self {
+        let counts = self
+            .counts
+            .iter()
+            .map(|&(event, count)| {
+                let prev = prev.get(event).unwrap_or_default();
+                (event, count.saturating_sub(prev))
+            })
+            .collect();
+
+        PerfCounts { counts }
+    }
+}
+
+impl fmt::Display for PerfCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        for (idx, (event, count)) in self.counts.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} {}", event.name(), count)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl JsonValue for PerfCounts {
+    fn write_json(&self, buf: &mut String) {
+        let mut line = crate::JsonLine::nested();
+        for (event, count) in &self.counts {
+            line.push(event.key(), *count);
+        }
+        buf.push_str(&line.to_string());
+    }
+}
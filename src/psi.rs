@@ -0,0 +1,56 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    fs,
+    io::{self, Write},
+    os::fd::{AsRawFd, RawFd},
+    path,
+};
+
+// a psi trigger, which stays registered while its fd is open
+//
+// The kernel signals POLLPRI on the fd when the trigger fires, and POLLERR when the cgroup of
+// the pressure file is removed.
+pub struct PsiTrigger {
+    pub spec: String,
+    fp: fs::File,
+}
+
+impl PsiTrigger {
+    // spec is in the form of "some:STALL_MS:WINDOW_MS" or "full:STALL_MS:WINDOW_MS"
+    pub fn register<P: AsRef<path::Path>>(path: P, spec: &str) -> Result<Self, io::Error> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid trigger {}", spec),
+            )
+        };
+
+        let mut fields = spec.split(':');
+        let kind = fields
+            .next()
+            .filter(|kind| *kind == "some" || *kind == "full")
+            .ok_or_else(invalid)?;
+        let [stall_ms, window_ms] = [fields.next(), fields.next()]
+            .map(|field| field.and_then(|field| field.parse::<u64>().ok()));
+        let (Some(stall_ms), Some(window_ms)) = (stall_ms, window_ms) else {
+            return Err(invalid());
+        };
+
+        let mut fp = fs::OpenOptions::new().read(true).write(true).open(path)?;
+        // the kernel wants the trigger in a single write, in us
+        fp.write_all(format!("{} {} {}\0", kind, stall_ms * 1000, window_ms * 1000).as_bytes())?;
+
+        Ok(PsiTrigger {
+            spec: spec.to_string(),
+            fp,
+        })
+    }
+}
+
+impl AsRawFd for PsiTrigger {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fp.as_raw_fd()
+    }
+}
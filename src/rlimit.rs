@@ -0,0 +1,159 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{fs, io};
+
+// the resource limits that get in the way of allocating or locking large amounts of memory
+#[derive(Clone, Copy)]
+pub enum Rlimit {
+    Memlock,
+    As,
+    Data,
+}
+
+impl Rlimit {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rlimit::Memlock => "RLIMIT_MEMLOCK",
+            Rlimit::As => "RLIMIT_AS",
+            Rlimit::Data => "RLIMIT_DATA",
+        }
+    }
+
+    // the ulimit option that sets the limit, in KB
+    fn ulimit(&self) -> &'static str {
+        match self {
+            Rlimit::Memlock => "ulimit -l",
+            Rlimit::As => "ulimit -v",
+            Rlimit::Data => "ulimit -d",
+        }
+    }
+
+    // the /proc/self/status key of the memory counted against the limit
+    fn status_key(&self) -> &'static str {
+        match self {
+            Rlimit::Memlock => "VmLck:",
+            Rlimit::As => "VmSize:",
+            Rlimit::Data => "VmData:",
+        }
+    }
+
+    fn get_raw(&self) -> Result<libc::rlimit, io::Error> {
+        let mut lim = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        let res = match self {
+            Rlimit::Memlock => libc::RLIMIT_MEMLOCK,
+            Rlimit::As => libc::RLIMIT_AS,
+            Rlimit::Data => libc::RLIMIT_DATA,
+        };
+
+        // SAFETY: lim is valid
+        let ret = unsafe { libc::getrlimit(res, &mut lim) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(lim)
+    }
+
+    fn set_raw(&self, lim: libc::rlimit) -> Result<(), io::Error> {
+        let res = match self {
+            Rlimit::Memlock => libc::RLIMIT_MEMLOCK,
+            Rlimit::As => libc::RLIMIT_AS,
+            Rlimit::Data => libc::RLIMIT_DATA,
+        };
+
+        // SAFETY: lim is valid
+        let ret = unsafe { libc::setrlimit(res, &lim) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    // returns the soft and hard limits in bytes, with None for unlimited
+    pub fn get(&self) -> Result<(Option<u64>, Option<u64>), io::Error> {
+        Ok(Self::from_raw(self.get_raw()?))
+    }
+
+    fn from_raw(lim: libc::rlimit) -> (Option<u64>, Option<u64>) {
+        let [cur, max] =
+            [lim.rlim_cur, lim.rlim_max].map(|val| (val != libc::RLIM_INFINITY).then_some(val));
+
+        (cur, max)
+    }
+
+    // returns the bytes of this process that count against the limit
+    pub fn usage(&self) -> u64 {
+        fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix(self.status_key()))
+                    .and_then(|val| val.split_ascii_whitespace().next())
+                    .and_then(|val| val.parse::<u64>().ok())
+            })
+            .unwrap_or_default()
+            * 1024
+    }
+
+    // raises the soft limit so that bytes more can be used, or as far as allowed when None
+    //
+    // The hard limit is raised too when needed, which requires CAP_SYS_RESOURCE.  With None,
+    // the soft limit becomes unlimited when allowed and the hard limit otherwise, and only
+    // getrlimit errors are reported.
+    pub fn reserve(&self, bytes: Option<u64>) -> Result<(), io::Error> {
+        let lim = self.get_raw()?;
+        let Some(bytes) = bytes else {
+            let unlimited = libc::rlimit {
+                rlim_cur: libc::RLIM_INFINITY,
+                rlim_max: libc::RLIM_INFINITY,
+            };
+            if self.set_raw(unlimited).is_err() {
+                let _ = self.set_raw(libc::rlimit {
+                    rlim_cur: lim.rlim_max,
+                    rlim_max: lim.rlim_max,
+                });
+            }
+            return Ok(());
+        };
+
+        let usage = self.usage();
+        let needed = usage.saturating_add(bytes);
+        let fits = |val: libc::rlim_t| val == libc::RLIM_INFINITY || val >= needed;
+        if fits(lim.rlim_cur) {
+            return Ok(());
+        }
+
+        let new_lim = libc::rlimit {
+            rlim_cur: needed,
+            rlim_max: if fits(lim.rlim_max) {
+                lim.rlim_max
+            } else {
+                needed
+            },
+        };
+        self.set_raw(new_lim).map_err(|err| {
+            let [cur, max] = <[_; 2]>::from(Self::from_raw(lim)).map(|val| {
+                val.map_or(String::from("unlimited"), |val| format!("{} KB", val / 1024))
+            });
+            io::Error::new(
+                err.kind(),
+                format!(
+                    "{} of {} (hard {}) is below the {} KB needed, {} KB in use; raise it with {} or run with CAP_SYS_RESOURCE: {}",
+                    self.name(),
+                    cur,
+                    max,
+                    needed / 1024,
+                    usage / 1024,
+                    self.ulimit(),
+                    err
+                ),
+            )
+        })
+    }
+}
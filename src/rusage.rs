@@ -0,0 +1,81 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{fmt, io, mem};
+
+// resource usage from getrusage, to cross-check what /proc reports
+#[derive(Clone, Copy, Default)]
+pub struct Rusage {
+    // peak resident set size in KB
+    pub maxrss_kb: u64,
+    // page faults without and with io
+    pub minflt: u64,
+    pub majflt: u64,
+    // voluntary and involuntary context switches
+    pub nvcsw: u64,
+    pub nivcsw: u64,
+}
+
+impl Rusage {
+    // the usage of this process, summed over its threads
+    pub fn collect() -> Result<Self, io::Error> {
+        Self::collect_who(libc::RUSAGE_SELF)
+    }
+
+    // the usage of the children that have been waited for
+    pub fn collect_children() -> Result<Self, io::Error> {
+        Self::collect_who(libc::RUSAGE_CHILDREN)
+    }
+
+    fn collect_who(who: libc::c_int) -> Result<Self, io::Error> {
+        // SAFETY: rusage is plain data
+        let mut usage: libc::rusage = unsafe { mem::zeroed() };
+
+        // SAFETY: usage is valid
+        let ret = unsafe { libc::getrusage(who, &mut usage) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let [maxrss_kb, minflt, majflt, nvcsw, nivcsw] = [
+            usage.ru_maxrss,
+            usage.ru_minflt,
+            usage.ru_majflt,
+            usage.ru_nvcsw,
+            usage.ru_nivcsw,
+        ]
+        .map(|val| val as u64);
+
+        Ok(Rusage {
+            maxrss_kb,
+            minflt,
+            majflt,
+            nvcsw,
+            nivcsw,
+        })
+    }
+
+    // returns the counters accumulated since prev, keeping the peak rss
+    pub fn delta(&self, prev: &Self) -> Self {
+        Rusage {
+            maxrss_kb: self.maxrss_kb,
+            minflt: self.minflt.saturating_sub(prev.minflt),
+            majflt: self.majflt.saturating_sub(prev.majflt),
+            nvcsw: self.nvcsw.saturating_sub(prev.nvcsw),
+            nivcsw: self.nivcsw.saturating_sub(prev.nivcsw),
+        }
+    }
+}
+
+impl fmt::Display for Rusage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "maxrss {:5} MB, minflt {:6}, majflt {:5}, nvcsw {:5}",
+            self.maxrss_kb / 1024,
+            self.minflt,
+            self.majflt,
+            self.nvcsw
+        )
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{fmt, io, mem};
+
+// parses a cpu list such as "0-3,8,10-11", in the format of taskset -c and
+// /sys/devices/system/cpu/online
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>, io::Error> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid cpu list {}", list),
+        )
+    };
+
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',') {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        let [first, last] = [first, last].map(|cpu| cpu.trim().parse::<usize>());
+        let (Ok(first), Ok(last)) = (first, last) else {
+            return Err(invalid());
+        };
+        if first > last {
+            return Err(invalid());
+        }
+
+        cpus.extend(first..=last);
+    }
+
+    Ok(cpus)
+}
+
+// fails unless the calling thread may run on all of the cpus, to catch bad cpu options before
+// threads are spawned
+pub fn check_cpus(cpus: &[usize]) -> Result<(), io::Error> {
+    let allowed = affinity()?;
+    match cpus.iter().find(|cpu| !allowed.contains(cpu)) {
+        Some(cpu) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("cpu {} is not available", cpu),
+        )),
+        None => Ok(()),
+    }
+}
+
+// restricts the calling thread to the cpus
+pub fn set_affinity(cpus: &[usize]) -> Result<(), io::Error> {
+    // SAFETY: cpu_set_t is plain data
+    let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+    for &cpu in cpus {
+        // CPU_SET indexes the set without checking the cpu
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid cpu {}", cpu),
+            ));
+        }
+
+        // SAFETY: cpu is within the set
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+
+    // SAFETY: set is valid
+    let ret = unsafe { libc::sched_setaffinity(0, mem::size_of_val(&set), &set) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+// pins the calling thread to the cpu
+pub fn pin_to_cpu(cpu: usize) -> Result<(), io::Error> {
+    set_affinity(&[cpu])
+}
+
+// returns the cpus that the calling thread may run on
+pub fn affinity() -> Result<Vec<usize>, io::Error> {
+    // SAFETY: cpu_set_t is plain data
+    let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+
+    // SAFETY: set is valid
+    let ret = unsafe { libc::sched_getaffinity(0, mem::size_of_val(&set), &mut set) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok((0..libc::CPU_SETSIZE as usize)
+        // SAFETY: cpu is within the set
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect())
+}
+
+// ioprio_set classes and encoding, not in libc yet
+const IOPRIO_CLASS_RT: i32 = 1;
+const IOPRIO_CLASS_BE: i32 = 2;
+const IOPRIO_CLASS_IDLE: i32 = 3;
+const IOPRIO_CLASS_SHIFT: i32 = 13;
+const IOPRIO_WHO_PROCESS: i32 = 1;
+
+// an io priority, in the classes of ionice
+#[derive(Clone, Copy)]
+pub enum IoPrio {
+    // levels 0 (highest) to 7, needs CAP_SYS_ADMIN
+    RealTime(u8),
+    // levels 0 (highest) to 7
+    BestEffort(u8),
+    // only served when the disk is otherwise idle
+    Idle,
+}
+
+impl IoPrio {
+    // parses "idle", "be", "be:LEVEL", "rt", or "rt:LEVEL"; the level defaults to 4
+    pub fn parse(s: &str) -> Result<Self, io::Error> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid io priority {}", s),
+            )
+        };
+
+        if s == "idle" {
+            return Ok(IoPrio::Idle);
+        }
+
+        let (class, level) = match s.split_once(':') {
+            Some((class, level)) => (
+                class,
+                level
+                    .parse()
+                    .ok()
+                    .filter(|&level| level <= 7)
+                    .ok_or_else(invalid)?,
+            ),
+            None => (s, 4),
+        };
+
+        match class {
+            "rt" => Ok(IoPrio::RealTime(level)),
+            "be" => Ok(IoPrio::BestEffort(level)),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn raw(&self) -> i32 {
+        let (class, level) = match self {
+            IoPrio::RealTime(level) => (IOPRIO_CLASS_RT, *level),
+            IoPrio::BestEffort(level) => (IOPRIO_CLASS_BE, *level),
+            IoPrio::Idle => (IOPRIO_CLASS_IDLE, 0),
+        };
+
+        (class << IOPRIO_CLASS_SHIFT) | level as i32
+    }
+}
+
+impl fmt::Display for IoPrio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            IoPrio::RealTime(level) => write!(f, "rt:{}", level),
+            IoPrio::BestEffort(level) => write!(f, "be:{}", level),
+            IoPrio::Idle => f.write_str("idle"),
+        }
+    }
+}
+
+// sets the nice value of the calling thread, which the threads and processes it creates
+// afterward inherit
+pub fn set_nice(nice: i32) -> Result<(), io::Error> {
+    // SAFETY: valid gettid call
+    let tid = unsafe { libc::gettid() };
+
+    // SAFETY: valid setpriority call
+    let ret = unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) };
+    if ret != 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::PermissionDenied {
+            return Err(io::Error::new(
+                err.kind(),
+                format!("nice {} needs CAP_SYS_NICE", nice),
+            ));
+        }
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+// sets the nice value and the io priority of the calling thread, leaving the ones that are None
+// unchanged
+pub fn set_priority(nice: Option<i32>, ioprio: Option<IoPrio>) -> Result<(), io::Error> {
+    if let Some(nice) = nice {
+        set_nice(nice)?;
+    }
+    if let Some(prio) = ioprio {
+        set_ioprio(prio)?;
+    }
+
+    Ok(())
+}
+
+// sets the io priority of the calling thread, which the threads and processes it creates
+// afterward inherit
+pub fn set_ioprio(prio: IoPrio) -> Result<(), io::Error> {
+    // SAFETY: valid gettid call
+    let tid = unsafe { libc::gettid() };
+
+    // SAFETY: valid ioprio_set call
+    let ret = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, prio.raw()) };
+    if ret != 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::PermissionDenied {
+            return Err(io::Error::new(
+                err.kind(),
+                format!("io priority {} needs CAP_SYS_ADMIN", prio),
+            ));
+        }
+        return Err(err);
+    }
+
+    Ok(())
+}
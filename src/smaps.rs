@@ -0,0 +1,161 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    fs,
+    io::{self, BufRead},
+};
+
+// size fields of a vma in smaps, or of all vmas in smaps_rollup, in KB
+#[derive(Clone, Copy, Default)]
+pub struct SmapsUsage {
+    pub size: u64,
+    pub rss: u64,
+    pub pss: u64,
+    pub pss_anon: u64,
+    pub pss_file: u64,
+    pub pss_shmem: u64,
+    pub shared_clean: u64,
+    pub shared_dirty: u64,
+    pub private_clean: u64,
+    pub private_dirty: u64,
+    pub anonymous: u64,
+    pub anon_huge_pages: u64,
+    pub swap: u64,
+    pub swap_pss: u64,
+    pub locked: u64,
+}
+
+impl SmapsUsage {
+    // updates the field of the key, if known
+    fn update(&mut self, key: &str, val: &str) {
+        let field = match key {
+            "Size" => &mut self.size,
+            "Rss" => &mut self.rss,
+            "Pss" => &mut self.pss,
+            "Pss_Anon" => &mut self.pss_anon,
+            "Pss_File" => &mut self.pss_file,
+            "Pss_Shmem" => &mut self.pss_shmem,
+            "Shared_Clean" => &mut self.shared_clean,
+            "Shared_Dirty" => &mut self.shared_dirty,
+            "Private_Clean" => &mut self.private_clean,
+            "Private_Dirty" => &mut self.private_dirty,
+            "Anonymous" => &mut self.anonymous,
+            "AnonHugePages" => &mut self.anon_huge_pages,
+            "Swap" => &mut self.swap,
+            "SwapPss" => &mut self.swap_pss,
+            "Locked" => &mut self.locked,
+            _ => return,
+        };
+
+        *field = val
+            .split_ascii_whitespace()
+            .next()
+            .and_then(|val| val.parse().ok())
+            .unwrap_or_default();
+    }
+
+    // unique set size, the memory that would be freed if the process exited
+    pub fn uss(&self) -> u64 {
+        self.private_clean + self.private_dirty
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut usage = SmapsUsage::default();
+        for line in text.lines() {
+            if let Some((key, val)) = line.split_once(':') {
+                usage.update(key, val);
+            }
+        }
+
+        usage
+    }
+}
+
+// a vma in smaps
+pub struct Vma {
+    pub start: usize,
+    pub end: usize,
+    pub perms: String,
+    pub offset: u64,
+    pub inode: u64,
+    // the path, a pseudo name such as "[heap]", or empty
+    pub name: String,
+    pub usage: SmapsUsage,
+    // two-letter VmFlags such as "rd" and "lo"
+    pub flags: Vec<String>,
+}
+
+impl Vma {
+    // header is in the form of "start-end perms offset dev inode [name]"
+    fn parse_header(header: &str) -> Option<Self> {
+        let mut tokens = header.splitn(6, ' ');
+        let (start, end) = tokens.next()?.split_once('-')?;
+        let perms = tokens.next()?.to_string();
+        let offset = tokens.next()?;
+        let inode = tokens.nth(1)?;
+        let name = tokens.next().unwrap_or_default().trim_start().to_string();
+
+        Some(Vma {
+            start: usize::from_str_radix(start, 16).ok()?,
+            end: usize::from_str_radix(end, 16).ok()?,
+            perms,
+            offset: u64::from_str_radix(offset, 16).ok()?,
+            inode: inode.parse().ok()?,
+            name,
+            usage: SmapsUsage::default(),
+            flags: Vec::new(),
+        })
+    }
+
+    pub fn is_anon(&self) -> bool {
+        self.inode == 0
+    }
+
+    // shmem is backed by an inode but is not a regular file, such as memfd and SysV shm
+    pub fn is_shmem(&self) -> bool {
+        self.inode != 0
+            && (self.name.starts_with("/memfd:")
+                || self.name.starts_with("/SYSV")
+                || self.name.starts_with("/dev/shm/")
+                || self.name.starts_with("/dev/zero"))
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.inode != 0 && !self.is_shmem()
+    }
+}
+
+// returns the vmas of the process
+pub fn smaps(pid: &str) -> Result<Vec<Vma>, io::Error> {
+    let fp = fs::File::open(format!("/proc/{}/smaps", pid))?;
+    let reader = io::BufReader::new(fp);
+
+    let mut vmas: Vec<Vma> = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+
+        // field keys have no spaces, while headers do
+        let Some((key, val)) = line.split_once(':').filter(|(key, _)| !key.contains(' ')) else {
+            vmas.extend(Vma::parse_header(&line));
+            continue;
+        };
+        let Some(vma) = vmas.last_mut() else {
+            continue;
+        };
+
+        if key == "VmFlags" {
+            vma.flags = val.split_ascii_whitespace().map(String::from).collect();
+        } else {
+            vma.usage.update(key, val);
+        }
+    }
+
+    Ok(vmas)
+}
+
+// returns the usage of all vmas of the process combined
+pub fn smaps_rollup(pid: &str) -> Result<SmapsUsage, io::Error> {
+    let rollup = fs::read_to_string(format!("/proc/{}/smaps_rollup", pid))?;
+    Ok(SmapsUsage::parse(&rollup))
+}
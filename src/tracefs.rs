@@ -0,0 +1,177 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use std::{
+    fs,
+    io::{self, Read},
+    mem,
+    os::unix::fs::OpenOptionsExt,
+    path, process, time,
+};
+
+const TRACEFS_DIRS: [&str; 2] = ["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+
+// mm tracepoints that fire at most a few times per reclaim or compaction pass
+pub const MM_TRACEPOINTS: [&str; 10] = [
+    "vmscan/mm_vmscan_kswapd_wake",
+    "vmscan/mm_vmscan_kswapd_sleep",
+    "vmscan/mm_vmscan_direct_reclaim_begin",
+    "vmscan/mm_vmscan_direct_reclaim_end",
+    "compaction/mm_compaction_kcompactd_wake",
+    "compaction/mm_compaction_begin",
+    "compaction/mm_compaction_end",
+    "oom/mark_victim",
+    "oom/wake_reaper",
+    "oom/finish_task_reaping",
+];
+
+// an event read from the trace buffer
+pub struct TraceEvent {
+    // CLOCK_MONOTONIC time of the event, on the same clock as time::Instant
+    pub time: time::Duration,
+    pub name: String,
+    pub msg: String,
+}
+
+impl TraceEvent {
+    // parses a trace_pipe line such as
+    // "kswapd0-90 [001] ..... 1234.567890: mm_vmscan_kswapd_wake: nid=0 order=0"
+    fn parse(line: &str) -> Option<Self> {
+        let (head, rest) = line.split_once(": ")?;
+        let secs = head.split_ascii_whitespace().last()?.parse::<f64>().ok()?;
+        let (name, msg) = rest.split_once(": ").unwrap_or((rest, ""));
+
+        Some(TraceEvent {
+            time: time::Duration::try_from_secs_f64(secs).ok()?,
+            name: name.to_string(),
+            msg: msg.to_string(),
+        })
+    }
+
+    // returns how long ago the event happened
+    pub fn age(&self) -> time::Duration {
+        // SAFETY: timespec is plain data
+        let mut now: libc::timespec = unsafe { mem::zeroed() };
+        // SAFETY: now is valid
+        unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+
+        let now = time::Duration::new(now.tv_sec as u64, now.tv_nsec as u32);
+        now.saturating_sub(self.time)
+    }
+}
+
+// tracepoints enabled in a tracefs instance of our own, so that the global trace buffer and
+// other tracers are left alone
+//
+// The instance is removed on drop, which disables the tracepoints.  Instances that outlive
+// their processes, such as after SIGKILL, are removed by the next open.
+pub struct Tracefs {
+    dir: path::PathBuf,
+    // None only while dropping, as the instance cannot be removed while its pipe is open
+    pipe: Option<fs::File>,
+    // a partial line left by the last read
+    partial: String,
+}
+
+impl Tracefs {
+    // removes the instances whose processes are gone, or whose pid has been reused by us
+    //
+    // An instance that is still in use cannot be removed and is skipped.
+    fn remove_stale(instances: &path::Path) {
+        let Ok(entries) = fs::read_dir(instances) else {
+            return;
+        };
+
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name();
+            let Some(pid) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("rustest-"))
+                .and_then(|pid| pid.parse::<u32>().ok())
+            else {
+                continue;
+            };
+
+            if pid == process::id() || !path::Path::new("/proc").join(pid.to_string()).exists() {
+                let _ = fs::remove_dir(entry.path());
+            }
+        }
+    }
+
+    // enables the tracepoints, given as "SYSTEM/EVENT", that the kernel has; fails when it has
+    // none of them
+    pub fn open(tracepoints: &[&str]) -> Result<Self, io::Error> {
+        let root = TRACEFS_DIRS
+            .iter()
+            .map(path::Path::new)
+            .find(|dir| dir.join("instances").is_dir())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "tracefs is not mounted"))?;
+        let instances = root.join("instances");
+        Self::remove_stale(&instances);
+
+        let dir = instances.join(format!("rustest-{}", process::id()));
+        fs::create_dir(&dir)?;
+
+        let mut trace = Tracefs {
+            dir,
+            pipe: None,
+            partial: String::new(),
+        };
+
+        // timestamps on the clock of time::Instant
+        fs::write(trace.dir.join("trace_clock"), "mono")?;
+
+        let mut enabled = 0;
+        for tracepoint in tracepoints {
+            let enable = trace.dir.join("events").join(tracepoint).join("enable");
+            if fs::write(enable, "1").is_ok() {
+                enabled += 1;
+            }
+        }
+        if enabled == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "none of the tracepoints are available",
+            ));
+        }
+
+        trace.pipe = Some(
+            fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(trace.dir.join("trace_pipe"))?,
+        );
+
+        Ok(trace)
+    }
+
+    // returns the events logged since the last read
+    pub fn read(&mut self) -> Vec<TraceEvent> {
+        let Some(pipe) = &mut self.pipe else {
+            return Vec::new();
+        };
+
+        let mut buf = vec![0; 65536];
+        loop {
+            match pipe.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => self.partial.push_str(&String::from_utf8_lossy(&buf[..len])),
+                Err(_) => break,
+            }
+        }
+
+        let Some(end) = self.partial.rfind('\n') else {
+            return Vec::new();
+        };
+        let lines: String = self.partial.drain(..=end).collect();
+
+        lines.lines().filter_map(TraceEvent::parse).collect()
+    }
+}
+
+impl Drop for Tracefs {
+    fn drop(&mut self) {
+        self.pipe = None;
+        let _ = fs::remove_dir(&self.dir);
+    }
+}
@@ -0,0 +1,317 @@
+// Copyright 2025 Google LLC
+// SPDX-License-Identifier: MIT
+
+use crate::Mmap;
+use std::{
+    fs, io, mem, ops,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    ptr,
+    sync::atomic,
+};
+
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x8000000;
+const IORING_OFF_SQES: libc::off_t = 0x10000000;
+
+const IORING_ENTER_GETEVENTS: u32 = 1;
+const IORING_REGISTER_BUFFERS: u32 = 0;
+const IORING_OP_READ_FIXED: u8 = 4;
+
+// the structs below mirror the kernel uapi
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct SqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct CqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SqringOffsets,
+    cq_off: CqringOffsets,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct Sqe {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    rw_flags: u32,
+    user_data: u64,
+    buf_index: u16,
+    personality: u16,
+    splice_fd_in: i32,
+    addr3: u64,
+    pad: u64,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+struct Cqe {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+// an io_uring instance with one registered buffer per submission queue entry
+pub struct Uring {
+    fd: OwnedFd,
+    sq_ring: Mmap,
+    cq_ring: Mmap,
+    sqes: Mmap,
+    sq_off: SqringOffsets,
+    cq_off: CqringOffsets,
+    bufs: Mmap,
+    buf_size: usize,
+    buf_count: usize,
+}
+
+impl Uring {
+    pub fn new(depth: u32, buf_size: usize) -> Result<Self, io::Error> {
+        let mut params = Params::default();
+
+        // SAFETY: params is valid for the syscall
+        let ret =
+            unsafe { libc::syscall(libc::SYS_io_uring_setup, depth, &mut params as *mut Params) };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: io_uring_setup returned a new fd that we now own
+        let fd = unsafe { OwnedFd::from_raw_fd(ret as _) };
+        let raw_fd = fd.as_raw_fd();
+
+        let sq_off = params.sq_off;
+        let cq_off = params.cq_off;
+        let prot = libc::PROT_READ | libc::PROT_WRITE;
+        let flags = libc::MAP_SHARED | libc::MAP_POPULATE;
+
+        let sq_len = sq_off.array as usize + params.sq_entries as usize * mem::size_of::<u32>();
+        let sq_ring = Mmap::mmap_raw_at(sq_len, prot, flags, raw_fd, IORING_OFF_SQ_RING)?;
+        let cq_len = cq_off.cqes as usize + params.cq_entries as usize * mem::size_of::<Cqe>();
+        let cq_ring = Mmap::mmap_raw_at(cq_len, prot, flags, raw_fd, IORING_OFF_CQ_RING)?;
+        let sqes_len = params.sq_entries as usize * mem::size_of::<Sqe>();
+        let sqes = Mmap::mmap_raw_at(sqes_len, prot, flags, raw_fd, IORING_OFF_SQES)?;
+
+        let buf_count = params.sq_entries as usize;
+        let bufs = Mmap::anonymous(buf_count * buf_size)?;
+        let iovecs: Vec<_> = (0..buf_count)
+            .map(|idx| libc::iovec {
+                iov_base: bufs.addr.wrapping_byte_add(idx * buf_size),
+                iov_len: buf_size,
+            })
+            .collect();
+
+        // SAFETY: iovecs is valid for buf_count entries
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_register,
+                raw_fd,
+                IORING_REGISTER_BUFFERS,
+                iovecs.as_ptr(),
+                buf_count as u32,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Uring {
+            fd,
+            sq_ring,
+            cq_ring,
+            sqes,
+            sq_off,
+            cq_off,
+            bufs,
+            buf_size,
+            buf_count,
+        })
+    }
+
+    fn ring_u32(ring: &Mmap, offset: usize) -> &atomic::AtomicU32 {
+        let ptr = ring.addr.wrapping_byte_add(offset) as *const atomic::AtomicU32;
+
+        // SAFETY: the kernel places aligned u32s at the offsets within the ring
+        unsafe { &*ptr }
+    }
+
+    fn push(&self, sqe: Sqe) {
+        let mask = Self::ring_u32(&self.sq_ring, self.sq_off.ring_mask as _)
+            .load(atomic::Ordering::Relaxed);
+        let tail = Self::ring_u32(&self.sq_ring, self.sq_off.tail as _);
+
+        // we are the only producer
+        let cur = tail.load(atomic::Ordering::Relaxed);
+        let idx = (cur & mask) as usize;
+
+        let ptr = self
+            .sqes
+            .addr
+            .wrapping_byte_add(idx * mem::size_of::<Sqe>()) as *mut Sqe;
+        // SAFETY: idx is within the sqes array
+        unsafe { ptr::write(ptr, sqe) };
+
+        let array = self.sq_off.array as usize + idx * mem::size_of::<u32>();
+        Self::ring_u32(&self.sq_ring, array).store(idx as u32, atomic::Ordering::Relaxed);
+
+        tail.store(cur.wrapping_add(1), atomic::Ordering::Release);
+    }
+
+    // takes back the last count entries pushed, which the kernel has not consumed
+    fn unpush(&self, count: u32) {
+        let tail = Self::ring_u32(&self.sq_ring, self.sq_off.tail as _);
+        let cur = tail.load(atomic::Ordering::Relaxed);
+        tail.store(cur.wrapping_sub(count), atomic::Ordering::Release);
+    }
+
+    fn pop(&self) -> Option<Cqe> {
+        let mask = Self::ring_u32(&self.cq_ring, self.cq_off.ring_mask as _)
+            .load(atomic::Ordering::Relaxed);
+        let head = Self::ring_u32(&self.cq_ring, self.cq_off.head as _);
+        let tail = Self::ring_u32(&self.cq_ring, self.cq_off.tail as _);
+
+        let cur = head.load(atomic::Ordering::Relaxed);
+        if cur == tail.load(atomic::Ordering::Acquire) {
+            return None;
+        }
+
+        let idx = (cur & mask) as usize;
+        let offset = self.cq_off.cqes as usize + idx * mem::size_of::<Cqe>();
+        let ptr = self.cq_ring.addr.wrapping_byte_add(offset) as *const Cqe;
+        // SAFETY: idx is within the cqes array
+        let cqe = unsafe { ptr::read(ptr) };
+
+        head.store(cur.wrapping_add(1), atomic::Ordering::Release);
+
+        Some(cqe)
+    }
+
+    // returns the number of entries that the kernel consumed, which can be fewer than to_submit
+    fn enter(&self, to_submit: u32, min_complete: u32) -> Result<u32, io::Error> {
+        // SAFETY: all args are valid
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_enter,
+                self.fd.as_raw_fd(),
+                to_submit,
+                min_complete,
+                IORING_ENTER_GETEVENTS,
+                ptr::null::<libc::sigset_t>(),
+                0,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(ret as u32)
+    }
+
+    // reads the byte range of the file into the registered buffers and returns the number of
+    // bytes read; short reads are not retried
+    //
+    // On a failed read, the reads in flight are still waited for, so that none of them
+    // completes into a later call.
+    pub fn read(&mut self, file: &fs::File, range: ops::Range<u64>) -> Result<u64, io::Error> {
+        let mut free: Vec<usize> = (0..self.buf_count).collect();
+        let mut offset = range.start;
+        // entries in the submission queue that the kernel has not consumed yet
+        let mut queued = 0;
+        let mut inflight = 0;
+        let mut total = 0;
+        // the first failed read; no more are queued after it
+        let mut err = None;
+
+        loop {
+            while err.is_none() && offset < range.end {
+                let Some(buf) = free.pop() else {
+                    break;
+                };
+                let len = (range.end - offset).min(self.buf_size as u64);
+
+                self.push(Sqe {
+                    opcode: IORING_OP_READ_FIXED,
+                    fd: file.as_raw_fd(),
+                    off: offset,
+                    addr: self.bufs.addr.wrapping_byte_add(buf * self.buf_size) as u64,
+                    len: len as u32,
+                    user_data: buf as u64,
+                    buf_index: buf as u16,
+                    ..Default::default()
+                });
+
+                offset += len;
+                queued += 1;
+            }
+
+            if queued == 0 && inflight == 0 {
+                break;
+            }
+
+            // the kernel does not wait for completions when it consumes only some entries
+            let submitted = self.enter(queued, 1)?;
+            if submitted == 0 && inflight == 0 {
+                self.unpush(queued);
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            queued -= submitted;
+            inflight += submitted;
+
+            while let Some(cqe) = self.pop() {
+                inflight -= 1;
+                free.push(cqe.user_data as usize);
+
+                if cqe.res < 0 {
+                    err.get_or_insert(io::Error::from_raw_os_error(-cqe.res));
+                } else {
+                    total += cqe.res as u64;
+                }
+            }
+        }
+
+        match err {
+            Some(err) => Err(err),
+            None => Ok(total),
+        }
+    }
+}